@@ -1,6 +1,6 @@
 pub use self::events::{
     DialogueCompleteEvent, DialogueStartEvent, ExecuteCommandEvent, LineHintsEvent,
-    NodeCompleteEvent, NodeStartEvent, PresentLineEvent, PresentOptionsEvent,
+    NodeCompleteEvent, NodeStartEvent, PresentLineEvent, PresentOptionsEvent, VariableChangedEvent,
 };
 pub use self::{
     builder::DialogueRunnerBuilder,
@@ -55,6 +55,7 @@ pub struct DialogueRunner {
     localizations: Option<Localizations>,
     pub(crate) is_running: bool,
     run_selected_options_as_lines: bool,
+    pub(crate) skip_seen_lines: bool,
     pub(crate) just_started: bool,
     pub(crate) popped_line_hints: Option<Vec<LineId>>,
     pub(crate) unsent_events: Vec<DialogueEvent>,
@@ -134,6 +135,40 @@ impl DialogueRunner {
         self.run_selected_options_as_lines
     }
 
+    /// If set, every line that was presented before is automatically continued in the next update, as if [`DialogueRunner::continue_in_next_update`] was called.
+    /// The dialogue still stops at lines that are presented for the first time, at options and at commands that have not finished yet.
+    /// Lines are only considered seen if a [`SeenLineTracker`] was set with [`DialogueRunner::set_seen_line_tracker`]. Defaults to `false`.
+    ///
+    /// A [`PresentLineEvent`] is still sent for every skipped line, e.g. so that it can be flashed on screen or added to a backlog.
+    pub fn skip_seen_lines(&mut self, skip_seen_lines: bool) -> &mut Self {
+        self.skip_seen_lines = skip_seen_lines;
+        self
+    }
+
+    /// If set, every line that was presented before is automatically continued in the next update. Defaults to `false`.
+    /// See [`DialogueRunner::skip_seen_lines`].
+    #[must_use]
+    pub fn skips_seen_lines(&self) -> bool {
+        self.skip_seen_lines
+    }
+
+    /// Returns the [`SeenLineTracker`] that records the presented lines, if one was set with [`DialogueRunner::set_seen_line_tracker`].
+    #[must_use]
+    pub fn seen_line_tracker(&self) -> Option<&SeenLineTracker> {
+        self.inner().0.seen_line_tracker()
+    }
+
+    /// Sets the [`SeenLineTracker`] that records every line presented through a [`PresentLineEvent`]. `None` stops recording. Defaults to `None`.
+    /// Since clones of a tracker share their state, the same tracker can be passed to several dialogue runners and persisted independently of any save slot.
+    /// See [`Dialogue::set_seen_line_tracker`].
+    pub fn set_seen_line_tracker(
+        &mut self,
+        seen_line_tracker: impl Into<Option<SeenLineTracker>>,
+    ) -> &mut Self {
+        self.inner_mut().0.set_seen_line_tracker(seen_line_tracker);
+        self
+    }
+
     /// Stops the execution of the dialogue. Any pending dialogue events will still be sent in the next update, including a [`DialogueCompleteEvent`].
     /// After this, [`DialogueRunner::start_node`] must be called before the dialogue can be advanced again.
     pub fn stop(&mut self) -> &mut Self {
@@ -200,6 +235,87 @@ impl DialogueRunner {
         self.inner().0.current_node()
     }
 
+    /// Gets the names of the nodes that are waiting for a `<<detour>>` to return, with the node that detoured to [`DialogueRunner::current_node`] last.
+    #[must_use]
+    pub fn call_stack(&self) -> Vec<String> {
+        self.inner().0.call_stack()
+    }
+
+    /// Gets how many jumps away from the current node the [`LineHintsEvent`]s look for lines. Defaults to 0, which means that only the lines of the current node are hinted at.
+    #[must_use]
+    pub fn line_hints_lookahead(&self) -> usize {
+        self.inner().0.line_hints_lookahead()
+    }
+
+    /// Sets how many jumps away from the current node the [`LineHintsEvent`]s look for lines, so that the registered [`AssetProvider`]s can start loading
+    /// the assets of a node before it is entered. Note that a [`DialogueRunner`] waits for the assets of all hinted lines before presenting the next line,
+    /// so a large lookahead can delay the start of a node. See [`Dialogue::set_line_hints_lookahead`].
+    pub fn set_line_hints_lookahead(&mut self, lookahead: usize) -> &mut Self {
+        self.inner_mut().0.set_line_hints_lookahead(lookahead);
+        self
+    }
+
+    /// Gets the maximum number of instructions the [`DialogueRunner`] runs in one go before it gives up on a dialogue that loops without delivering content.
+    /// Running out of instructions is treated like any other error of the underlying [`Dialogue`]. See [`Dialogue::instruction_budget`].
+    #[must_use]
+    pub fn instruction_budget(&self) -> Option<usize> {
+        self.inner().0.instruction_budget()
+    }
+
+    /// Sets the maximum number of instructions the [`DialogueRunner`] runs in one go. `None` means there is no limit, which is the default.
+    /// See [`Dialogue::set_instruction_budget`].
+    pub fn set_instruction_budget(
+        &mut self,
+        instruction_budget: impl Into<Option<usize>>,
+    ) -> &mut Self {
+        self.inner_mut()
+            .0
+            .set_instruction_budget(instruction_budget);
+        self
+    }
+
+    /// Gets how many of the most recently presented lines [`DialogueRunner::rewind`] can return to. Defaults to 0, which disables rewinding.
+    #[must_use]
+    pub fn rewind_depth(&self) -> usize {
+        self.inner().0.rewind_depth()
+    }
+
+    /// Sets how many of the most recently presented lines [`DialogueRunner::rewind`] can return to. See [`Dialogue::set_rewind_depth`].
+    pub fn set_rewind_depth(&mut self, rewind_depth: usize) -> &mut Self {
+        self.inner_mut().0.set_rewind_depth(rewind_depth);
+        self
+    }
+
+    /// Returns to the moment the line `steps` lines before the last presented one was presented, e.g. `1` to go back one line.
+    /// The execution state and the values of all variables are restored to what they were at that moment.
+    ///
+    /// The line is presented again through a [`PresentLineEvent`] in the next update, preceded by a [`VariableChangedEvent`] for every variable whose value changed back.
+    /// This also works after the dialogue was stopped or completed, as long as [`DialogueRunner::start_node`] was not called since. See [`Dialogue::rewind`].
+    pub fn rewind(&mut self, steps: usize) -> Result<&mut Self> {
+        let events = self.inner_mut().0.rewind(steps)?;
+        self.is_running = true;
+        self.last_selected_option = None;
+        self.will_continue_in_next_update = false;
+        self.unsent_events.extend(events);
+        Ok(self)
+    }
+
+    /// Replaces the program while keeping the dialogue where it is, which is used when Yarn files are recompiled because they changed.
+    /// If the current position cannot be found in the new program, the current node is restarted in the next update and a warning is logged.
+    /// Returns an error and keeps the current program if the current node no longer exists. See [`Dialogue::hot_swap_program`].
+    pub fn hot_swap_program(&mut self, program: YarnProgram) -> Result<&mut Self> {
+        let events = self.inner_mut().0.hot_swap_program(program)?;
+        if events
+            .iter()
+            .any(|event| matches!(event, DialogueEvent::HotSwapFallback { .. }))
+        {
+            self.last_selected_option = None;
+            self.continue_in_next_update();
+        }
+        self.unsent_events.extend(events);
+        Ok(self)
+    }
+
     /// Returns a shallow clone of the registered [`VariableStorage`]. The storage used can be overridden by calling [`DialogueRunnerBuilder::with_variable_storage`].
     #[must_use]
     pub fn variable_storage(&self) -> &dyn VariableStorage {
@@ -212,6 +328,80 @@ impl DialogueRunner {
         self.inner_mut().0.variable_storage_mut()
     }
 
+    /// Sets a variable in the registered [`VariableStorage`]. If the value changed, a [`VariableChangedEvent`] is sent in the next update.
+    /// Prefer this over [`DialogueRunner::variable_storage_mut`] when anything observes [`VariableChangedEvent`]s.
+    pub fn set_variable(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<YarnValue>,
+    ) -> Result<&mut Self> {
+        let events = self.inner_mut().0.set_variable(name, value)?;
+        self.unsent_events.extend(events);
+        Ok(self)
+    }
+
+    /// Begins a transaction, so that all variable writes from now on can be undone with [`DialogueRunner::rollback_transaction`].
+    /// See [`Dialogue::begin_transaction`].
+    pub fn begin_transaction(&mut self) -> &mut Self {
+        self.inner_mut().0.begin_transaction();
+        self
+    }
+
+    /// Keeps all variable writes since [`DialogueRunner::begin_transaction`] and ends the transaction. See [`Dialogue::commit_transaction`].
+    pub fn commit_transaction(&mut self) -> &mut Self {
+        self.inner_mut().0.commit_transaction();
+        self
+    }
+
+    /// Undoes all variable writes since [`DialogueRunner::begin_transaction`] and ends the transaction.
+    /// A [`VariableChangedEvent`] is sent in the next update for every variable whose value changed back. See [`Dialogue::rollback_transaction`].
+    pub fn rollback_transaction(&mut self) -> Result<&mut Self> {
+        let events = self.inner_mut().0.rollback_transaction()?;
+        self.unsent_events.extend(events);
+        Ok(self)
+    }
+
+    /// Returns `true` if a transaction was begun and not yet committed or rolled back.
+    #[must_use]
+    pub fn is_in_transaction(&self) -> bool {
+        self.inner().0.is_in_transaction()
+    }
+
+    /// If set, [`DialogueRunner::stop`] undoes the variable writes of the current run, e.g. so that cancelling a trade halfway undoes it.
+    /// Defaults to `false`. See [`Dialogue::set_rollback_on_stop`].
+    pub fn rollback_on_stop(&mut self, rollback_on_stop: bool) -> &mut Self {
+        self.inner_mut().0.set_rollback_on_stop(rollback_on_stop);
+        self
+    }
+
+    /// If set, [`DialogueRunner::stop`] undoes the variable writes of the current run. Defaults to `false`.
+    #[must_use]
+    pub fn rolls_back_on_stop(&self) -> bool {
+        self.inner().0.rollback_on_stop()
+    }
+
+    /// Returns the [`DialogueHistory`] that records the presented lines and selected options, if recording was enabled with [`DialogueRunner::set_history`].
+    #[must_use]
+    pub fn history(&self) -> Option<&DialogueHistory> {
+        self.inner().0.history()
+    }
+
+    /// Mutably returns the [`DialogueHistory`], if recording was enabled with [`DialogueRunner::set_history`].
+    #[must_use]
+    pub fn history_mut(&mut self) -> Option<&mut DialogueHistory> {
+        self.inner_mut().0.history_mut()
+    }
+
+    /// Sets the [`DialogueHistory`] that records every line presented through a [`PresentLineEvent`] and every option selected
+    /// with [`DialogueRunner::select_option`], e.g. a new one or one restored from a save game. `None` stops recording. Defaults to `None`.
+    ///
+    /// When the text language changes, the history is re-localized as soon as the [`TextProvider`] has loaded the new language.
+    /// See [`Dialogue::set_history`].
+    pub fn set_history(&mut self, history: impl Into<Option<DialogueHistory>>) -> &mut Self {
+        self.inner_mut().0.set_history(history);
+        self
+    }
+
     /// Returns whether both the text and asset providers have loaded all their lines.
     #[must_use]
     pub fn update_line_availability(
@@ -10,7 +10,8 @@ pub(crate) fn dialogue_runner_events_plugin(app: &mut App) {
         .add_message::<NodeStartEvent>()
         .add_message::<LineHintsEvent>()
         .add_message::<DialogueCompleteEvent>()
-        .add_message::<DialogueStartEvent>();
+        .add_message::<DialogueStartEvent>()
+        .add_message::<VariableChangedEvent>();
 }
 
 /// An event that is fired after a dialogue advances and wishes to present a line to the user.
@@ -95,3 +96,18 @@ pub struct DialogueCompleteEvent {
     /// The [`DialogueRunner`] that has completed this dialogue.
     pub source: Entity,
 }
+
+/// An event that is fired after a variable has been set to a different value, either by the dialogue or by [`DialogueRunner::set_variable`].
+/// Changes made directly through [`DialogueRunner::variable_storage_mut`] are not observed.
+/// Handling this event is **optional** for dialogue views.
+#[derive(Debug, Clone, PartialEq, Message)]
+pub struct VariableChangedEvent {
+    /// The name of the variable, including the leading `$`.
+    pub name: String,
+    /// The value of the variable before the change, if it had one.
+    pub old: Option<YarnValue>,
+    /// The new value of the variable.
+    pub new: YarnValue,
+    /// The [`DialogueRunner`] whose variable changed.
+    pub source: Entity,
+}
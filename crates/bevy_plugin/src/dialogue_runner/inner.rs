@@ -31,6 +31,12 @@ impl InnerDialogue<'_> {
         self.0.analyse(context);
         self
     }
+
+    /// Proxy for [`Dialogue::rng`].
+    #[must_use]
+    pub fn rng(&self) -> &DialogueRng {
+        self.0.rng()
+    }
 }
 
 impl InnerDialogueMut<'_> {
@@ -57,4 +63,16 @@ impl InnerDialogueMut<'_> {
         self.0.analyse(context);
         self
     }
+
+    /// Proxy for [`Dialogue::rng`].
+    #[must_use]
+    pub fn rng(&self) -> &DialogueRng {
+        self.0.rng()
+    }
+
+    /// Proxy for [`Dialogue::rng_mut`].
+    #[must_use]
+    pub fn rng_mut(&mut self) -> &mut DialogueRng {
+        self.0.rng_mut()
+    }
 }
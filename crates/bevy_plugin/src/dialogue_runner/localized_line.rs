@@ -178,6 +178,8 @@ impl From<LocalizedLine> for YarnLine {
             id: line.id,
             text: line.text,
             attributes: line.attributes,
+            substitutions: Vec::new(),
+            values: Vec::new(),
         }
     }
 }
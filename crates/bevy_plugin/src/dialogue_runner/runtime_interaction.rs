@@ -13,6 +13,7 @@ pub(crate) fn runtime_interaction_plugin(app: &mut App) {
     app.add_systems(
         Update,
         (
+            relocalize_histories.pipe(log_error),
             continue_runtime
                 .pipe(panic_on_err)
                 .run_if(resource_exists::<YarnProject>),
@@ -133,6 +134,7 @@ fn continue_runtime(
         MessageWriter<NodeStartEvent>,
         MessageWriter<LineHintsEvent>,
         MessageWriter<DialogueCompleteEvent>,
+        MessageWriter<VariableChangedEvent>,
         Res<YarnProject>,
     )> = SystemState::new(world);
 
@@ -145,6 +147,7 @@ fn continue_runtime(
         mut node_start_events,
         mut line_hints_events,
         mut dialogue_complete_events,
+        mut variable_changed_events,
         project,
     ) = system_state.get_mut(world);
 
@@ -156,6 +159,16 @@ fn continue_runtime(
             for event in events {
                 match event {
                     DialogueEvent::Line(line) => {
+                        // The line was already recorded by the dialogue, so it was seen before if it was seen more than once
+                        let was_seen_before = dialogue_runner
+                            .seen_line_tracker()
+                            .is_some_and(|tracker| tracker.times_seen(&line.id) > 1);
+                        if dialogue_runner.skip_seen_lines
+                            && was_seen_before
+                            && !is_sending_missed_events
+                        {
+                            dialogue_runner.continue_in_next_update();
+                        }
                         let assets = dialogue_runner.get_assets(&line);
                         let metadata = project.line_metadata(&line.id).unwrap_or_default().to_vec();
                         present_line_events.write(PresentLineEvent {
@@ -191,6 +204,19 @@ fn continue_runtime(
                     DialogueEvent::LineHints(line_ids) => {
                         line_hints_events.write(LineHintsEvent { line_ids, source });
                     }
+                    DialogueEvent::VariableChanged { name, old, new } => {
+                        variable_changed_events.write(VariableChangedEvent {
+                            name,
+                            old,
+                            new,
+                            source,
+                        });
+                    }
+                    DialogueEvent::HotSwapFallback { node_name, reason } => {
+                        warn!(
+                            "Restarted node \"{node_name}\" after recompiling the Yarn files: {reason}"
+                        );
+                    }
                     DialogueEvent::DialogueComplete => {
                         if !is_sending_missed_events {
                             dialogue_runner.is_running = false;
@@ -204,6 +230,17 @@ fn continue_runtime(
     Ok(())
 }
 
+fn relocalize_histories(mut dialogue_runners: Query<&mut DialogueRunner>) -> SystemResult {
+    for mut dialogue_runner in dialogue_runners.iter_mut() {
+        if dialogue_runner.inner().0.is_history_outdated()
+            && dialogue_runner.text_provider().are_lines_available()
+        {
+            dialogue_runner.inner_mut().0.relocalize_history()?;
+        }
+    }
+    Ok(())
+}
+
 fn accept_line_hints(
     mut events: MessageReader<LineHintsEvent>,
     mut dialogue_runners: Query<&mut DialogueRunner>,
@@ -124,6 +124,7 @@ pub mod events {
     pub use crate::dialogue_runner::{
         DialogueCompleteEvent, DialogueStartEvent, ExecuteCommandEvent, LineHintsEvent,
         NodeCompleteEvent, NodeStartEvent, PresentLineEvent, PresentOptionsEvent,
+        VariableChangedEvent,
     };
 }
 
@@ -148,8 +149,9 @@ pub mod prelude {
     pub(crate) use serde::{Deserialize, Serialize};
     pub(crate) use yarnspinner::prelude::*;
     pub use yarnspinner::prelude::{
-        IntoYarnValueFromNonYarnValue, Language, LineId, MarkupAttribute, MarkupValue, OptionId,
-        VariableStorage, YarnFn, YarnLibrary, YarnValue,
+        DialogueHistory, DialogueRng, HistoryEntry, IntoYarnValueFromNonYarnValue, Language,
+        LineId, MarkupAttribute, MarkupValue, OptionId, SeenLineTracker, VariableStorage, YarnFn,
+        YarnLibrary, YarnValue,
     };
     pub(crate) type SystemResult = anyhow::Result<()>;
     pub(crate) use yarnspinner_internal_shared::prelude::*;
@@ -144,6 +144,7 @@ pub trait AssetProvider: Debug + Send + Sync {
     ) -> bool;
 
     /// Passes the [`LineId`]s that this [`AssetProvider`] should soon provide assets for. These are the [`LineId`]s that are contained in the current node and are not required to be actually reached.
+    /// They are ordered by priority, so assets should be loaded front to back. See [`DialogueRunner::set_line_hints_lookahead`] for hints about upcoming nodes.
     fn accept_line_hints(&mut self, line_ids: &[LineId]);
 
     /// Returns the [`LineAssets`] for the given [`UnderlyingYarnLine`]. Will only be called if [`AssetProvider::update_asset_availability`] returns `true`,
@@ -31,7 +31,7 @@ pub struct FileExtensionAssetProvider {
     asset_server: SkipDebug<Option<AssetServer>>,
     loading_handles: HashMap<PathBuf, Handle<LoadedUntypedAsset>>,
     loaded_handles: HashMap<PathBuf, UntypedHandle>,
-    line_ids: HashSet<LineId>,
+    line_ids: Vec<LineId>,
     file_extensions: HashMap<&'static str, Vec<String>>,
 }
 
@@ -145,23 +145,17 @@ fn recompile_loaded_yarn_files(
     yarn_project.metadata = metadata;
     let program = yarn_project.compilation.program.clone().unwrap();
     for mut dialogue_runner in dialogue_runners.iter_mut() {
-        let current_node = dialogue_runner.current_node();
-        dialogue_runner
-            .inner_mut()
-            .0
-            .replace_program(program.clone());
         dialogue_runner
             .text_provider
             .set_base_string_table(yarn_project.compilation.string_table.clone());
-        if let Some(current_node) = current_node {
+        if let Err(e) = dialogue_runner.hot_swap_program(program.clone()) {
+            // The current node was removed, so there is nothing to continue from
+            warn!("Restarting dialogue at node \"Start\" after recompiling the Yarn files: {e}");
             dialogue_runner
-                .stop()
-                .try_start_node(current_node)
-                .map(|_| ())
-                .ok()
-                .unwrap_or_else(|| {
-                    dialogue_runner.start_node("Start");
-                });
+                .inner_mut()
+                .0
+                .replace_program(program.clone());
+            dialogue_runner.stop().start_node("Start");
         }
     }
     events.clear();
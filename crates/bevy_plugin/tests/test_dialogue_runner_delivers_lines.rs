@@ -133,6 +133,32 @@ fn presents_all_lines() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn skips_seen_lines() -> Result<()> {
+    let mut app = App::new();
+    let mut asserter = EventAsserter::new();
+    let tracker = SeenLineTracker::new();
+    setup_dialogue_runner_without_localizations(&mut app)
+        .set_seen_line_tracker(tracker.clone())
+        .skip_seen_lines(true)
+        .start_node("Start");
+    app.update();
+    app.update();
+    assert_events!(asserter, app contains PresentLineEvent with |event| event.line.text == english_lines()[0]);
+
+    app.continue_dialogue_and_update_n_times(12);
+    assert!(!app.dialogue_runner().is_running());
+    assert_eq!(12, tracker.seen_lines().len());
+    asserter.clear_events(&mut app);
+
+    app.dialogue_runner_mut().start_node("Start");
+    for _ in 0..13 {
+        app.update();
+    }
+    assert!(!app.dialogue_runner().is_running());
+    Ok(())
+}
+
 #[test]
 #[should_panic]
 fn panics_on_continue_after_all_lines() {
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use bevy::prelude::*;
+use bevy_yarnspinner::{events::*, prelude::*};
+use utils::prelude::*;
+
+mod utils;
+
+#[test]
+fn sends_variable_changes_of_dialogue() -> Result<()> {
+    let mut app = App::new();
+    let mut asserter = EventAsserter::new();
+    setup_dialogue_runner(&mut app).start_node("Start");
+    app.update();
+    assert_events!(asserter, app contains [
+        // Setting a variable to the value it already has is not a change
+        VariableChangedEvent (n = 1) with |event| event.name == "$gold"
+            && event.old == Some(YarnValue::Number(0.0))
+            && event.new == YarnValue::Number(10.0),
+        PresentLineEvent with |event| event.line.text == "Gold: 10",
+    ]);
+
+    Ok(())
+}
+
+#[test]
+fn sends_variable_changes_of_runner() -> Result<()> {
+    let mut app = App::new();
+    let mut asserter = EventAsserter::new();
+    setup_dialogue_runner(&mut app).set_variable("$gold", 5)?;
+    app.update();
+    assert_events!(asserter, app contains [
+        VariableChangedEvent with |event| event.name == "$gold"
+            && event.old == Some(YarnValue::Number(0.0))
+            && event.new == YarnValue::Number(5.0),
+    ]);
+
+    app.dialogue_runner_mut().set_variable("$gold", 5)?;
+    app.update();
+    assert_events!(asserter, app contains [
+        VariableChangedEvent (n = 0),
+    ]);
+
+    Ok(())
+}
+
+fn setup_dialogue_runner(app: &mut App) -> Mut<'_, DialogueRunner> {
+    app.setup_default_plugins()
+        .add_plugins(YarnSpinnerPlugin::with_yarn_source(YarnFileSource::file(
+            "variables.yarn",
+        )))
+        .dialogue_runner_mut()
+}
@@ -12,6 +12,7 @@ pub struct EventAsserter {
     pub node_complete_cursor: MessageCursor<NodeCompleteEvent>,
     pub line_hints_cursor: MessageCursor<LineHintsEvent>,
     pub execute_command_cursor: MessageCursor<ExecuteCommandEvent>,
+    pub variable_changed_cursor: MessageCursor<VariableChangedEvent>,
 }
 
 impl EventAsserter {
@@ -36,6 +37,8 @@ impl EventAsserter {
             .clear(app.world().resource::<Messages<LineHintsEvent>>());
         self.execute_command_cursor
             .clear(app.world().resource::<Messages<ExecuteCommandEvent>>());
+        self.variable_changed_cursor
+            .clear(app.world().resource::<Messages<VariableChangedEvent>>());
     }
 }
 
@@ -65,6 +68,9 @@ macro_rules! get_cursor {
     ($asserter:ident, ExecuteCommandEvent) => {
         &mut $asserter.execute_command_cursor
     };
+    ($asserter:ident, VariableChangedEvent) => {
+        &mut $asserter.variable_changed_cursor
+    };
 }
 
 #[macro_export]
@@ -165,6 +165,8 @@ impl DialogueRunnerExt for DialogueRunner {
             id: LineId(line_id.to_string()),
             text: String::new(),
             attributes: vec![],
+            substitutions: vec![],
+            values: vec![],
         };
         self.asset_providers()
             .map(|p| p.get_assets(&line_id))
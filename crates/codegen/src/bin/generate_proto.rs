@@ -15,6 +15,9 @@ fn main() -> Result<()> {
     // Use BTreeMap instead of HashMap for no-std compatibility
     config.btree_map(["."]);
 
+    // The op codes are extended beyond the ones in the protobuf definition, see `crates/core/src/op_code.rs`
+    config.extern_path(".Yarn.Instruction.OpCode", "crate::op_code::OpCode");
+
     config
         .type_attribute(
             ".",
@@ -1,4 +1,5 @@
 mod add_initial_value_registrations;
+mod add_once_declarations;
 mod add_tracking_declarations;
 mod check_types;
 mod clean_up_diagnostics;
@@ -6,7 +7,10 @@ mod create_declarations_for_tracking_nodes;
 mod early_breaks;
 mod find_tracking_nodes;
 mod generate_code;
+mod generate_node_groups;
+mod generate_smart_variable_nodes;
 mod get_declarations;
+mod get_enums;
 mod parse_files;
 mod register_initial_variables;
 mod register_strings;
@@ -14,9 +18,10 @@ mod resolve_deferred_type_diagnostic;
 mod validate_unique_node_names;
 
 pub(crate) use self::{
-    add_initial_value_registrations::*, add_tracking_declarations::*, check_types::*,
-    clean_up_diagnostics::*, create_declarations_for_tracking_nodes::*, early_breaks::*,
-    find_tracking_nodes::*, generate_code::*, get_declarations::*, parse_files::*,
+    add_initial_value_registrations::*, add_once_declarations::*, add_tracking_declarations::*,
+    check_types::*, clean_up_diagnostics::*, create_declarations_for_tracking_nodes::*,
+    early_breaks::*, find_tracking_nodes::*, generate_code::*, generate_node_groups::*,
+    generate_smart_variable_nodes::*, get_declarations::*, get_enums::*, parse_files::*,
     register_initial_variables::*, register_strings::*, resolve_deferred_type_diagnostic::*,
     validate_unique_node_names::*,
 };
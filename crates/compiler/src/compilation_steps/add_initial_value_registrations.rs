@@ -16,7 +16,11 @@ pub(crate) fn add_initial_value_registrations(
     let declarations = state
         .known_variable_declarations
         .iter()
-        .filter(|decl| !matches!(decl.r#type, Type::Function(_)));
+        .filter(|decl| !matches!(decl.r#type, Type::Function(_)))
+        // Smart variables are computed from their expressions instead of being stored
+        .filter(|decl| !decl.is_smart_variable)
+        // Local variables only exist once they are introduced
+        .filter(|decl| !decl.is_local);
 
     for declaration in declarations {
         let Some(default_value) = declaration.default_value.clone() else {
@@ -32,6 +36,15 @@ pub(crate) fn add_initial_value_registrations(
                 Type::String => Operand::from(String::from(default_value)),
                 Type::Number => Operand::from(f32::try_from(default_value).unwrap()),
                 Type::Boolean => Operand::from(bool::try_from(default_value).unwrap()),
+                // Enums are stored as the raw values of their cases
+                Type::Enum(_) => match default_value {
+                    YarnValue::Number(number) => Operand::from(number),
+                    YarnValue::String(string) => Operand::from(string),
+                    YarnValue::Boolean(_) => bug!(
+                        "Enum variable {} has a boolean default value.",
+                        declaration.name
+                    ),
+                },
                 _ => bug!(
                     "Cannot create initial value registration for type {}.",
                     declaration.r#type.format()
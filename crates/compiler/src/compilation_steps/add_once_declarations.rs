@@ -0,0 +1,31 @@
+use crate::prelude::*;
+use crate::visitors::OnceVisitor;
+use antlr_rust::tree::ParseTreeVisitorCompat;
+use yarnspinner_core::types::Type;
+
+pub(crate) fn add_once_declarations(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    let mut once_declarations = Vec::new();
+    for (file, _) in &state.parsed_files {
+        let mut visitor = OnceVisitor::new(file.clone());
+        visitor.visit(file.tree.as_ref());
+        state.diagnostics.extend(visitor.diagnostics);
+
+        once_declarations.extend(visitor.once_variables.into_iter().map(|name| {
+            Declaration::new(name, Type::Boolean)
+                .with_default_value(false)
+                .with_description(
+                    "The generated variable for tracking whether a once statement has run",
+                )
+        }));
+    }
+
+    // Just like the tracking variables for visits, these need to be known
+    // to the variable storage, so they get an initial value
+    state
+        .known_variable_declarations
+        .extend(once_declarations.clone());
+    state
+        .derived_variable_declarations
+        .extend(once_declarations);
+    state
+}
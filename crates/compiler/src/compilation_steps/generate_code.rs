@@ -43,10 +43,15 @@ pub(crate) fn generate_code(mut state: CompilationIntermediate) -> CompilationIn
         Err(CompilerError(total_diagnostics))
     } else {
         let compilations = results.into_iter().map(|r| r.unwrap());
-        Ok(Compilation::combine(
-            compilations,
-            state.string_table.clone(),
-        ))
+        let mut compilation = Compilation::combine(compilations, state.string_table.clone());
+        if let Some(program) = compilation.program.as_mut() {
+            let node_group_hubs = state
+                .node_group_hubs
+                .iter()
+                .map(|node| (node.name.clone(), node.clone()));
+            program.nodes.extend(node_group_hubs);
+        }
+        Ok(compilation)
     };
 
     state.result = Some(result);
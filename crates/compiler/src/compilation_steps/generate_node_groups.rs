@@ -0,0 +1,208 @@
+//! Type checks the `when:` headers of node groups and generates the nodes that choose between their members.
+
+use crate::listeners::CompilerListener;
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::*;
+use crate::visitors::*;
+use antlr_rust::parser_rule_context::ParserRuleContext;
+use antlr_rust::token::Token;
+use std::collections::HashSet;
+use std::ops::Deref;
+use std::rc::Rc;
+use yarnspinner_core::types::Type;
+
+/// Generates a node for every node group, i.e. for every title shared by nodes with `when:` headers.
+/// The generated node is named after the title and runs one of the nodes of the group, chosen by the saliency strategy of the runtime.
+pub(crate) fn generate_node_groups(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    let node_groups = find_node_groups(&mut state);
+    for node_group in node_groups {
+        if let Some(node) = generate_node_group(&mut state, &node_group) {
+            state.node_group_hubs.push(node);
+        }
+    }
+    state
+}
+
+struct NodeGroup<'input> {
+    title: String,
+    /// The index of the file the first node of the group was found in.
+    file_index: usize,
+    members: Vec<NodeGroupMemberHeaders<'input>>,
+}
+
+/// The `when:` headers of a node of a node group.
+struct NodeGroupMemberHeaders<'input> {
+    name: String,
+    /// The index of the file the node was found in.
+    file_index: usize,
+    /// The headers whose conditions are neither `once` nor `always`.
+    expressions: Vec<Rc<HeaderContext<'input>>>,
+    is_once: bool,
+    /// The first `when:` header of the node. The code generated for the group points at the one of its first node.
+    first_header: Rc<HeaderContext<'input>>,
+}
+
+fn find_node_groups<'input>(state: &mut CompilationIntermediate<'input>) -> Vec<NodeGroup<'input>> {
+    let mut node_groups: Vec<NodeGroup> = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (file_index, (file, _)) in state.parsed_files.iter().enumerate() {
+        for node in file.tree.node_all() {
+            let Some(name) = get_node_group_member_name(&node, file) else {
+                continue;
+            };
+            let title = node
+                .header_all()
+                .into_iter()
+                .filter(|header| header.header_key.as_ref().unwrap().get_text() == "title")
+                .find_map(|header| {
+                    header
+                        .header_value
+                        .as_ref()
+                        .map(|value| value.get_text().to_owned())
+                })
+                .unwrap_or_bug();
+            let conditions = get_node_group_conditions(&node);
+
+            let mut member = NodeGroupMemberHeaders {
+                name,
+                file_index,
+                expressions: Vec::new(),
+                is_once: false,
+                first_header: conditions[0].1.clone(),
+            };
+            for (condition, header) in conditions {
+                match condition.as_str() {
+                    NODE_GROUP_ALWAYS_CONDITION => {}
+                    NODE_GROUP_ONCE_CONDITION => member.is_once = true,
+                    "" => diagnostics.push(
+                        Diagnostic::from_message(format!(
+                            "The '{NODE_GROUP_CONDITION_HEADER}' header of a node in the group '{title}' must have a condition"
+                        ))
+                        .with_file_name(file.name.clone())
+                        .with_parser_context(header.as_ref(), file.tokens()),
+                    ),
+                    _ => member.expressions.push(header),
+                }
+            }
+
+            if let Some(node_group) = node_groups.iter_mut().find(|group| group.title == title) {
+                node_group.members.push(member);
+            } else {
+                node_groups.push(NodeGroup {
+                    title,
+                    file_index,
+                    members: vec![member],
+                });
+            }
+        }
+    }
+    state.diagnostics.extend(diagnostics);
+    node_groups
+}
+
+fn generate_node_group<'input>(
+    state: &mut CompilationIntermediate<'input>,
+    node_group: &NodeGroup<'input>,
+) -> Option<Node> {
+    let group_file = state.parsed_files[node_group.file_index].0.clone();
+    let mut diagnostics = Vec::new();
+    let mut type_check_visitor = TypeCheckVisitor::new(
+        state.known_variable_declarations.clone(),
+        state.enums.clone(),
+        group_file.clone(),
+    );
+    let mut members = Vec::with_capacity(node_group.members.len());
+    for member in &node_group.members {
+        let file = &state.job.files[member.file_index];
+        let parse_result = &state.parsed_files[member.file_index].0;
+        let chars = state.file_chars[member.file_index];
+        let conditions = member
+            .expressions
+            .iter()
+            .map(|header| {
+                let value = header.header_value.as_ref().unwrap_or_bug();
+                let (condition, condition_parse_result) =
+                    parse_expression(file, parse_result, chars, value.deref(), &mut diagnostics);
+                type_check_visitor.check_condition(&condition, condition_parse_result);
+                condition
+            })
+            .collect();
+        members.push(NodeGroupMember {
+            name: member.name.clone(),
+            conditions,
+            is_once: member.is_once,
+        });
+    }
+    diagnostics.append(&mut type_check_visitor.diagnostics);
+
+    // All other deferred types have already been resolved at this point, so any left over are errors
+    let known_declarations: HashSet<_> = state
+        .known_variable_declarations
+        .iter()
+        .chain(type_check_visitor.new_declarations.iter())
+        .map(|declaration| declaration.name.clone())
+        .collect();
+    let unresolved_types = type_check_visitor
+        .deferred_types
+        .into_iter()
+        .filter(|deferred_type| !known_declarations.contains(&deferred_type.name))
+        .map(|deferred_type| deferred_type.diagnostic);
+    diagnostics.extend(unresolved_types);
+
+    let has_errors = diagnostics.has_errors();
+    let diagnostics = diagnostics.into_iter().map(|diagnostic| Diagnostic {
+        message: format!(
+            "Error in the '{NODE_GROUP_CONDITION_HEADER}' header of a node in the group '{}': {}",
+            node_group.title, diagnostic.message
+        ),
+        ..diagnostic
+    });
+    state.diagnostics.extend(diagnostics);
+    if has_errors {
+        return None;
+    }
+
+    let once_declarations = node_group
+        .members
+        .iter()
+        .filter(|member| member.is_once)
+        .map(|member| {
+            Declaration::new(
+                generate_unique_once_variable_for_line(&member.name),
+                Type::Boolean,
+            )
+            .with_default_value(false)
+            .with_description("The generated variable for tracking whether a node of a node group marked with 'once' has run")
+        });
+    let new_declarations: Vec<_> = type_check_visitor
+        .new_declarations
+        .into_iter()
+        .chain(once_declarations)
+        .collect();
+    state
+        .known_variable_declarations
+        .extend(new_declarations.clone());
+    state.derived_variable_declarations.extend(new_declarations);
+
+    let mut compiler_listener = CompilerListener::new(
+        state.tracking_nodes.clone(),
+        type_check_visitor.known_types,
+        group_file,
+    );
+    compiler_listener.current_node = Some(Node {
+        name: node_group.title.clone(),
+        headers: vec![Header {
+            key: Node::NODE_GROUP_HUB_HEADER.to_owned(),
+            value: node_group.title.clone(),
+        }],
+        ..Default::default()
+    });
+    let track = state
+        .tracking_nodes
+        .contains(&node_group.title)
+        .then(|| Library::generate_unique_visited_variable_for_node(&node_group.title));
+    let token = node_group.members[0].first_header.start();
+    CodeGenerationVisitor::new(&mut compiler_listener, track)
+        .generate_code_for_node_group(&members, token.deref());
+    compiler_listener.current_node
+}
@@ -0,0 +1,48 @@
+//! Compiles the expressions of smart variables into the hidden nodes that the runtime runs to read them.
+
+use crate::listeners::CompilerListener;
+use crate::prelude::*;
+use crate::visitors::*;
+use antlr_rust::tree::ParseTreeVisitorCompat;
+
+/// Generates a node for every smart variable, named after the variable.
+/// The node leaves the value of the variable's expression on the stack, and is run by the runtime whenever the variable is read.
+pub(crate) fn generate_smart_variable_nodes(
+    mut state: CompilationIntermediate,
+) -> CompilationIntermediate {
+    let Some(Ok(compilation)) = state.result.as_mut() else {
+        return state;
+    };
+    let Some(program) = compilation.program.as_mut() else {
+        return state;
+    };
+    for (file_index, smart_variable) in &state.smart_variables {
+        let (file, known_types) = &state.parsed_files[*file_index];
+        let name = smart_variable.declaration.name.clone();
+        let mut compiler_listener = CompilerListener::new(
+            state.tracking_nodes.clone(),
+            known_types.clone(),
+            file.clone(),
+        );
+        compiler_listener.current_node = Some(Node {
+            name: name.clone(),
+            headers: vec![
+                Header {
+                    key: Node::SMART_VARIABLE_HEADER.to_owned(),
+                    value: name.clone(),
+                },
+                Header {
+                    key: Node::HIDDEN_HEADER.to_owned(),
+                    value: String::new(),
+                },
+            ],
+            ..Default::default()
+        });
+        CodeGenerationVisitor::new(&mut compiler_listener, None)
+            .visit(smart_variable.expression.as_ref());
+        program
+            .nodes
+            .insert(name, compiler_listener.current_node.unwrap_or_bug());
+    }
+    state
+}
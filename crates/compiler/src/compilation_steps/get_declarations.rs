@@ -1,12 +1,18 @@
 use crate::prelude::*;
-use crate::visitors::DeclarationVisitor;
+use crate::visitors::{
+    DeclarationVisitor, SmartVariableDeclaration, TypeCheckVisitor, VariableReferenceVisitor,
+};
 use antlr_rust::tree::ParseTreeVisitorCompat;
 
 pub(crate) fn get_declarations(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    let mut smart_variables = Vec::new();
     // Find the variable declarations in these files.
-    for (file, _) in &state.parsed_files {
-        let mut variable_declaration_visitor =
-            DeclarationVisitor::new(state.known_variable_declarations.clone(), file.clone());
+    for (file_index, (file, _)) in state.parsed_files.iter().enumerate() {
+        let mut variable_declaration_visitor = DeclarationVisitor::new(
+            state.known_variable_declarations.clone(),
+            state.enums.clone(),
+            file.clone(),
+        );
 
         variable_declaration_visitor.visit(file.tree.as_ref());
 
@@ -24,6 +30,110 @@ pub(crate) fn get_declarations(mut state: CompilationIntermediate) -> Compilatio
         state
             .file_tags
             .insert(file.name.clone(), variable_declaration_visitor.file_tags);
+
+        smart_variables.extend(
+            variable_declaration_visitor
+                .smart_variables
+                .into_iter()
+                .map(|smart_variable| (file_index, smart_variable)),
+        );
     }
+    resolve_smart_variable_types(&mut state, smart_variables);
     state
 }
+
+/// Declares the smart variables with the types of their expressions.
+/// Since smart variables can refer to each other, they are resolved one after the other
+/// until none of the remaining ones only depends on variables whose types are known.
+fn resolve_smart_variable_types<'input>(
+    state: &mut CompilationIntermediate<'input>,
+    mut smart_variables: Vec<(usize, SmartVariableDeclaration<'input>)>,
+) {
+    smart_variables.retain(|(file_index, smart_variable)| {
+        let file = &state.parsed_files[*file_index].0;
+        let existing_declaration = state
+            .known_variable_declarations
+            .iter()
+            .find(|declaration| {
+                !declaration.is_implicit && declaration.name == smart_variable.declaration.name
+            });
+        if let Some(existing_declaration) = existing_declaration {
+            state.diagnostics.push(
+                Diagnostic::from_message(format!(
+                    "{} has already been declared in {}",
+                    existing_declaration.name, existing_declaration.source_file_name
+                ))
+                .with_file_name(file.name.clone())
+                .with_parser_context(smart_variable.expression.as_ref(), file.tokens()),
+            );
+        }
+        existing_declaration.is_none()
+    });
+
+    loop {
+        let unresolved_names: Vec<_> = smart_variables
+            .iter()
+            .map(|(_, smart_variable)| smart_variable.declaration.name.clone())
+            .collect();
+        let resolvable_index = smart_variables.iter().position(|(_, smart_variable)| {
+            let mut variable_reference_visitor = VariableReferenceVisitor::default();
+            variable_reference_visitor.visit(smart_variable.expression.as_ref());
+            !variable_reference_visitor
+                .variables
+                .iter()
+                .any(|variable| unresolved_names.contains(variable))
+        });
+        // Anything left over refers to itself, directly or through other smart variables.
+        let Some(index) = resolvable_index else {
+            for (file_index, smart_variable) in smart_variables {
+                let file = &state.parsed_files[file_index].0;
+                state.diagnostics.push(
+                    Diagnostic::from_message(format!(
+                        "The smart variable {} cannot depend on itself",
+                        smart_variable.declaration.name
+                    ))
+                    .with_file_name(file.name.clone())
+                    .with_parser_context(smart_variable.expression.as_ref(), file.tokens()),
+                );
+            }
+            return;
+        };
+
+        let (file_index, smart_variable) = smart_variables.remove(index);
+        let file = state.parsed_files[file_index].0.clone();
+        let mut type_check_visitor = TypeCheckVisitor::new(
+            state.known_variable_declarations.clone(),
+            state.enums.clone(),
+            file.clone(),
+        );
+        let expression_type = type_check_visitor.visit(smart_variable.expression.as_ref());
+        state.diagnostics.extend(type_check_visitor.diagnostics);
+        state
+            .potential_issues
+            .extend(type_check_visitor.deferred_types);
+        let Some(expression_type) = expression_type else {
+            state.diagnostics.push(
+                Diagnostic::from_message(format!(
+                    "Can't figure out the type of the smart variable {} from its expression",
+                    smart_variable.declaration.name
+                ))
+                .with_file_name(file.name.clone())
+                .with_parser_context(smart_variable.expression.as_ref(), file.tokens()),
+            );
+            continue;
+        };
+        let smart_variable = SmartVariableDeclaration {
+            declaration: smart_variable.declaration.with_type(expression_type),
+            ..smart_variable
+        };
+        let declarations = type_check_visitor
+            .new_declarations
+            .into_iter()
+            .chain(std::iter::once(smart_variable.declaration.clone()));
+        for declaration in declarations {
+            state.known_variable_declarations.push(declaration.clone());
+            state.derived_variable_declarations.push(declaration);
+        }
+        state.smart_variables.push((file_index, smart_variable));
+    }
+}
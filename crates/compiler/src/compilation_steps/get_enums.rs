@@ -0,0 +1,16 @@
+//! Collects the enums declared in all files before any variables are declared.
+
+use crate::prelude::*;
+use crate::visitors::EnumVisitor;
+use antlr_rust::tree::ParseTreeVisitorCompat;
+
+/// Collects the enums declared in all files, so that variables of their types can be declared afterwards.
+pub(crate) fn get_enums(mut state: CompilationIntermediate) -> CompilationIntermediate {
+    for (file, _) in &state.parsed_files {
+        let mut enum_visitor = EnumVisitor::new(state.enums.clone(), file.clone());
+        enum_visitor.visit(file.tree.as_ref());
+        state.enums.extend(enum_visitor.new_enums);
+        state.diagnostics.extend(enum_visitor.diagnostics);
+    }
+    state
+}
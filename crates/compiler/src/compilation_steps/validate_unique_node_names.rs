@@ -1,5 +1,6 @@
 use crate::prelude::generated::yarnspinnerparser::{DialogueContextAttrs, NodeContextAttrs};
 use crate::prelude::*;
+use crate::visitors::{NODE_GROUP_CONDITION_HEADER, is_node_group_member};
 use antlr_rust::token::Token;
 use std::collections::HashMap;
 
@@ -30,14 +31,17 @@ pub(crate) fn validate_unique_node_names(
                     .unwrap()
                     .get_text()
                     .to_owned();
-                (title, title_header.clone(), file)
+                let is_node_group_member = is_node_group_member(&node);
+                (title, title_header.clone(), file, is_node_group_member)
             })
     });
 
     let nodes_by_name = nodes_with_names.fold(
         HashMap::default(),
-        |mut map: HashMap<_, Vec<_>>, (name, header_context, file)| {
-            map.entry(name).or_default().push((header_context, file));
+        |mut map: HashMap<_, Vec<_>>, (name, header_context, file, is_node_group_member)| {
+            map.entry(name)
+                .or_default()
+                .push((header_context, file, is_node_group_member));
             map
         },
     );
@@ -48,8 +52,32 @@ pub(crate) fn validate_unique_node_names(
         .into_iter()
         .filter(|(_, nodes)| nodes.len() > 1)
     {
+        let node_group_member_count = nodes
+            .iter()
+            .filter(|(_, _, is_node_group_member)| *is_node_group_member)
+            .count();
+        if node_group_member_count == nodes.len() {
+            // All nodes are part of a node group, which is allowed to share a name.
+            continue;
+        }
+        if node_group_member_count > 0 {
+            // Some nodes are part of a node group, but not all. Report an error on those that are missing a condition.
+            for (header_context, file, _) in nodes
+                .into_iter()
+                .filter(|(_, _, is_node_group_member)| !is_node_group_member)
+            {
+                state.diagnostics.push(
+                    Diagnostic::from_message(format!(
+                        "All nodes in the group '{name}' must have a '{NODE_GROUP_CONDITION_HEADER}' header"
+                    ))
+                    .with_file_name(file.name.clone())
+                    .with_parser_context(header_context.as_ref(), file.tokens()),
+                );
+            }
+            continue;
+        }
         // More than one node has this name! Report an error on both.
-        for (header_context, file) in nodes {
+        for (header_context, file, _) in nodes {
             state.diagnostics.push(
                 Diagnostic::from_message(format!("More than one node is named {name}",))
                     .with_file_name(file.name.clone())
@@ -7,6 +7,7 @@ use yarnspinner_core::prelude::*;
 
 mod add_tags_to_lines;
 pub(crate) mod antlr_rust_ext;
+pub(crate) mod format_specifiers;
 pub(crate) mod run_compilation;
 pub(crate) mod utils;
 
@@ -0,0 +1,7 @@
+//! Contains the format specifiers, e.g. `N0` in `{$gold:N0}`, of the inline expressions in lines.
+
+use std::collections::HashMap;
+
+/// The format specifiers of a file, keyed by the index of the character that closes their expression.
+/// They are skipped by the [`crate::parser::YarnSpinnerLexer`], since the grammar does not know about them.
+pub(crate) type FormatSpecifiers = HashMap<usize, String>;
@@ -5,6 +5,7 @@ use crate::prelude::*;
 use crate::string_table_manager::StringTableManager;
 use crate::visitors::*;
 use std::collections::{HashMap, HashSet};
+use yarnspinner_core::types::EnumType;
 
 /// Compile Yarn code, as specified by a compilation job.
 pub(crate) fn compile(compiler: &Compiler) -> Result<Compilation> {
@@ -14,14 +15,18 @@ pub(crate) fn compile(compiler: &Compiler) -> Result<Compilation> {
         &register_strings,
         &validate_unique_node_names,
         &break_on_job_with_only_strings,
+        &get_enums,
         &get_declarations,
         &check_types,
         &find_tracking_nodes,
         &create_declarations_for_tracking_nodes,
         &add_tracking_declarations,
+        &add_once_declarations,
         &resolve_deferred_type_diagnostic,
+        &generate_node_groups,
         &break_on_job_with_only_declarations,
         &generate_code,
+        &generate_smart_variable_nodes,
         &add_initial_value_registrations,
     ];
 
@@ -68,6 +73,12 @@ pub(crate) struct CompilationIntermediate<'input> {
     pub(crate) potential_issues: Vec<DeferredTypeDiagnostic>,
     pub(crate) parsed_files: Vec<(FileParseResult<'input>, KnownTypes)>,
     pub(crate) tracking_nodes: HashSet<String>,
+    /// All enums declared in the Yarn files
+    pub(crate) enums: Vec<EnumType>,
+    /// The nodes generated for node groups, which select one of the nodes sharing a title
+    pub(crate) node_group_hubs: Vec<Node>,
+    /// The smart variables whose types could be resolved, together with the index of the parsed file they were declared in
+    pub(crate) smart_variables: Vec<(usize, SmartVariableDeclaration<'input>)>,
     pub(crate) string_table: StringTableManager,
     pub(crate) diagnostics: Vec<Diagnostic>,
     pub(crate) file_tags: HashMap<String, Vec<String>>,
@@ -85,6 +96,9 @@ impl<'input> CompilationIntermediate<'input> {
             potential_issues: Default::default(),
             parsed_files: Default::default(),
             tracking_nodes: Default::default(),
+            enums: Default::default(),
+            node_group_hubs: Default::default(),
+            smart_variables: Default::default(),
             string_table: Default::default(),
             diagnostics: Default::default(),
             file_tags: Default::default(),
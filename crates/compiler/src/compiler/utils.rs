@@ -9,7 +9,9 @@ use crate::prelude::*;
 use antlr_rust::Parser;
 use antlr_rust::common_token_stream::CommonTokenStream;
 use antlr_rust::input_stream::CodePoint32BitCharStream;
-use antlr_rust::token::{TOKEN_DEFAULT_CHANNEL, Token};
+use antlr_rust::int_stream::IntStream;
+use antlr_rust::lexer_atn_simulator::ILexerATNSimulator;
+use antlr_rust::token::{TOKEN_DEFAULT_CHANNEL, TOKEN_EOF, Token};
 use std::collections::HashSet;
 use std::rc::Rc;
 use yarnspinner_core::prelude::*;
@@ -45,6 +47,7 @@ pub(crate) fn parse_syntax_tree<'a, 'b: 'a>(
     let lexer_error_listener = LexerErrorListener::new(file_name.clone());
     let lexer_error_listener_diagnostics = lexer_error_listener.diagnostics.clone();
     let lexer_diagnostics = lexer.diagnostics.clone();
+    let format_specifiers = lexer.format_specifiers.clone();
     lexer.remove_error_listeners();
     lexer.add_error_listener(Box::new(lexer_error_listener));
 
@@ -70,7 +73,74 @@ pub(crate) fn parse_syntax_tree<'a, 'b: 'a>(
         .cloned();
     diagnostics.extend(new_diagnostics);
 
-    FileParseResult::new(file_name, tree, Rc::new(parser))
+    let format_specifiers = format_specifiers.take();
+    FileParseResult::new(file_name, tree, Rc::new(parser), format_specifiers)
+}
+
+/// Parses the text of `token` on its own as an expression, e.g. the condition of a `when:` header.
+///
+/// The text is lexed as if it were inside of a command, so a `>>` ends the expression and is reported like any other leftover input.
+/// The tokens keep their positions in the file, so diagnostics about the expression point at the right spot.
+/// Since a parse result needs a tree, the returned one shares the tree of `parse_result`, the file containing `token`.
+pub(crate) fn parse_expression<'input>(
+    file: &File,
+    parse_result: &FileParseResult<'input>,
+    file_chars: &'input [u32],
+    token: &(impl Token + ?Sized),
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (Rc<ExpressionContextAll<'input>>, FileParseResult<'input>) {
+    let chars = &file_chars[token.get_start() as usize..=token.get_stop() as usize];
+    let input = CodePoint32BitCharStream::new(chars);
+    let mut lexer = YarnSpinnerLexer::new(input, file.file_name.clone());
+    lexer.mode = yarnspinnerlexer::ExpressionMode;
+    let interpreter = lexer.interpreter.as_mut().unwrap_or_bug();
+    // antlr4rust's `set_line` sets the column instead, so we move to the line by consuming line breaks
+    let line_breaks = vec!['\n' as u32; token.get_line_as_usize().saturating_sub(1)];
+    let mut line_breaks = CodePoint32BitCharStream::new(&line_breaks);
+    while line_breaks.la(1) != TOKEN_EOF {
+        interpreter.consume(&mut line_breaks);
+    }
+    interpreter.set_char_position_in_line(token.get_column());
+
+    let lexer_error_listener = LexerErrorListener::new(file.file_name.clone());
+    let lexer_error_listener_diagnostics = lexer_error_listener.diagnostics.clone();
+    let lexer_diagnostics = lexer.diagnostics.clone();
+    lexer.remove_error_listeners();
+    lexer.add_error_listener(Box::new(lexer_error_listener));
+
+    let tokens = CommonTokenStream::new(lexer);
+    let mut parser = YarnSpinnerParser::with_strategy(tokens, ErrorStrategy::new());
+    let parser_error_listener = ParserErrorListener::new(file.clone());
+    let parser_error_listener_diagnostics = parser_error_listener.diagnostics.clone();
+    parser.remove_error_listeners();
+    parser.add_error_listener(Box::new(parser_error_listener));
+
+    let expression = parser.expression().unwrap();
+    if parser.input.la(1) != TOKEN_EOF {
+        let message = format!(
+            "Unexpected '{}' after the expression",
+            parser.get_current_token().get_text()
+        );
+        parser.notify_error_listeners(message, None, None);
+    }
+
+    let lexer_diagnostics_borrowed = lexer_diagnostics.borrow();
+    let lexer_error_listener_diagnostics_borrowed = lexer_error_listener_diagnostics.borrow();
+    let parser_error_listener_diagnostics_borrowed = parser_error_listener_diagnostics.borrow();
+    let new_diagnostics = lexer_error_listener_diagnostics_borrowed
+        .iter()
+        .chain(lexer_diagnostics_borrowed.iter())
+        .chain(parser_error_listener_diagnostics_borrowed.iter())
+        .cloned();
+    diagnostics.extend(new_diagnostics);
+
+    let expression_parse_result = FileParseResult::new(
+        parse_result.name.clone(),
+        parse_result.tree.clone(),
+        Rc::new(parser),
+        Default::default(),
+    );
+    (expression, expression_parse_result)
 }
 
 pub(crate) fn get_line_id_for_node_name(name: &str) -> LineId {
@@ -200,6 +270,9 @@ where
 pub(crate) fn get_declarations_from_library(library: &Library) -> Vec<Declaration> {
     let operators: HashSet<_> = Type::EXPLICITLY_CONSTRUCTABLE
         .iter()
+        .cloned()
+        // All enums share the same methods
+        .chain(std::iter::once(Type::Enum(Default::default())))
         .flat_map(|r#type| {
             r#type
                 .methods()
@@ -228,6 +301,16 @@ pub(crate) fn get_declarations_from_library(library: &Library) -> Vec<Declaratio
         .collect()
 }
 
+/// Hashes the given text with FNV-1a, so that names generated from it do not depend on the hasher of the standard library
+/// and stay the same across compilations.
+pub(crate) fn stable_hash(text: &str) -> u32 {
+    const OFFSET_BASIS: u32 = 0x811c_9dc5;
+    const PRIME: u32 = 0x0100_0193;
+    text.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,6 +19,9 @@ pub(crate) struct FileParseResult<'input> {
     /// We also end up leading the `ErrorStrategy` into the public interface, but using generics here makes
     /// the code a lot more complicated without actually providing much benefit.
     pub parser: Rc<ActualYarnSpinnerParser<'input>>,
+
+    /// Not in the original. The format specifiers that the lexer skipped in the inline expressions of lines.
+    pub format_specifiers: Rc<FormatSpecifiers>,
 }
 
 impl<'input> FileParseResult<'input> {
@@ -26,8 +29,14 @@ impl<'input> FileParseResult<'input> {
         name: String,
         tree: Rc<DialogueContextAll<'input>>,
         parser: Rc<ActualYarnSpinnerParser<'input>>,
+        format_specifiers: FormatSpecifiers,
     ) -> Self {
-        Self { name, tree, parser }
+        Self {
+            name,
+            tree,
+            parser,
+            format_specifiers: Rc::new(format_specifiers),
+        }
     }
 
     pub(crate) fn tokens(&self) -> &ActualTokenStream<'input> {
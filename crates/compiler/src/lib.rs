@@ -26,9 +26,9 @@ pub use crate::compiler::Result;
 pub mod prelude {
     //! Everything you need to get started with the Yarn Spinner compiler.
     pub(crate) use crate::{
-        compiler::antlr_rust_ext::*, compiler::run_compilation::*, compiler::utils::*,
-        file_parse_result::*, parser::*, parser_rule_context_ext::*, string_table_manager::*,
-        token_ext::*,
+        compiler::antlr_rust_ext::*, compiler::format_specifiers::*, compiler::run_compilation::*,
+        compiler::utils::*, file_parse_result::*, parser::*, parser_rule_context_ext::*,
+        string_table_manager::*, token_ext::*,
     };
     pub use crate::{
         compiler::{CompilationType, Compiler, File},
@@ -15,7 +15,7 @@ use crate::parser::generated::yarnspinnerparser::{
 };
 use crate::prelude::generated::yarnspinnerparser::BodyContextAttrs;
 use crate::prelude::generated::yarnspinnerparserlistener::YarnSpinnerParserListener;
-use crate::visitors::{CodeGenerationVisitor, KnownTypes};
+use crate::visitors::{CodeGenerationVisitor, KnownTypes, get_node_group_member_name};
 pub(crate) use emit::*;
 use yarnspinner_core::prelude::OpCode;
 
@@ -31,10 +31,12 @@ pub(crate) struct CompilerListener<'input> {
     pub(crate) current_node: Option<Node>,
     /// The current debug information that describes [`current_node`].
     current_debug_info: DebugInfo,
+    /// The name [`current_node`] is compiled under if it is part of a node group.
+    current_node_group_member_name: Option<String>,
     /// Whether we are currently parsing the
     /// current node as a 'raw text' node, or as a fully syntactic node.
     is_current_node_raw_text: bool,
-    file: FileParseResult<'input>,
+    pub(crate) file: FileParseResult<'input>,
     label_count: usize,
 }
 
@@ -50,6 +52,7 @@ impl<'input> CompilerListener<'input> {
             tracking_nodes: Rc::new(RefCell::new(tracking_nodes)),
             current_node: Default::default(),
             current_debug_info: Default::default(),
+            current_node_group_member_name: Default::default(),
             is_current_node_raw_text: Default::default(),
             diagnostics: Default::default(),
             program: Default::default(),
@@ -73,10 +76,11 @@ impl<'input> CompilerListener<'input> {
 impl<'input> ParseTreeListener<'input, YarnSpinnerParserContextType> for CompilerListener<'input> {}
 
 impl<'input> YarnSpinnerParserListener<'input> for CompilerListener<'input> {
-    fn enter_node(&mut self, _ctx: &NodeContext<'input>) {
+    fn enter_node(&mut self, ctx: &NodeContext<'input>) {
         // we have found a new node set up the currentNode var ready to hold it and otherwise continue
         self.current_node = Some(Node::default());
         self.current_debug_info = Default::default();
+        self.current_node_group_member_name = get_node_group_member_name(ctx, &self.file);
         self.is_current_node_raw_text = false;
     }
 
@@ -131,8 +135,12 @@ impl<'input> YarnSpinnerParserListener<'input> for CompilerListener<'input> {
             .to_owned();
         match header_key {
             "title" => {
-                // Set the name of the node
-                current_node.name.clone_from(&header_value);
+                // Set the name of the node. Nodes of a node group share their title,
+                // so they get a unique name instead.
+                current_node.name = self
+                    .current_node_group_member_name
+                    .clone()
+                    .unwrap_or_else(|| header_value.clone());
             }
             "tags" => {
                 // Split the list of tags by spaces, and use that
@@ -196,8 +204,9 @@ impl<'input> YarnSpinnerParserListener<'input> for CompilerListener<'input> {
         if let Some(track) = track {
             CodeGenerationVisitor::generate_tracking_code(self, track);
         }
-        // We have exited the body; emit a 'stop' opcode here.
-        self.emit(Emit::from_op_code(OpCode::Stop).with_source(Position {
+        // We have exited the body; emit a 'return' opcode here,
+        // which stops the dialogue unless this node was entered by a detour.
+        self.emit(Emit::from_op_code(OpCode::Return).with_source(Position {
             line: (ctx.stop().line as usize).saturating_sub(1),
             character: 0,
         }));
@@ -1,14 +1,14 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner.Compiler/CompilationResult.cs>
 
 use crate::listeners::*;
-pub use crate::output::{debug_info::*, declaration::*, string_info::*};
+pub use crate::output::{declaration::*, string_info::*};
 use crate::prelude::*;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Debug, Display};
 use yarnspinner_core::prelude::*;
+pub use yarnspinner_core::prelude::{DebugInfo, LineInfo};
 
-mod debug_info;
 mod declaration;
 mod string_info;
 
@@ -107,7 +107,10 @@ impl Compilation {
             diagnostics.extend(compilation.warnings);
             node_debug_infos.extend(compilation.debug_info);
         }
-        let combined_program = Program::combine(programs);
+        // Duplicate node names are reported as diagnostics before code generation
+        // and initial values are only registered after combining, so the programs cannot collide
+        let combined_program = Program::combine(programs)
+            .expect_or_bug("Programs of a successful compilation should not collide");
         let contains_implicit_string_tags = string_table_manager.contains_implicit_string_tags();
         Compilation {
             program: combined_program,
@@ -56,6 +56,18 @@ pub struct Declaration {
     /// If `false`, this declaration appears in the source code.
     pub is_implicit: bool,
 
+    /// A value indicating whether this declaration is a smart variable,
+    /// i.e. a variable declared with an expression that is evaluated every time the variable is read.
+    ///
+    /// Smart variables have no [`Declaration::default_value`] and are never stored in a variable storage.
+    pub is_smart_variable: bool,
+
+    /// A value indicating whether this declaration is a local variable,
+    /// i.e. a variable introduced with `<<local>>` that only exists until the node it was introduced in is left.
+    ///
+    /// Local variables have no initial value in the [`Program`].
+    pub is_local: bool,
+
     /// The type of the variable, as represented by an object found
     /// in a variant of [`Type`].
     pub r#type: Type,
@@ -89,6 +101,8 @@ impl Declaration {
             source_file_name: Default::default(),
             source_node_name: Default::default(),
             is_implicit: Default::default(),
+            is_smart_variable: Default::default(),
+            is_local: Default::default(),
             range: Default::default(),
         }
     }
@@ -150,6 +164,18 @@ impl Declaration {
         self
     }
 
+    #[doc(hidden)]
+    pub fn with_smart_variable(mut self) -> Self {
+        self.is_smart_variable = true;
+        self
+    }
+
+    #[doc(hidden)]
+    pub fn with_local(mut self) -> Self {
+        self.is_local = true;
+        self
+    }
+
     #[doc(hidden)]
     pub fn with_range(mut self, range: impl Into<Range<Position>>) -> Self {
         self.range = Some(range.into());
@@ -163,6 +189,8 @@ impl Declaration {
             && self.source_file_name == other.source_file_name
             && self.source_node_name == other.source_node_name
             && self.is_implicit == other.is_implicit
+            && self.is_smart_variable == other.is_smart_variable
+            && self.is_local == other.is_local
             && self.r#type == other.r#type
             && self.range == other.range
             && match (&self.default_value, &other.default_value) {
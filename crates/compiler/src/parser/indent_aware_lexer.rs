@@ -9,12 +9,20 @@ use super::generated::yarnspinnerlexer::{
     self, LocalTokenFactory, YarnSpinnerLexer as GeneratedYarnSpinnerLexer,
 };
 use crate::collections::*;
+use crate::compiler::format_specifiers::FormatSpecifiers;
 use crate::listeners::Diagnostic;
 use crate::prelude::{DiagnosticSeverity, TokenExt, create_common_token};
+#[cfg(doc)]
+use crate::visitors::{EnumCaseReference, EnumCommand};
+use crate::visitors::{
+    ONCE_CONDITION_MARKER, OnceCommand, has_once_condition_text, is_constant_declaration_value,
+};
+use antlr_rust::lexer_atn_simulator::ILexerATNSimulator;
 use antlr_rust::token::CommonToken;
 use antlr_rust::{
     Lexer, TokenSource,
     char_stream::CharStream,
+    lexer::BaseLexer,
     token::{TOKEN_DEFAULT_CHANNEL, Token},
     token_factory::{CommonTokenFactory, TokenFactory},
 };
@@ -34,6 +42,15 @@ antlr_rust::tid! { impl<'input, Input> TidAble<'input> for IndentAwareYarnSpinne
 /// ## Implementation notes
 ///
 /// In contrast to the original implementation, the warnings emitted by this lexer are actually respected in the diagnostics.
+///
+/// Since our grammar predates `once` statements, this lexer also rewrites a `<<once>>` at the end of a line
+/// into the line condition `<<if $Yarn.Internal.Once>>`, see [`ONCE_CONDITION_MARKER`],
+/// and an `<<else>>` inside a `<<once>>` block into a regular command.
+/// Likewise, a `<<declare>>` statement whose value is an expression is rewritten into a `<<set>>` statement,
+/// see [`crate::visitors::is_smart_variable_declaration`], and so is a `<<local>>` statement,
+/// see [`crate::visitors::is_local_variable_declaration`]. Enum declarations are turned into regular commands
+/// and references to enum cases into single variable tokens, see [`EnumCommand`] and [`EnumCaseReference`].
+/// The format specifiers of inline expressions in lines are skipped, see [`Self::skip_format_specifier`].
 pub(crate) struct IndentAwareYarnSpinnerLexer<
     'input,
     Input: CharStream<From<'input>>,
@@ -50,6 +67,10 @@ pub(crate) struct IndentAwareYarnSpinnerLexer<
     /// only returns a single [`Token`] at a time, which
     /// means we use this list to buffer it.
     pending_tokens: Queue<TF::Tok>,
+    /// Tokens that were read from the generated lexer to look ahead, but have not been processed yet.
+    lookahead_tokens: Queue<TF::Tok>,
+    /// The `<<if>>` statements and `<<once>>` blocks of the current node that have not been closed yet, innermost last.
+    open_blocks: Stack<OpenBlock>,
     /// A flag to say the last line observed was a shortcut or not.
     /// Used to determine if tracking indents needs to occur.
     line_contains_shortcut: bool,
@@ -63,6 +84,8 @@ pub(crate) struct IndentAwareYarnSpinnerLexer<
     last_seen_option_content: Option<isize>,
     file_name: String,
     pub(crate) diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    /// The format specifiers that were skipped in the inline expressions of lines, see [`Self::skip_format_specifier`].
+    pub(crate) format_specifiers: Rc<RefCell<FormatSpecifiers>>,
 }
 
 impl<'input, Input: CharStream<From<'input>>> Deref for IndentAwareYarnSpinnerLexer<'input, Input> {
@@ -118,6 +141,13 @@ impl<'input, Input: CharStream<From<'input>>> TokenSource<'input>
     }
 }
 
+/// A statement that spans multiple lines, as tracked by [`IndentAwareYarnSpinnerLexer::open_blocks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenBlock {
+    If,
+    Once,
+}
+
 /// Copied from generated/yarnspinnerlexer.rs
 type From<'a> = <LocalTokenFactory<'a> as TokenFactory<'a>>::From;
 
@@ -132,16 +162,22 @@ where
             hit_eof: false,
             last_token: Default::default(),
             pending_tokens: Default::default(),
+            lookahead_tokens: Default::default(),
+            open_blocks: Default::default(),
             line_contains_shortcut: false,
             last_indent: Default::default(),
             unbalanced_indents: Default::default(),
             last_seen_option_content: None,
             diagnostics: Default::default(),
+            format_specifiers: Default::default(),
         }
     }
 
     fn check_next_token(&mut self) {
-        let current = self.base.next_token();
+        let current = self
+            .lookahead_tokens
+            .dequeue()
+            .unwrap_or_else(|| self.next_base_token());
 
         match current.token_type {
             // Insert indents or dedents depending on the next token's
@@ -161,11 +197,30 @@ where
                 self.diagnose_newlines_in_commands(&current);
                 self.pending_tokens.enqueue(current.clone());
             }
+            // a command following the text of a line, i.e. a line condition
+            yarnspinnerlexer::COMMAND_START
+                if self.last_token.as_ref().is_some_and(|last| {
+                    [yarnspinnerlexer::TEXT, yarnspinnerlexer::EXPRESSION_END]
+                        .contains(&last.token_type)
+                }) =>
+            {
+                self.handle_line_condition_start(current.clone())
+            }
+            yarnspinnerlexer::COMMAND_START => self.handle_command_start(current.clone()),
+            yarnspinnerlexer::COMMAND_DECLARE => self.handle_declare_token(current.clone()),
+            yarnspinnerlexer::COMMAND_LOCAL => self.handle_local_token(current.clone()),
+            yarnspinnerlexer::COMMAND_ENUM
+            | yarnspinnerlexer::COMMAND_CASE
+            | yarnspinnerlexer::COMMAND_ENDENUM => self.handle_enum_command_token(current.clone()),
+            yarnspinnerlexer::FUNC_ID | yarnspinnerlexer::DOT => {
+                self.handle_enum_case_reference(current.clone())
+            }
             yarnspinnerlexer::BODY_END => {
                 self.line_contains_shortcut = false;
                 self.last_indent = 0;
                 self.unbalanced_indents.0.clear();
                 self.last_seen_option_content = None;
+                self.open_blocks.0.clear();
                 // [sic from the original!] TODO: this should be empty by now actually...
                 self.pending_tokens.enqueue(current.clone());
             }
@@ -269,6 +324,361 @@ where
         self.last_indent = current_indentation_length;
     }
 
+    /// Rewrites the line condition `<<once>>` into `<<if $Yarn.Internal.Once>>`, which our grammar can parse.
+    /// All other line conditions are passed through unchanged.
+    fn handle_line_condition_start(
+        &mut self,
+        current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
+    ) {
+        self.pending_tokens.enqueue(current_token);
+
+        // Read the command's text up to the first token that is not part of it
+        let mut command_tokens = Vec::new();
+        loop {
+            let token = self.next_base_token();
+            let is_command_text = token.token_type == yarnspinnerlexer::COMMAND_TEXT
+                || token.get_channel() != TOKEN_DEFAULT_CHANNEL;
+            command_tokens.push(token);
+            if !is_command_text {
+                break;
+            }
+        }
+        let command_text: String = command_tokens
+            .iter()
+            .filter(|token| token.token_type == yarnspinnerlexer::COMMAND_TEXT)
+            .map(|token| token.get_text())
+            .collect();
+        let first_token = command_tokens.first().unwrap();
+        let last_token = command_tokens.last().unwrap();
+
+        if last_token.token_type == yarnspinnerlexer::COMMAND_TEXT_END
+            && OnceCommand::parse(&command_text) == Some(OnceCommand::Once)
+        {
+            if has_once_condition_text(&command_text) {
+                // Rewritten anyway so that this is the only error reported for the line
+                self.diagnostics.borrow_mut().push(
+                    Diagnostic::from_message(
+                        "<<once if>> is not supported as a line condition, put the line inside an <<if>> statement and mark it with <<once>> instead",
+                    )
+                    .with_range(
+                        Position {
+                            line: first_token.get_line_as_usize() - 1,
+                            character: first_token.get_column_as_usize(),
+                        }..Position {
+                            line: last_token.get_line_as_usize() - 1,
+                            character: last_token.get_column_as_usize(),
+                        },
+                    )
+                    .with_context(command_text.clone())
+                    .with_start_line(first_token.get_line_as_usize() - 1)
+                    .with_file_name(self.file_name.clone())
+                    .with_severity(DiagnosticSeverity::Error),
+                );
+            }
+            let mut if_token = first_token.clone();
+            if_token.token_type = yarnspinnerlexer::COMMAND_IF;
+            if_token.text = "if".into();
+            let mut marker_token = first_token.clone();
+            marker_token.token_type = yarnspinnerlexer::VAR_ID;
+            marker_token.text = ONCE_CONDITION_MARKER.into();
+            let mut end_token = last_token.clone();
+            end_token.token_type = yarnspinnerlexer::COMMAND_END;
+
+            self.pending_tokens.enqueue(if_token);
+            self.pending_tokens.enqueue(marker_token);
+            self.pending_tokens.enqueue(end_token);
+        } else {
+            // Not ours, so process these tokens as usual
+            self.lookahead_tokens.0.extend(command_tokens);
+        }
+    }
+
+    /// Keeps track of the open `<<if>>` statements and `<<once>>` blocks, and rewrites an `<<else>>` that belongs to
+    /// a `<<once>>` block into a regular command, as our grammar only allows it as part of an `<<if>>` statement.
+    fn handle_command_start(
+        &mut self,
+        current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
+    ) {
+        self.pending_tokens.enqueue(current_token);
+
+        let mut command_tokens = Vec::new();
+        loop {
+            let token = self.next_unprocessed_token();
+            let is_hidden = token.get_channel() != TOKEN_DEFAULT_CHANNEL;
+            command_tokens.push(token);
+            if !is_hidden {
+                break;
+            }
+        }
+        match command_tokens.last().unwrap().token_type {
+            yarnspinnerlexer::COMMAND_IF => self.open_blocks.push(OpenBlock::If),
+            yarnspinnerlexer::COMMAND_ENDIF if self.open_blocks.peek() == Some(&OpenBlock::If) => {
+                self.open_blocks.pop();
+            }
+            yarnspinnerlexer::COMMAND_ELSE if self.open_blocks.peek() == Some(&OpenBlock::Once) => {
+                command_tokens.last_mut().unwrap().token_type = yarnspinnerlexer::COMMAND_TEXT;
+                loop {
+                    let mut token = self.next_unprocessed_token();
+                    let token_type = token.token_type;
+                    if token_type == yarnspinnerlexer::COMMAND_END {
+                        token.token_type = yarnspinnerlexer::COMMAND_TEXT_END;
+                    }
+                    command_tokens.push(token);
+                    if [
+                        yarnspinnerlexer::COMMAND_END,
+                        yarnspinnerlexer::NEWLINE,
+                        yarnspinnerlexer::BODY_END,
+                        antlr_rust::token::TOKEN_EOF,
+                    ]
+                    .contains(&token_type)
+                    {
+                        break;
+                    }
+                }
+            }
+            yarnspinnerlexer::COMMAND_TEXT => {
+                loop {
+                    let token = self.next_unprocessed_token();
+                    let is_command_text = token.token_type == yarnspinnerlexer::COMMAND_TEXT
+                        || token.get_channel() != TOKEN_DEFAULT_CHANNEL;
+                    command_tokens.push(token);
+                    if !is_command_text {
+                        break;
+                    }
+                }
+                let command_text: String = command_tokens
+                    .iter()
+                    .filter(|token| token.token_type == yarnspinnerlexer::COMMAND_TEXT)
+                    .map(|token| token.get_text())
+                    .collect();
+                match OnceCommand::parse(&command_text) {
+                    Some(OnceCommand::Once) => self.open_blocks.push(OpenBlock::Once),
+                    Some(OnceCommand::EndOnce)
+                        if self.open_blocks.peek() == Some(&OpenBlock::Once) =>
+                    {
+                        self.open_blocks.pop();
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        // Process the command's tokens as usual
+        self.unread_tokens(command_tokens.into_iter());
+    }
+
+    /// Rewrites a `<<declare>>` statement whose value is not a constant into a `<<set>>` statement,
+    /// as our grammar only accepts expressions in the latter. The command token keeps its text so that
+    /// the statement can still be told apart from a regular `<<set>>`.
+    fn handle_declare_token(
+        &mut self,
+        mut current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
+    ) {
+        // Read the rest of the statement
+        let mut statement_tokens = Vec::new();
+        loop {
+            let token = self.next_base_token();
+            let token_type = token.token_type;
+            statement_tokens.push(token);
+            if [
+                yarnspinnerlexer::COMMAND_END,
+                yarnspinnerlexer::NEWLINE,
+                yarnspinnerlexer::BODY_END,
+                antlr_rust::token::TOKEN_EOF,
+            ]
+            .contains(&token_type)
+            {
+                break;
+            }
+        }
+        let value_token_types: Vec<_> = statement_tokens
+            .iter()
+            .filter(|token| token.get_channel() == TOKEN_DEFAULT_CHANNEL)
+            .map(|token| token.token_type)
+            .skip_while(|token_type| *token_type != yarnspinnerlexer::OPERATOR_ASSIGNMENT)
+            .skip(1)
+            .take_while(|token_type| {
+                ![
+                    yarnspinnerlexer::EXPRESSION_AS,
+                    yarnspinnerlexer::COMMAND_END,
+                    yarnspinnerlexer::NEWLINE,
+                    yarnspinnerlexer::BODY_END,
+                    antlr_rust::token::TOKEN_EOF,
+                ]
+                .contains(token_type)
+            })
+            .collect();
+        if !value_token_types.is_empty() && !is_constant_declaration_value(&value_token_types) {
+            current_token.token_type = yarnspinnerlexer::COMMAND_SET;
+        }
+        self.pending_tokens.enqueue(current_token);
+        self.lookahead_tokens.0.extend(statement_tokens);
+    }
+
+    /// Rewrites a `<<local>>` statement into a `<<set>>` statement, as our grammar has no rule for the former.
+    /// The command token keeps its text so that the statement can still be told apart from a regular `<<set>>`.
+    fn handle_local_token(
+        &mut self,
+        mut current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
+    ) {
+        current_token.token_type = yarnspinnerlexer::COMMAND_SET;
+        // Unlike `set`, the lexer doesn't switch to expressions after `local`, so we do that ourselves.
+        // If the following tokens were already read, it's too late for that and the parser reports the statement.
+        if self.lookahead_tokens.0.is_empty() {
+            self.base.push_mode(yarnspinnerlexer::ExpressionMode);
+        }
+        self.pending_tokens.enqueue(current_token);
+    }
+
+    /// Rewrites the `<<enum>>`, `<<case>>` and `<<endenum>>` commands into regular commands,
+    /// as our grammar has no rules for them.
+    fn handle_enum_command_token(
+        &mut self,
+        mut current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
+    ) {
+        current_token.token_type = yarnspinnerlexer::COMMAND_TEXT;
+        self.pending_tokens.enqueue(current_token);
+        let mut command_tokens = Vec::new();
+        loop {
+            let mut token = self.next_unprocessed_token();
+            let token_type = token.token_type;
+            match token_type {
+                yarnspinnerlexer::ID => token.token_type = yarnspinnerlexer::COMMAND_TEXT,
+                yarnspinnerlexer::COMMAND_END => {
+                    token.token_type = yarnspinnerlexer::COMMAND_TEXT_END
+                }
+                _ => {}
+            }
+            command_tokens.push(token);
+            if [
+                yarnspinnerlexer::COMMAND_END,
+                yarnspinnerlexer::COMMAND_TEXT_END,
+                yarnspinnerlexer::NEWLINE,
+                yarnspinnerlexer::BODY_END,
+                antlr_rust::token::TOKEN_EOF,
+            ]
+            .contains(&token_type)
+            {
+                break;
+            }
+        }
+        // Process the rest of the rewritten command as usual
+        self.unread_tokens(command_tokens.into_iter());
+    }
+
+    /// Merges a reference to an enum case, i.e. `Food.Apple` or `.Apple`, into a single variable token,
+    /// as our grammar has no syntax for it.
+    fn handle_enum_case_reference(
+        &mut self,
+        current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
+    ) {
+        let expected_token_types: &[isize] = if current_token.token_type == yarnspinnerlexer::DOT {
+            &[yarnspinnerlexer::FUNC_ID]
+        } else {
+            &[yarnspinnerlexer::DOT, yarnspinnerlexer::FUNC_ID]
+        };
+        let mut following_tokens = Vec::new();
+        for expected_token_type in expected_token_types {
+            let token = self.next_unprocessed_token();
+            let is_expected = token.token_type == *expected_token_type;
+            following_tokens.push(token);
+            if !is_expected {
+                break;
+            }
+        }
+        let is_enum_case_reference = following_tokens
+            .iter()
+            .map(|token| token.token_type)
+            .eq(expected_token_types.iter().copied());
+        if !is_enum_case_reference {
+            self.pending_tokens.enqueue(current_token);
+            self.unread_tokens(following_tokens.into_iter());
+            return;
+        }
+
+        let mut reference_token = current_token;
+        let text: String = std::iter::once(reference_token.get_text())
+            .chain(following_tokens.iter().map(|token| token.get_text()))
+            .collect();
+        reference_token.token_type = yarnspinnerlexer::VAR_ID;
+        reference_token.text = text.into();
+        reference_token.stop = following_tokens.last().unwrap().stop;
+        self.pending_tokens.enqueue(reference_token);
+    }
+
+    /// Reads the next token from the generated lexer.
+    fn next_base_token(
+        &mut self,
+    ) -> Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>> {
+        self.skip_format_specifier();
+        self.base.next_token()
+    }
+
+    /// Skips the format specifier of an inline expression in a line, e.g. `:N0` in `{$gold:N0}`, since the grammar does not know about them.
+    /// The specifier is stored in [`Self::format_specifiers`] under the index of the `}` that closes its expression
+    /// and is passed on to the runtime as part of the line text.
+    ///
+    /// Expressions in commands are left alone, as they are only turned into text for the game.
+    /// If the expression does not end on the same line, the specifier is not skipped and the generated lexer reports the error.
+    fn skip_format_specifier(&mut self) {
+        let base: &mut BaseLexer<'input, _, Input, _> = &mut self.base;
+        if base.mode != yarnspinnerlexer::ExpressionMode
+            || base.mode_stack.last() != Some(&yarnspinnerlexer::TextMode)
+        {
+            return;
+        }
+        let (Some(input), Some(interpreter)) = (base.input.as_mut(), base.interpreter.as_ref())
+        else {
+            return;
+        };
+        if input.la(1) != ':' as isize {
+            return;
+        }
+        let mut format_specifier = String::new();
+        let mut length = 1;
+        loop {
+            let next = input.la(length + 1);
+            if next == '}' as isize {
+                break;
+            }
+            match u32::try_from(next).ok().and_then(char::from_u32) {
+                Some('\n' | '\r' | '{') | None => return,
+                Some(c) => format_specifier.push(c),
+            }
+            length += 1;
+        }
+        for _ in 0..length {
+            interpreter.consume(input);
+        }
+        let format_specifier = format_specifier.trim();
+        if !format_specifier.is_empty() {
+            self.format_specifiers
+                .borrow_mut()
+                .insert(input.index() as usize, format_specifier.to_owned());
+        }
+    }
+
+    /// Returns the next token that has not been processed yet.
+    fn next_unprocessed_token(
+        &mut self,
+    ) -> Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>> {
+        self.lookahead_tokens
+            .dequeue()
+            .unwrap_or_else(|| self.next_base_token())
+    }
+
+    /// Puts tokens back so that they are processed next, in the given order.
+    fn unread_tokens(
+        &mut self,
+        tokens: impl DoubleEndedIterator<
+            Item = Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
+        >,
+    ) {
+        for token in tokens.rev() {
+            self.lookahead_tokens.0.push_front(token);
+        }
+    }
+
     fn handle_eof_token(
         &mut self,
         current_token: Box<antlr_rust::token::GenericToken<std::borrow::Cow<'input, str>>>,
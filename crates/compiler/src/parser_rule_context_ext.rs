@@ -51,9 +51,11 @@ pub(crate) trait ParserRuleContextExt<'input>: ParserRuleContext<'input> {
             .map(|(byte_start, _)| byte_start)
             .nth(char_start)
             .unwrap();
+        // The context may end with the text, e.g. when it was parsed on its own
         let byte_stop = whole_file
             .char_indices()
             .map(|(byte_start, _)| byte_start)
+            .chain(iter::once(whole_file.len()))
             .nth(char_stop)
             .unwrap();
         let first_line = self.start().get_line_as_usize().saturating_sub(1);
@@ -1,14 +1,22 @@
 mod code_generation_visitor;
 mod constant_value_visitor;
 mod declaration_visitor;
+mod detour;
+mod enum_visitor;
 mod hashable_interval;
 mod last_line_before_options_visitor;
+mod line_group;
+mod local_variable;
+mod node_group;
 mod node_tracking_visitor;
+mod once_visitor;
+mod smart_variable;
 mod string_table_generator_visitor;
 mod type_check_visitor;
 
 pub(crate) use self::{
-    code_generation_visitor::*, declaration_visitor::*, hashable_interval::*,
-    last_line_before_options_visitor::*, node_tracking_visitor::*,
+    code_generation_visitor::*, declaration_visitor::*, detour::*, enum_visitor::*,
+    hashable_interval::*, last_line_before_options_visitor::*, line_group::*, local_variable::*,
+    node_group::*, node_tracking_visitor::*, once_visitor::*, smart_variable::*,
     string_table_generator_visitor::*, type_check_visitor::*,
 };
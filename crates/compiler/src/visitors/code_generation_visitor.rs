@@ -5,6 +5,12 @@ use crate::prelude::generated::yarnspinnerlexer;
 use crate::prelude::generated::yarnspinnerparser::*;
 use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
 use crate::prelude::*;
+use crate::visitors::{
+    DetourCommand, DetourDestination, EnumCaseReference, EnumCommand, NodeGroupMember, OnceCommand,
+    generate_unique_once_variable_for_block, generate_unique_once_variable_for_line,
+    get_condition_complexity, get_line_group_starting_at, has_once_condition, is_line_group_item,
+    is_local_variable_declaration, is_smart_variable_declaration,
+};
 use antlr_rust::parser_rule_context::ParserRuleContext;
 use antlr_rust::token::Token;
 use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat, Tree};
@@ -17,9 +23,19 @@ use yarnspinner_core::types::Type;
 pub(crate) struct CodeGenerationVisitor<'a, 'input: 'a> {
     compiler_listener: &'a mut CompilerListener<'input>,
     tracking_enabled: Option<String>,
+    /// The labels needed to finish the currently open `<<once>>` blocks.
+    open_once_blocks: Vec<OnceBlockLabel>,
     _dummy: (),
 }
 
+/// The label needed to finish an open `<<once>>` block.
+enum OnceBlockLabel {
+    /// The label to jump to when skipping the statements that are only run the first time.
+    Skip(String),
+    /// The label of the end of the block, once an `<<else>>` was found.
+    End(String),
+}
+
 impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
     pub(crate) fn new(
         compiler_listener: &'a mut CompilerListener<'input>,
@@ -28,6 +44,7 @@ impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
         Self {
             compiler_listener,
             tracking_enabled: tracking_enabled.into(),
+            open_once_blocks: Default::default(),
             _dummy: Default::default(),
         }
     }
@@ -87,28 +104,69 @@ impl<'a, 'input: 'a> ParseTreeVisitorCompat<'input> for CodeGenerationVisitor<'a
 impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVisitor<'a, 'input> {
     /// a regular ol' line of text
     fn visit_line_statement(&mut self, ctx: &Line_statementContext<'input>) -> Self::Return {
-        // [sic] TODO: add support for line conditions:
+        // A line condition, i.e.
         //
         // Mae: here's a line <<if true>>
         //
         // is identical to
         //
         // <<if true>> Mae: here's a line <<endif>>
+        if is_line_group_item(ctx) {
+            // All variants of a line group are generated together when visiting the first one.
+            if let Some(line_group) = get_line_group_starting_at(ctx) {
+                self.generate_code_for_line_group(&line_group);
+            }
+            return;
+        }
+        let line_id = get_line_id(ctx);
+        let once_variable =
+            has_once_condition(ctx).then(|| generate_unique_once_variable_for_line(&line_id));
+
+        let skip_line_label = ctx
+            .line_condition()
+            .and_then(|condition| condition.expression())
+            .map(|expression| {
+                let skip_line_label = self.compiler_listener.register_label("skipline");
+                let token = expression.start();
+                match once_variable.clone() {
+                    Some(once_variable) => {
+                        self.generate_code_for_once_check(once_variable, token.deref())
+                    }
+                    None => self.visit(expression.as_ref()),
+                }
+                self.compiler_listener.emit(
+                    Emit::from_op_code(OpCode::JumpIfFalse)
+                        .with_token(token.deref())
+                        .with_operand(skip_line_label.clone()),
+                );
+                self.compiler_listener
+                    .emit(Emit::from_op_code(OpCode::Pop).with_token(token.deref()));
+                skip_line_label
+            });
+        if let Some(once_variable) = once_variable {
+            self.generate_code_for_once_mark(once_variable, ctx.start().deref());
+        }
 
-        // Evaluate the inline expressions and push the results onto the
-        // stack.
-        let formatted_text = ctx.line_formatted_text().unwrap();
-        let expression_count =
-            self.generate_code_for_expressions_in_formatted_text(formatted_text.get_children());
-        let line_id_tag = get_line_id_tag(&ctx.hashtag_all())
-            .expect_or_bug("Internal error: line should have an implicit or explicit line ID tag, but none was found.");
-        let line_id = line_id_tag.text.as_ref().unwrap().get_text().to_owned();
-        self.compiler_listener.emit(
-            Emit::from_op_code(OpCode::RunLine)
-                .with_token(ctx.start().deref())
-                .with_operand(line_id)
-                .with_operand(expression_count),
-        );
+        self.generate_code_for_run_line(ctx, line_id);
+
+        if let Some(skip_line_label) = skip_line_label {
+            let end_of_line_label = self.compiler_listener.register_label("endline");
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::JumpTo)
+                    .with_token(ctx.stop().deref())
+                    .with_operand(end_of_line_label.clone()),
+            );
+            let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+            current_node
+                .labels
+                .insert(skip_line_label, current_node.instructions.len() as i32);
+            self.compiler_listener
+                .emit(Emit::from_op_code(OpCode::Pop).with_token(ctx.stop().deref()));
+            let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+            current_node
+                .labels
+                .insert(end_of_line_label, current_node.instructions.len() as i32);
+        }
     }
 
     /// (expression)
@@ -264,6 +322,26 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
 
     fn visit_variable(&mut self, ctx: &VariableContext<'input>) -> Self::Return {
         let variable_name = ctx.VAR_ID().unwrap().get_text();
+        if let Some(enum_case_reference) = EnumCaseReference::parse(&variable_name) {
+            // Enum cases are represented by their raw values
+            let Some(Type::Enum(enum_type)) = self.compiler_listener.types.get(ctx) else {
+                bug!("Internal error: enum case {variable_name} was not resolved to an enum.");
+            };
+            let emit = match enum_case_reference.raw_value(enum_type) {
+                YarnValue::Number(number) => {
+                    Emit::from_op_code(OpCode::PushFloat).with_operand(number)
+                }
+                YarnValue::String(string) => {
+                    Emit::from_op_code(OpCode::PushString).with_operand(string)
+                }
+                YarnValue::Boolean(_) => {
+                    bug!("Internal error: enum case {variable_name} has a boolean raw value.")
+                }
+            };
+            self.compiler_listener
+                .emit(emit.with_token(ctx.start().deref()));
+            return;
+        }
         self.compiler_listener.emit(
             Emit::from_op_code(OpCode::PushVariable)
                 .with_token(ctx.start().deref())
@@ -342,6 +420,10 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
 
     /// A set command: explicitly setting a value to an expression <<set $foo to 1>>
     fn visit_set_statement(&mut self, ctx: &Set_statementContext<'input>) -> Self::Return {
+        if is_smart_variable_declaration(ctx) {
+            // Smart variables are not stored, their expression is compiled into a node of its own instead.
+            return;
+        }
         // Ensure that the correct result is on the stack by evaluating the
         // expression. If this assignment includes an operation (e.g. +=),
         // do that work here too.
@@ -390,8 +472,13 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
         // now store the variable and clean up the stack
         let variable_name = variable.get_text();
         let token = variable.start();
+        let store_op_code = if is_local_variable_declaration(ctx) {
+            OpCode::StoreLocalVariable
+        } else {
+            OpCode::StoreVariable
+        };
         self.compiler_listener.emit(
-            Emit::from_op_code(OpCode::StoreVariable)
+            Emit::from_op_code(store_op_code)
                 .with_token(token.deref())
                 .with_operand(variable_name),
         );
@@ -411,6 +498,14 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
     /// like <<turn fred left>> or <<unlockAchievement FacePlant>>
     fn visit_command_statement(&mut self, ctx: &Command_statementContext<'input>) -> Self::Return {
         let formatted_text = ctx.command_formatted_text().unwrap();
+        if let Some(once_command) = OnceCommand::parse(&formatted_text.get_text()) {
+            self.generate_code_for_once_command(once_command, ctx, formatted_text.start().deref());
+            return;
+        }
+        // Enum declarations only exist at compile time
+        if EnumCommand::parse(&formatted_text.get_text()).is_some() {
+            return;
+        }
         let (composed_string, expression_count) = formatted_text.get_children().fold(
             (String::new(), 0_usize),
             |(composed_string, expression_count), node| {
@@ -430,6 +525,11 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
             },
         );
 
+        if let Some(detour_command) = DetourCommand::parse(&composed_string, expression_count) {
+            self.generate_code_for_detour_command(detour_command, &formatted_text);
+            return;
+        }
+
         // [sic] TODO: look into replacing this as it seems a bit odd
         match composed_string.as_str() {
             "stop" => {
@@ -457,6 +557,7 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
     ) -> Self::Return {
         let end_of_group_label = self.compiler_listener.register_label("group_end");
         let mut labels = Vec::new();
+        let mut once_variables = Vec::new();
 
         // For each option, create an internal destination label that, if
         // the user selects the option, control flow jumps to. Then,
@@ -480,16 +581,31 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
                 .register_label(format!("shortcutoption_{name}_{}", option_count + 1).as_str());
             labels.push(option_destination_label.clone());
 
+            // Get the line ID from the hashtags if it has one
+            let line_statement = shortcut.line_statement().unwrap();
+            let line_id_tag = get_line_id_tag(&line_statement.hashtag_all())
+                .expect_or_bug("Internal error: no line ID provided.");
+            let line_id = line_id_tag.text.as_ref().unwrap().get_text().to_owned();
+
+            // An option marked with `<<once>>` is only available until it has been selected once.
+            let once_variable = has_once_condition(&line_statement)
+                .then(|| generate_unique_once_variable_for_line(&line_id));
+            once_variables.push(once_variable.clone());
+
             // This line statement may have a condition on it. If it does,
             // emit code that evaluates the condition, and add a flag on the
             // 'Add Option' instruction that indicates that a condition exists.
-            let has_line_condition = if let Some(expression) = shortcut
-                .line_statement()
-                .and_then(|ctx| ctx.line_condition())
+            let has_line_condition = if let Some(expression) = line_statement
+                .line_condition()
                 .and_then(|ctx| ctx.expression())
             {
                 // Evaluate the condition, and leave it on the stack
-                self.visit(expression.as_ref());
+                match once_variable {
+                    Some(once_variable) => {
+                        self.generate_code_for_once_check(once_variable, expression.start().deref())
+                    }
+                    None => self.visit(expression.as_ref()),
+                }
                 true
             } else {
                 false
@@ -499,16 +615,10 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
 
             // Start by figuring out the text that we want to add. This will
             // involve evaluating any inline expressions.
-            let line_statement = shortcut.line_statement().unwrap();
             let expression_count = self.generate_code_for_expressions_in_formatted_text(
                 line_statement.line_formatted_text().unwrap().get_children(),
             );
 
-            // Get the line ID from the hashtags if it has one
-            let line_id_tag = get_line_id_tag(&line_statement.hashtag_all())
-                .expect_or_bug("Internal error: no line ID provided.");
-            let line_id = line_id_tag.text.as_ref().unwrap().get_text().to_owned();
-
             // And add this option to the list.
             self.compiler_listener.emit(
                 Emit::from_op_code(OpCode::AddOption)
@@ -538,6 +648,11 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
                 current_node.instructions.len() as i32,
             );
 
+            // Selecting an option marked with `<<once>>` makes it unavailable from now on
+            if let Some(once_variable) = once_variables[option_count].clone() {
+                self.generate_code_for_once_mark(once_variable, shortcut.start().deref());
+            }
+
             // Run through all the children statements of the shortcut option
             for child in shortcut.statement_all() {
                 self.visit(child.as_ref());
@@ -593,6 +708,225 @@ impl<'a, 'input: 'a> YarnSpinnerParserVisitorCompat<'input> for CodeGenerationVi
 }
 
 impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
+    fn generate_code_for_run_line(&mut self, ctx: &Line_statementContext<'input>, line_id: String) {
+        // Evaluate the inline expressions and push the results onto the
+        // stack.
+        let formatted_text = ctx.line_formatted_text().unwrap();
+        let expression_count =
+            self.generate_code_for_expressions_in_formatted_text(formatted_text.get_children());
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::RunLine)
+                .with_token(ctx.start().deref())
+                .with_operand(line_id)
+                .with_operand(expression_count),
+        );
+    }
+
+    /// => a line
+    /// => another line <<if $condition>>
+    ///
+    /// Every variant whose condition passes becomes a candidate, and the saliency strategy of the runtime selects which one is run.
+    fn generate_code_for_line_group(&mut self, variants: &[Rc<Line_statementContext<'input>>]) {
+        let first_token = variants[0].start();
+        let last_token = variants[variants.len() - 1].stop();
+        let no_variant_label = self.compiler_listener.register_label("linegroup_none");
+        let end_of_group_label = self.compiler_listener.register_label("linegroup_end");
+
+        // Offer every variant as a candidate, together with the result of its condition
+        let mut variant_labels = Vec::with_capacity(variants.len());
+        for variant in variants {
+            let line_id = get_line_id(variant);
+            let once_variable = has_once_condition(variant)
+                .then(|| generate_unique_once_variable_for_line(&line_id));
+            let token = variant.start();
+            let condition = variant
+                .line_condition()
+                .and_then(|condition| condition.expression());
+            let complexity_score = match (&once_variable, condition) {
+                (Some(once_variable), _) => {
+                    self.generate_code_for_once_check(once_variable.clone(), token.deref());
+                    1
+                }
+                (None, Some(condition)) => {
+                    self.visit(condition.as_ref());
+                    get_condition_complexity(&condition)
+                }
+                (None, None) => {
+                    self.compiler_listener.emit(
+                        Emit::from_op_code(OpCode::PushBool)
+                            .with_token(token.deref())
+                            .with_operand(true),
+                    );
+                    0
+                }
+            };
+            let variant_label = self.compiler_listener.register_label("linegroup_item");
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::AddSaliencyCandidate)
+                    .with_token(token.deref())
+                    .with_operand(line_id)
+                    .with_operand(complexity_score)
+                    .with_operand(variant_label.clone()),
+            );
+            variant_labels.push((variant_label, once_variable));
+        }
+
+        // Jump to the selected variant, if any
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::SelectSaliencyCandidate).with_token(first_token.deref()),
+        );
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::JumpIfFalse)
+                .with_token(first_token.deref())
+                .with_operand(no_variant_label.clone()),
+        );
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Pop).with_token(first_token.deref()));
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Jump).with_token(first_token.deref()));
+
+        for (variant, (variant_label, once_variable)) in variants.iter().zip(variant_labels) {
+            let token = variant.start();
+            let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+            current_node
+                .labels
+                .insert(variant_label, current_node.instructions.len() as i32);
+            // Pop the destination we jumped with
+            self.compiler_listener
+                .emit(Emit::from_op_code(OpCode::Pop).with_token(token.deref()));
+            if let Some(once_variable) = once_variable {
+                self.generate_code_for_once_mark(once_variable, token.deref());
+            }
+            self.generate_code_for_run_line(variant, get_line_id(variant));
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::JumpTo)
+                    .with_token(variant.stop().deref())
+                    .with_operand(end_of_group_label.clone()),
+            );
+        }
+
+        // No variant was selected, so pop the `false` left by the selection
+        let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+        current_node
+            .labels
+            .insert(no_variant_label, current_node.instructions.len() as i32);
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Pop).with_token(last_token.deref()));
+        let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+        current_node
+            .labels
+            .insert(end_of_group_label, current_node.instructions.len() as i32);
+    }
+
+    /// title: Greeting
+    /// when: $met_player
+    ///
+    /// Generates the body of the node that stands in for a node group: every member whose condition passes becomes a candidate,
+    /// and the saliency strategy of the runtime selects which one is run.
+    /// The code before the [`OpCode::SelectSaliencyCandidate`] only evaluates the conditions, so that the runtime can query the candidates without side effects.
+    pub(crate) fn generate_code_for_node_group(
+        &mut self,
+        members: &[NodeGroupMember<'input>],
+        token: &(impl Token + ?Sized),
+    ) {
+        let no_member_label = self.compiler_listener.register_label("nodegroup_none");
+
+        // Offer every member as a candidate, together with the result of its condition
+        let mut member_labels = Vec::with_capacity(members.len());
+        for member in members {
+            let once_variable = member
+                .is_once
+                .then(|| generate_unique_once_variable_for_line(&member.name));
+            let mut complexity_score = 0;
+            for condition in &member.conditions {
+                self.visit(condition.as_ref());
+                complexity_score += get_condition_complexity(condition);
+            }
+            if let Some(once_variable) = &once_variable {
+                self.generate_code_for_once_check(once_variable.clone(), token);
+                complexity_score += 1;
+            }
+            let check_count = member.conditions.len() + usize::from(once_variable.is_some());
+            if check_count == 0 {
+                self.compiler_listener.emit(
+                    Emit::from_op_code(OpCode::PushBool)
+                        .with_token(token)
+                        .with_operand(true),
+                );
+            }
+            // All checks must pass
+            let function_name =
+                Type::Boolean.get_canonical_name_for_method(&Operator::And.to_string());
+            for _ in 1..check_count {
+                self.compiler_listener.emit(
+                    Emit::from_op_code(OpCode::PushFloat)
+                        .with_token(token)
+                        .with_operand(2_usize),
+                );
+                self.compiler_listener.emit(
+                    Emit::from_op_code(OpCode::CallFunc)
+                        .with_token(token)
+                        .with_operand(function_name.clone()),
+                );
+            }
+            let member_label = self.compiler_listener.register_label("nodegroup_item");
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::AddSaliencyCandidate)
+                    .with_token(token)
+                    .with_operand(member.name.clone())
+                    .with_operand(complexity_score)
+                    .with_operand(member_label.clone()),
+            );
+            member_labels.push((member_label, once_variable));
+        }
+
+        // Jump to the selected member, if any
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::SelectSaliencyCandidate).with_token(token));
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::JumpIfFalse)
+                .with_token(token)
+                .with_operand(no_member_label.clone()),
+        );
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Pop).with_token(token));
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Jump).with_token(token));
+
+        for (member, (member_label, once_variable)) in members.iter().zip(member_labels) {
+            let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+            current_node
+                .labels
+                .insert(member_label, current_node.instructions.len() as i32);
+            // Pop the destination we jumped with
+            self.compiler_listener
+                .emit(Emit::from_op_code(OpCode::Pop).with_token(token));
+            if let Some(once_variable) = once_variable {
+                self.generate_code_for_once_mark(once_variable, token);
+            }
+            if let Some(track) = self.tracking_enabled.clone() {
+                Self::generate_tracking_code(self.compiler_listener, track);
+            }
+            self.compiler_listener.emit(
+                Emit::from_op_code(OpCode::PushString)
+                    .with_token(token)
+                    .with_operand(member.name.clone()),
+            );
+            self.compiler_listener
+                .emit(Emit::from_op_code(OpCode::RunNode).with_token(token));
+        }
+
+        // No member was selected, so pop the `false` left by the selection and leave the node
+        let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+        current_node
+            .labels
+            .insert(no_member_label, current_node.instructions.len() as i32);
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Pop).with_token(token));
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Return).with_token(token));
+    }
+
     fn generate_code_for_expressions_in_formatted_text(
         &mut self,
         nodes: impl Iterator<Item = Rc<ActualParserContext<'input>>>,
@@ -653,6 +987,164 @@ impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
         );
     }
 
+    /// Emits code that leaves `true` on the stack if the content guarded by the given once variable has not been run yet.
+    fn generate_code_for_once_check(
+        &mut self,
+        once_variable: String,
+        token: &(impl Token + ?Sized),
+    ) {
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::PushVariable)
+                .with_token(token)
+                .with_operand(once_variable),
+        );
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::PushFloat)
+                .with_token(token)
+                .with_operand(1_usize),
+        );
+        let function_name = Type::Boolean.get_canonical_name_for_method(&Operator::Not.to_string());
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::CallFunc)
+                .with_token(token)
+                .with_operand(function_name),
+        );
+    }
+
+    /// Emits code that marks the content guarded by the given once variable as run.
+    fn generate_code_for_once_mark(
+        &mut self,
+        once_variable: String,
+        token: &(impl Token + ?Sized),
+    ) {
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::PushBool)
+                .with_token(token)
+                .with_operand(true),
+        );
+        self.compiler_listener.emit(
+            Emit::from_op_code(OpCode::StoreVariable)
+                .with_token(token)
+                .with_operand(once_variable),
+        );
+        self.compiler_listener
+            .emit(Emit::from_op_code(OpCode::Pop).with_token(token));
+    }
+
+    /// <<detour NodeName>>, <<detour {expression}>> or <<return>>
+    ///
+    /// A detour runs the node and then continues after the command, while a return goes back to the node that detoured to the current one.
+    fn generate_code_for_detour_command(
+        &mut self,
+        detour_command: DetourCommand,
+        formatted_text: &Command_formatted_textContext<'input>,
+    ) {
+        let token = formatted_text.start();
+        match detour_command {
+            DetourCommand::Detour(DetourDestination::NodeName(node_name)) => {
+                self.compiler_listener.emit(
+                    Emit::from_op_code(OpCode::PushString)
+                        .with_token(token.deref())
+                        .with_operand(node_name),
+                );
+                self.compiler_listener
+                    .emit(Emit::from_op_code(OpCode::DetourToNode).with_token(token.deref()));
+            }
+            DetourCommand::Detour(DetourDestination::Expression) => {
+                // The expression was already evaluated, so its result is on the stack
+                self.compiler_listener
+                    .emit(Emit::from_op_code(OpCode::DetourToNode).with_token(token.deref()));
+            }
+            DetourCommand::Detour(DetourDestination::Invalid) => {
+                self.compiler_listener.diagnostics.borrow_mut().push(
+                    Diagnostic::from_message(
+                        "<<detour>> must be followed by the name of a node or a single expression in braces",
+                    )
+                    .with_file_name(self.compiler_listener.file.name.clone())
+                    .with_parser_context(formatted_text, self.compiler_listener.file.tokens()),
+                );
+            }
+            DetourCommand::Return => {
+                // Returning leaves the node, just like a jump does
+                if let Some(tracking_enabled) = self.tracking_enabled.clone() {
+                    Self::generate_tracking_code(self.compiler_listener, tracking_enabled);
+                }
+                self.compiler_listener
+                    .emit(Emit::from_op_code(OpCode::Return).with_token(token.deref()));
+            }
+        }
+    }
+
+    /// <<once>> statements <<else>> statements <<endonce>>
+    ///
+    /// The statements before the optional `<<else>>` are only run the first time the block is reached,
+    /// the ones after it every time after that.
+    fn generate_code_for_once_command(
+        &mut self,
+        once_command: OnceCommand,
+        ctx: &Command_statementContext<'input>,
+        token: &(impl Token + ?Sized),
+    ) {
+        match once_command {
+            OnceCommand::Once => {
+                let node_name = self
+                    .compiler_listener
+                    .current_node
+                    .as_ref()
+                    .map(|node| node.name.clone())
+                    .unwrap_or_default();
+                let once_variable = generate_unique_once_variable_for_block(ctx, &node_name);
+
+                let skip_once_label = self.compiler_listener.register_label("skiponce");
+                self.generate_code_for_once_check(once_variable.clone(), token);
+                self.compiler_listener.emit(
+                    Emit::from_op_code(OpCode::JumpIfFalse)
+                        .with_token(token)
+                        .with_operand(skip_once_label.clone()),
+                );
+                self.compiler_listener
+                    .emit(Emit::from_op_code(OpCode::Pop).with_token(token));
+                self.generate_code_for_once_mark(once_variable, token);
+                self.open_once_blocks
+                    .push(OnceBlockLabel::Skip(skip_once_label));
+            }
+            OnceCommand::Else | OnceCommand::EndOnce => {
+                // Unbalanced blocks were already reported as errors, which prevents code generation
+                let open_once_block = self.open_once_blocks.pop().expect_or_bug(
+                    "Internal error: found <<else>> or <<endonce>> without an open <<once>> block during code generation.",
+                );
+                let end_of_once_label = match open_once_block {
+                    OnceBlockLabel::Skip(skip_once_label) => {
+                        let end_of_once_label = self.compiler_listener.register_label("endonce");
+                        self.compiler_listener.emit(
+                            Emit::from_op_code(OpCode::JumpTo)
+                                .with_token(token)
+                                .with_operand(end_of_once_label.clone()),
+                        );
+                        let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+                        current_node
+                            .labels
+                            .insert(skip_once_label, current_node.instructions.len() as i32);
+                        self.compiler_listener
+                            .emit(Emit::from_op_code(OpCode::Pop).with_token(token));
+                        end_of_once_label
+                    }
+                    // The `<<else>>` of the block already skipped over the statements that are only run the first time
+                    OnceBlockLabel::End(end_of_once_label) => end_of_once_label,
+                };
+                if once_command == OnceCommand::Else {
+                    self.open_once_blocks
+                        .push(OnceBlockLabel::End(end_of_once_label));
+                } else {
+                    let current_node = self.compiler_listener.current_node.as_mut().unwrap();
+                    current_node
+                        .labels
+                        .insert(end_of_once_label, current_node.instructions.len() as i32);
+                }
+            }
+        }
+    }
+
     fn generate_code_for_clause(
         &mut self,
         jump_label: String,
@@ -695,3 +1187,11 @@ impl<'a, 'input: 'a> CodeGenerationVisitor<'a, 'input> {
         }
     }
 }
+
+/// Returns the line ID of the given line. Lines without an explicit one were given an implicit one by the [`StringTableGeneratorVisitor`](crate::visitors::StringTableGeneratorVisitor).
+fn get_line_id(ctx: &Line_statementContext) -> String {
+    let line_id_tag = get_line_id_tag(&ctx.hashtag_all()).expect_or_bug(
+        "Internal error: line should have an implicit or explicit line ID tag, but none was found.",
+    );
+    line_id_tag.text.as_ref().unwrap().get_text().to_owned()
+}
@@ -3,11 +3,13 @@
 use crate::prelude::generated::yarnspinnerparser::*;
 use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
 use crate::prelude::*;
+use crate::visitors::EnumCaseReference;
 use antlr_rust::parser::ParserNodeType;
 use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat, VisitChildren};
 use std::mem;
 use std::ops::{Deref, DerefMut};
 use yarnspinner_core::prelude::*;
+use yarnspinner_core::types::EnumType;
 
 /// A visitor that visits any valid constant value, and returns a [`InternalValue`].
 /// Currently only supports terminals, not expressions,
@@ -16,13 +18,20 @@ use yarnspinner_core::prelude::*;
 pub(crate) struct ConstantValueVisitor<'input> {
     pub(crate) diagnostics: Vec<Diagnostic>,
     _dummy: ConstantValue,
+    /// The enums whose cases can be used as constant values.
+    enums: Vec<EnumType>,
     file: FileParseResult<'input>,
 }
 
 impl<'input> ConstantValueVisitor<'input> {
-    pub(crate) fn new(diagnostics: Vec<Diagnostic>, file: FileParseResult<'input>) -> Self {
+    pub(crate) fn new(
+        diagnostics: Vec<Diagnostic>,
+        enums: Vec<EnumType>,
+        file: FileParseResult<'input>,
+    ) -> Self {
         Self {
             diagnostics,
+            enums,
             file,
             _dummy: ConstantValue::non_panicking_default(),
         }
@@ -75,6 +84,23 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for ConstantValueVisitor<'in
 
     fn visit_valueVar(&mut self, ctx: &ValueVarContext<'input>) -> Self::Return {
         let text = ctx.get_text();
+        if let Some(enum_case_reference) = EnumCaseReference::parse(&text) {
+            return match enum_case_reference.resolve(&self.enums, None) {
+                Ok(enum_type) => InternalValue {
+                    raw_value: enum_case_reference.raw_value(&enum_type),
+                    r#type: Type::Enum(enum_type),
+                }
+                .into(),
+                Err(message) => {
+                    self.diagnostics.push(
+                        Diagnostic::from_message(message)
+                            .with_file_name(&self.file.name)
+                            .with_parser_context(ctx, self.file.tokens()),
+                    );
+                    ConstantValue::non_panicking_default()
+                }
+            };
+        }
         let message = format!(
             "Variable declarations must be constant values, but `{text}` is another variable",
         );
@@ -4,6 +4,7 @@ use crate::prelude::generated::yarnspinnerparser::*;
 use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
 use crate::prelude::*;
 use crate::visitors::constant_value_visitor::ConstantValueVisitor;
+use crate::visitors::{SmartVariableDeclaration, is_smart_variable_declaration};
 use antlr_rust::token::Token;
 use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat};
 use regex::Regex;
@@ -21,6 +22,9 @@ pub(crate) struct DeclarationVisitor<'input> {
     /// [`ParserRuleContext`].
     pub(crate) new_declarations: Vec<Declaration>,
 
+    /// The smart variables that were found. Their types are not known yet, so they are not part of [`Self::new_declarations`].
+    pub(crate) smart_variables: Vec<SmartVariableDeclaration<'input>>,
+
     /// Gets the collection of file-level hashtags that were found as a
     /// result of using this  [`DeclarationVisitor`] to visit a [`ParserRuleContext`].
     pub(crate) file_tags: Vec<String>,
@@ -34,6 +38,9 @@ pub(crate) struct DeclarationVisitor<'input> {
     /// The collection of variable declarations we know about before starting our work
     existing_declarations: Vec<Declaration>,
 
+    /// The enums declared in the Yarn files, which can be used as types and values of declarations.
+    enums: Vec<EnumType>,
+
     /// The name of the node that we're currently visiting.
     current_node_name: Option<String>,
 
@@ -46,12 +53,15 @@ pub(crate) struct DeclarationVisitor<'input> {
 impl<'input> DeclarationVisitor<'input> {
     pub(crate) fn new(
         existing_declarations: Vec<Declaration>,
+        enums: Vec<EnumType>,
         file: FileParseResult<'input>,
     ) -> Self {
         Self {
             file,
             existing_declarations,
+            enums,
             new_declarations: Default::default(),
+            smart_variables: Default::default(),
             regex: Regex::new(r"[\[<>\]{}|:\s#$]").unwrap(),
             file_tags: Default::default(),
             diagnostics: Default::default(),
@@ -69,6 +79,29 @@ impl<'input> DeclarationVisitor<'input> {
             .cloned()
             .collect()
     }
+
+    /// Returns an error if the variable has already been declared explicitly,
+    /// because you can't have two explicit declarations for the same variable.
+    fn diagnose_existing_explicit_declaration(&self, variable_name: &str) -> Option<Diagnostic> {
+        let existing_explicit_declaration = self
+            .declarations()
+            .into_iter()
+            .chain(
+                self.smart_variables
+                    .iter()
+                    .map(|smart_variable| smart_variable.declaration.clone()),
+            )
+            .find(|d| !d.is_implicit && d.name == variable_name)?;
+        let line = existing_explicit_declaration
+            .source_file_line()
+            .map(|l| format!(", line: {l}"))
+            .unwrap_or_default();
+        let msg = format!(
+            "{} has already been declared in {}{line}",
+            existing_explicit_declaration.name, existing_explicit_declaration.source_file_name,
+        );
+        Some(Diagnostic::from_message(msg).with_file_name(&self.file.name))
+    }
 }
 
 impl<'input> ParseTreeVisitorCompat<'input> for DeclarationVisitor<'input> {
@@ -117,31 +150,18 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
         let variable_name = variable_context.get_text();
 
         // Does this variable name already exist in our declarations?
-        let existing_explicit_declaration = self
-            .declarations()
-            .into_iter()
-            .find(|d| !d.is_implicit && d.name == variable_name);
-        if let Some(existing_explicit_declaration) = existing_explicit_declaration {
-            // Then this is an error, because you can't have two explicit declarations for the same variable.
-            let line = existing_explicit_declaration
-                .source_file_line()
-                .map(|l| format!(", line: {l}"))
-                .unwrap_or_default();
-            let msg = format!(
-                "{} has already been declared in {}{line}",
-                existing_explicit_declaration.name, existing_explicit_declaration.source_file_name,
-            );
-            self.diagnostics.push(
-                Diagnostic::from_message(msg)
-                    .with_file_name(&self.file.name)
-                    .with_parser_context(ctx, self.file.tokens()),
-            );
+        if let Some(diagnostic) = self.diagnose_existing_explicit_declaration(&variable_name) {
+            self.diagnostics
+                .push(diagnostic.with_parser_context(ctx, self.file.tokens()));
             return;
         }
 
         // Figure out the value and its type
-        let mut constant_value_visitor =
-            ConstantValueVisitor::new(self.diagnostics.clone(), self.file.clone());
+        let mut constant_value_visitor = ConstantValueVisitor::new(
+            self.diagnostics.clone(),
+            self.enums.clone(),
+            self.file.clone(),
+        );
         let value_context = ctx.value().unwrap();
         let value = constant_value_visitor.visit(value_context.as_ref());
         self.diagnostics
@@ -156,9 +176,11 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
                 // type. Look for the type in our type collection.
                 None => match Type::EXPLICITLY_CONSTRUCTABLE
                     .iter()
+                    .cloned()
+                    .chain(self.enums.iter().cloned().map(Type::Enum))
                     .find(|t| t.to_string() == declaration_type.get_text())
                 {
-                    Some(explicit_type) => explicit_type.clone(),
+                    Some(explicit_type) => explicit_type,
                     None => {
                         // We didn't find a type by this name.
                         let msg = format!("Unknown type {}", declaration_type.get_text());
@@ -205,6 +227,33 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for DeclarationVisitor<'inpu
             self.new_declarations.push(declaration);
         }
     }
+
+    fn visit_set_statement(&mut self, ctx: &Set_statementContext<'input>) -> Self::Return {
+        if !is_smart_variable_declaration(ctx) {
+            return;
+        }
+        let variable_context = ctx.variable().unwrap();
+        let variable_name = variable_context.get_text();
+        if let Some(diagnostic) = self.diagnose_existing_explicit_declaration(&variable_name) {
+            self.diagnostics
+                .push(diagnostic.with_parser_context(ctx, self.file.tokens()));
+            return;
+        }
+
+        // The type is determined later by type checking the expression
+        let description = get_document_comments(self.file.tokens(), ctx);
+        let description_as_option = (!description.is_empty()).then_some(description);
+        let declaration = Declaration::new(variable_name, Type::Any)
+            .with_description_optional(description_as_option)
+            .with_source_file_name(self.file.name.clone())
+            .with_source_node_name_optional(self.current_node_name.clone())
+            .with_range(variable_context.range())
+            .with_smart_variable();
+        self.smart_variables.push(SmartVariableDeclaration {
+            declaration,
+            expression: ctx.expression().unwrap(),
+        });
+    }
 }
 
 fn keyword_to_type(keyword: &str) -> Option<Type> {
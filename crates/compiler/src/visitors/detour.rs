@@ -0,0 +1,47 @@
+//! Recognizes `<<detour>>` and `<<return>>` statements, which run another node and come back from it.
+//!
+//! ## Implementation notes
+//!
+//! Our parser is generated from a grammar that predates detours, so `<<detour>>` and `<<return>>` are parsed as regular commands
+//! and recognized by their text after the expressions in them have been generated, like `<<stop>>`.
+
+/// The commands that enter a node as a subroutine and leave it again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DetourCommand {
+    /// `<<detour NodeName>>` or `<<detour {$expression}>>`
+    Detour(DetourDestination),
+    /// `<<return>>`
+    Return,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DetourDestination {
+    /// The name of the node was written directly in the command.
+    NodeName(String),
+    /// The name of the node is the result of the command's only expression, which is already on the stack.
+    Expression,
+    /// The command is a detour, but its destination is not a single node name or expression.
+    Invalid,
+}
+
+impl DetourCommand {
+    /// Parses the text of a command in which each expression has been replaced by its index, e.g. `detour {0}`.
+    pub(crate) fn parse(composed_text: &str, expression_count: usize) -> Option<Self> {
+        let composed_text = composed_text.trim();
+        if composed_text == "return" {
+            return Some(Self::Return);
+        }
+        if composed_text == "detour" {
+            return Some(Self::Detour(DetourDestination::Invalid));
+        }
+        let destination = composed_text.strip_prefix("detour ")?.trim();
+        let destination = match expression_count {
+            0 if !destination.contains(char::is_whitespace) => {
+                DetourDestination::NodeName(destination.to_owned())
+            }
+            1 if destination == "{0}" => DetourDestination::Expression,
+            _ => DetourDestination::Invalid,
+        };
+        Some(Self::Detour(destination))
+    }
+}
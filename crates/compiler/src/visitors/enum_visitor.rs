@@ -0,0 +1,338 @@
+//! Collects the enums declared with `<<enum>>` statements and recognizes references to their cases.
+//!
+//! ## Implementation notes
+//!
+//! Our parser is generated from a grammar that predates enums, so they are recognized in two other ways:
+//! - `<<enum>>`, `<<case>>` and `<<endenum>>` are rewritten by the [`crate::parser::YarnSpinnerLexer`]
+//!   into regular commands, which are recognized by their text.
+//! - References to enum cases in expressions, i.e. `Food.Apple` or `.Apple`, are rewritten by the lexer
+//!   into a single variable token without the leading `$`. See [`EnumCaseReference`].
+
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
+use crate::prelude::*;
+use antlr_rust::parser_rule_context::ParserRuleContext;
+use antlr_rust::token::Token;
+use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat};
+use yarnspinner_core::prelude::*;
+use yarnspinner_core::types::*;
+
+/// The commands that declare an enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EnumCommand {
+    Enum {
+        name: String,
+    },
+    Case {
+        name: String,
+        /// The unparsed text after the `=`, if any.
+        raw_value: Option<String>,
+    },
+    EndEnum,
+}
+
+impl EnumCommand {
+    pub(crate) fn parse(command_text: &str) -> Option<Self> {
+        let command_text = command_text.trim();
+        if command_text == "endenum" {
+            return Some(Self::EndEnum);
+        }
+        if let Some(name) = command_text.strip_prefix("enum ") {
+            return Some(Self::Enum {
+                name: name.trim().to_owned(),
+            });
+        }
+        let case = command_text.strip_prefix("case ")?;
+        let (name, raw_value) = match case.split_once('=') {
+            Some((name, raw_value)) => (name, Some(raw_value.trim().to_owned())),
+            None => (case, None),
+        };
+        Some(Self::Case {
+            name: name.trim().to_owned(),
+            raw_value,
+        })
+    }
+}
+
+/// A reference to a case of an enum in an expression, e.g. `Food.Apple`, or `.Apple` if the enum can be inferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EnumCaseReference<'a> {
+    pub(crate) enum_name: Option<&'a str>,
+    pub(crate) case_name: &'a str,
+}
+
+impl<'a> EnumCaseReference<'a> {
+    /// Parses the text of a variable token. Returns `None` for actual variables, whose names start with a `$`.
+    pub(crate) fn parse(variable_name: &'a str) -> Option<Self> {
+        if variable_name.starts_with('$') {
+            return None;
+        }
+        let (enum_name, case_name) = variable_name.split_once('.')?;
+        Some(Self {
+            enum_name: (!enum_name.is_empty()).then_some(enum_name),
+            case_name,
+        })
+    }
+
+    /// Finds the enum this case belongs to. A reference without an enum name is resolved
+    /// by the type hint if there is one, or else by the only enum that has a case with this name.
+    pub(crate) fn resolve(
+        &self,
+        enums: &[EnumType],
+        hint: Option<&Type>,
+    ) -> Result<EnumType, String> {
+        let case_name = self.case_name;
+        if let Some(enum_name) = self.enum_name {
+            let enum_type = enums
+                .iter()
+                .find(|enum_type| enum_type.name == enum_name)
+                .ok_or_else(|| format!("Unknown enum {enum_name}"))?;
+            return enum_type
+                .case(case_name)
+                .map(|_| enum_type.clone())
+                .ok_or_else(|| format!("Enum {enum_name} has no case named {case_name}"));
+        }
+        if let Some(Type::Enum(enum_type)) = hint
+            && let Some(enum_type) = enums.iter().find(|candidate| *candidate == enum_type)
+            && enum_type.case(case_name).is_some()
+        {
+            return Ok(enum_type.clone());
+        }
+        let candidates: Vec<_> = enums
+            .iter()
+            .filter(|enum_type| enum_type.case(case_name).is_some())
+            .collect();
+        match candidates.as_slice() {
+            [] => Err(format!("No enum has a case named {case_name}")),
+            [enum_type] => Ok((*enum_type).clone()),
+            _ => {
+                let options = candidates
+                    .iter()
+                    .map(|enum_type| format!("{}.{case_name}", enum_type.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Err(format!(
+                    "Can't tell which enum .{case_name} belongs to. Write it as one of {options}"
+                ))
+            }
+        }
+    }
+
+    /// The raw value of the referenced case in the given enum, which should have been returned by [`EnumCaseReference::resolve`].
+    pub(crate) fn raw_value(&self, enum_type: &EnumType) -> YarnValue {
+        enum_type
+            .case(self.case_name)
+            .expect_or_bug(
+                "Internal error: enum case reference was not resolved to an enum containing it.",
+            )
+            .raw_value
+            .clone()
+    }
+}
+
+/// A visitor that collects the enums declared in a file and reports invalid declarations.
+pub(crate) struct EnumVisitor<'input> {
+    /// The enums that were found in this file.
+    pub(crate) new_enums: Vec<EnumType>,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    /// The enums declared in previously visited files.
+    existing_enums: Vec<EnumType>,
+    file: FileParseResult<'input>,
+    /// The enum whose cases are currently being declared.
+    open_enum: Option<OpenEnum>,
+    _dummy: (),
+}
+
+struct OpenEnum {
+    enum_type: EnumType,
+    /// Whether the enum can be used. Invalid enums are still read to the end to check their cases.
+    is_valid: bool,
+    unclosed_diagnostic: Diagnostic,
+}
+
+impl<'input> EnumVisitor<'input> {
+    pub(crate) fn new(existing_enums: Vec<EnumType>, file: FileParseResult<'input>) -> Self {
+        Self {
+            file,
+            existing_enums,
+            new_enums: Default::default(),
+            diagnostics: Default::default(),
+            open_enum: Default::default(),
+            _dummy: Default::default(),
+        }
+    }
+
+    fn diagnostic(
+        &self,
+        message: impl Into<String>,
+        ctx: &Command_statementContext<'input>,
+    ) -> Diagnostic {
+        Diagnostic::from_message(message)
+            .with_file_name(&self.file.name)
+            .with_parser_context(ctx, self.file.tokens())
+    }
+
+    fn open(&mut self, name: String, ctx: &Command_statementContext<'input>) {
+        if let Some(open_enum) = self.open_enum.take() {
+            self.diagnostics.push(open_enum.unclosed_diagnostic);
+        }
+        let is_builtin_type = Type::EXPLICITLY_CONSTRUCTABLE
+            .iter()
+            .any(|r#type| r#type.name() == name)
+            || ["string", "number", "bool"].contains(&name.as_str());
+        let is_duplicate = self
+            .existing_enums
+            .iter()
+            .chain(self.new_enums.iter())
+            .any(|enum_type| enum_type.name == name);
+        if !is_valid_name(&name) {
+            let diagnostic = self.diagnostic(format!("{name} is not a valid enum name"), ctx);
+            self.diagnostics.push(diagnostic);
+        } else if is_builtin_type {
+            let diagnostic = self.diagnostic(format!("{name} is the name of a built-in type"), ctx);
+            self.diagnostics.push(diagnostic);
+        } else if is_duplicate {
+            let diagnostic = self.diagnostic(format!("Enum {name} has already been declared"), ctx);
+            self.diagnostics.push(diagnostic);
+        }
+
+        let is_valid = is_valid_name(&name) && !is_builtin_type && !is_duplicate;
+        let line = ctx.start().get_line();
+        let unclosed_diagnostic = self.diagnostic(
+            format!(
+                "Expected an <<endenum>> to match the <<enum {name}>> statement on line {line}"
+            ),
+            ctx,
+        );
+        self.open_enum = Some(OpenEnum {
+            enum_type: EnumType::new(name),
+            is_valid,
+            unclosed_diagnostic,
+        });
+    }
+
+    fn add_case(
+        &mut self,
+        name: String,
+        raw_value: Option<String>,
+        ctx: &Command_statementContext<'input>,
+    ) {
+        let Some(open_enum) = self.open_enum.as_ref() else {
+            let diagnostic = self.diagnostic("Found a <<case>> outside of an <<enum>>", ctx);
+            self.diagnostics.push(diagnostic);
+            return;
+        };
+        let enum_type = &open_enum.enum_type;
+        let enum_name = enum_type.name.clone();
+        let raw_value = match raw_value {
+            Some(text) => parse_raw_value(&text).ok_or_else(|| {
+                format!("The raw value {text} of case {name} must be a number or a string")
+            }),
+            // Cases without an explicit raw value are numbered, unless the enum uses strings.
+            None if enum_type.raw_type() == Some(Type::String) => Ok(name.clone().into()),
+            None => Ok(enum_type.cases.len().into()),
+        };
+        let error = match &raw_value {
+            _ if !is_valid_name(&name) => Some(format!("{name} is not a valid case name")),
+            Err(message) => Some(message.clone()),
+            Ok(_) if enum_type.case(&name).is_some() => {
+                Some(format!("Enum {enum_name} already has a case named {name}"))
+            }
+            Ok(raw_value) => {
+                let raw_type = raw_value.r#type();
+                if let Some(expected_type) = enum_type.raw_type().filter(|t| *t != raw_type) {
+                    Some(format!(
+                        "The raw value of case {name} is a {raw_type}, but the other cases of enum {enum_name} have {expected_type} raw values"
+                    ))
+                } else {
+                    enum_type.case_for_raw_value(raw_value).map(|other| {
+                        format!(
+                            "Case {name} of enum {enum_name} has the same raw value as case {}",
+                            other.name
+                        )
+                    })
+                }
+            }
+        };
+        if let Some(message) = error {
+            let diagnostic = self.diagnostic(message, ctx);
+            self.diagnostics.push(diagnostic);
+            return;
+        }
+        let open_enum = self.open_enum.as_mut().unwrap_or_bug();
+        open_enum.enum_type.cases.push(EnumCase {
+            name,
+            raw_value: raw_value.unwrap_or_bug(),
+        });
+    }
+
+    fn close(&mut self, ctx: &Command_statementContext<'input>) {
+        let Some(open_enum) = self.open_enum.take() else {
+            let diagnostic = self.diagnostic(
+                "Found an <<endenum>> without a matching <<enum>> statement before it",
+                ctx,
+            );
+            self.diagnostics.push(diagnostic);
+            return;
+        };
+        if open_enum.enum_type.cases.is_empty() {
+            let message = format!(
+                "Enum {} must have at least one case",
+                open_enum.enum_type.name
+            );
+            let diagnostic = self.diagnostic(message, ctx);
+            self.diagnostics.push(diagnostic);
+        } else if open_enum.is_valid {
+            self.new_enums.push(open_enum.enum_type);
+        }
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_raw_value(text: &str) -> Option<YarnValue> {
+    if let Some(string) = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+    {
+        return Some(string.into());
+    }
+    text.parse::<f32>().ok().map(Into::into)
+}
+
+impl<'input> ParseTreeVisitorCompat<'input> for EnumVisitor<'input> {
+    type Node = YarnSpinnerParserContextType;
+    type Return = ();
+
+    fn temp_result(&mut self) -> &mut Self::Return {
+        &mut self._dummy
+    }
+}
+
+impl<'input> YarnSpinnerParserVisitorCompat<'input> for EnumVisitor<'input> {
+    fn visit_node(&mut self, ctx: &NodeContext<'input>) -> Self::Return {
+        if let Some(body) = ctx.body() {
+            self.visit(body.as_ref());
+        }
+        // Enums cannot span multiple nodes
+        if let Some(open_enum) = self.open_enum.take() {
+            self.diagnostics.push(open_enum.unclosed_diagnostic);
+        }
+    }
+
+    fn visit_command_statement(&mut self, ctx: &Command_statementContext<'input>) -> Self::Return {
+        let Some(command) = ctx
+            .command_formatted_text()
+            .and_then(|text| EnumCommand::parse(&text.get_text()))
+        else {
+            return;
+        };
+        match command {
+            EnumCommand::Enum { name } => self.open(name, ctx),
+            EnumCommand::Case { name, raw_value } => self.add_case(name, raw_value, ctx),
+            EnumCommand::EndEnum => self.close(ctx),
+        }
+    }
+}
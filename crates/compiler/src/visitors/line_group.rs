@@ -0,0 +1,78 @@
+//! Recognizes line groups, i.e. consecutive lines starting with `=>` of which only one is run, and scores the conditions of their variants.
+//!
+//! ## Implementation notes
+//!
+//! Our parser is generated from a grammar that predates line groups, so an item of a line group is parsed as a regular line
+//! whose text starts with [`LINE_GROUP_ARROW`]. Consecutive items in the same list of statements form one group.
+
+use crate::prelude::generated::yarnspinnerparser::*;
+use antlr_rust::tree::{ParseTree, Tree};
+use better_any::TidExt;
+use std::rc::Rc;
+
+/// The marker at the start of a line that makes it a variant of a line group.
+pub(crate) const LINE_GROUP_ARROW: &str = "=>";
+
+/// Returns `true` if the given line statement is a variant of a line group.
+pub(crate) fn is_line_group_item(ctx: &Line_statementContext) -> bool {
+    ctx.line_formatted_text()
+        .is_some_and(|text| text.get_text().trim_start().starts_with(LINE_GROUP_ARROW))
+}
+
+/// Removes the [`LINE_GROUP_ARROW`] from the start of a line's text, if present.
+pub(crate) fn strip_line_group_arrow(text: &str) -> &str {
+    text.trim_start()
+        .strip_prefix(LINE_GROUP_ARROW)
+        .map_or(text, str::trim_start)
+}
+
+/// Returns all variants of the line group that the given line statement starts,
+/// or `None` if the line is not the first variant of a line group.
+pub(crate) fn get_line_group_starting_at<'input>(
+    ctx: &Line_statementContext<'input>,
+) -> Option<Vec<Rc<Line_statementContext<'input>>>> {
+    if !is_line_group_item(ctx) {
+        return None;
+    }
+    // The line is wrapped in a statement, whose parent holds the list of statements the line belongs to.
+    let siblings: Vec<_> = ctx
+        .get_parent()?
+        .get_parent()?
+        .get_children()
+        .filter_map(|child| child.downcast_rc::<StatementContext>().ok())
+        .map(|statement| statement.line_statement())
+        .collect();
+    let interval = ctx.get_source_interval();
+    let index = siblings.iter().position(|line| {
+        line.as_ref()
+            .is_some_and(|line| line.get_source_interval() == interval)
+    })?;
+    let continues_previous_group = index
+        .checked_sub(1)
+        .and_then(|previous| siblings[previous].as_deref())
+        .is_some_and(is_line_group_item);
+    if continues_previous_group {
+        return None;
+    }
+    let group = siblings[index..]
+        .iter()
+        .map_while(|line| line.clone().filter(|line| is_line_group_item(line)))
+        .collect();
+    Some(group)
+}
+
+/// Calculates the complexity score of a line group variant's condition, i.e. the number of clauses joined by `and`, `or` or `xor`.
+/// The saliency strategies of the runtime use this to prefer variants with more specific conditions.
+pub(crate) fn get_condition_complexity(expression: &ExpressionContextAll) -> usize {
+    match expression {
+        ExpressionContextAll::ExpAndOrXorContext(ctx) => [ctx.expression(0), ctx.expression(1)]
+            .into_iter()
+            .flatten()
+            .map(|expression| get_condition_complexity(&expression))
+            .sum(),
+        ExpressionContextAll::ExpParensContext(ctx) => ctx
+            .expression()
+            .map_or(1, |expression| get_condition_complexity(&expression)),
+        _ => 1,
+    }
+}
@@ -0,0 +1,24 @@
+//! Recognizes `<<local>>` statements, which declare variables that only exist until their node is left.
+//!
+//! ## Implementation notes
+//!
+//! A local variable is introduced with `<<local $x = 1>>` and only exists until the node it was introduced in is left.
+//! While it exists, it shadows any stored variable of the same name.
+//!
+//! Our grammar has no rule for `<<local>>` statements, so the [`crate::parser::YarnSpinnerLexer`] rewrites them into
+//! `<<set>>` statements whose command token keeps the text `local`. See [`is_local_variable_declaration`].
+//! The value is stored with [`OpCode::StoreLocalVariable`] instead of [`OpCode::StoreVariable`].
+
+use crate::prelude::generated::yarnspinnerparser::*;
+use antlr_rust::tree::ParseTree;
+#[cfg(doc)]
+use yarnspinner_core::prelude::OpCode;
+
+/// The text of the command token of a `<<local>>` statement.
+const LOCAL_COMMAND: &str = "local";
+
+/// Returns `true` if the given set statement was written as a `<<local>>` statement.
+pub(crate) fn is_local_variable_declaration(ctx: &Set_statementContext) -> bool {
+    ctx.COMMAND_SET()
+        .is_some_and(|command| command.get_text().trim() == LOCAL_COMMAND)
+}
@@ -0,0 +1,108 @@
+//! Recognizes the nodes of a node group, i.e. nodes sharing a title that are chosen by their `when:` headers, and names them.
+//!
+//! ## Implementation notes
+//!
+//! Nodes that share a title are allowed if all of them have at least one [`NODE_GROUP_CONDITION_HEADER`].
+//! Every such node is compiled under a unique name generated by [`get_node_group_member_name`], and the compiler
+//! generates an additional node with the shared title that selects one of them through the saliency strategy of the runtime.
+//!
+//! Our parser is generated from a grammar that predates node groups, so the conditions are plain header values
+//! that are parsed separately when generating that node.
+
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::*;
+use antlr_rust::parser_rule_context::ParserRuleContext;
+use antlr_rust::token::Token;
+use std::rc::Rc;
+
+/// The header whose value is the condition under which a node of a node group can be selected.
+pub(crate) const NODE_GROUP_CONDITION_HEADER: &str = "when";
+
+/// The condition of a [`NODE_GROUP_CONDITION_HEADER`] that always passes.
+pub(crate) const NODE_GROUP_ALWAYS_CONDITION: &str = "always";
+
+/// The condition of a [`NODE_GROUP_CONDITION_HEADER`] that passes until the node has been run once.
+pub(crate) const NODE_GROUP_ONCE_CONDITION: &str = "once";
+
+/// A node of a node group, as needed to generate the code of the node that selects one of them.
+pub(crate) struct NodeGroupMember<'input> {
+    /// The name the node is compiled under, see [`get_node_group_member_name`].
+    pub(crate) name: String,
+    /// The conditions of all [`NODE_GROUP_CONDITION_HEADER`]s of the node that are expressions. All of them must pass.
+    pub(crate) conditions: Vec<Rc<ExpressionContextAll<'input>>>,
+    /// Whether the node has a [`NODE_GROUP_ONCE_CONDITION`].
+    pub(crate) is_once: bool,
+}
+
+/// Returns the values of all [`NODE_GROUP_CONDITION_HEADER`]s of a node, together with the headers themselves.
+pub(crate) fn get_node_group_conditions<'input>(
+    ctx: &NodeContext<'input>,
+) -> Vec<(String, Rc<HeaderContext<'input>>)> {
+    ctx.header_all()
+        .into_iter()
+        .filter(|header| {
+            header
+                .header_key
+                .as_ref()
+                .is_some_and(|key| key.get_text() == NODE_GROUP_CONDITION_HEADER)
+        })
+        .map(|header| {
+            let value = header
+                .header_value
+                .as_ref()
+                .map(|value| value.get_text().trim().to_owned())
+                .unwrap_or_default();
+            (value, header)
+        })
+        .collect()
+}
+
+/// Returns `true` if the node is part of a node group, i.e. has at least one [`NODE_GROUP_CONDITION_HEADER`].
+pub(crate) fn is_node_group_member(ctx: &NodeContext) -> bool {
+    !get_node_group_conditions(ctx).is_empty()
+}
+
+/// Returns the name a node of a node group is compiled under, or `None` if the node is not part of a node group.
+pub(crate) fn get_node_group_member_name(
+    ctx: &NodeContext,
+    file: &FileParseResult,
+) -> Option<String> {
+    if !is_node_group_member(ctx) {
+        return None;
+    }
+    let title = get_title(ctx)?;
+    let position = ctx.start().get_token_index();
+    let ordinal = file
+        .tree
+        .node_all()
+        .into_iter()
+        .take_while(|node| node.start().get_token_index() < position)
+        .filter(|node| is_node_group_member(node) && get_title(node).as_ref() == Some(&title))
+        .count();
+    Some(generate_node_group_member_name(&title, &file.name, ordinal))
+}
+
+fn get_title(ctx: &NodeContext) -> Option<String> {
+    ctx.header_all()
+        .into_iter()
+        .filter(|header| {
+            header
+                .header_key
+                .as_ref()
+                .is_some_and(|key| key.get_text() == "title")
+        })
+        .find_map(|header| {
+            header
+                .header_value
+                .as_ref()
+                .map(|value| value.get_text().to_owned())
+        })
+}
+
+/// Generates a name that is unique for every node of a node group.
+/// The `ordinal` counts the nodes of the group that come before this one in the same file,
+/// so that the name, and with it the variables tracking the node, survive edits that don't add or reorder nodes of the group.
+fn generate_node_group_member_name(title: &str, file_name: &str, ordinal: usize) -> String {
+    let file_hash = stable_hash(file_name);
+    format!("{title}.{file_hash:08x}-{ordinal}")
+}
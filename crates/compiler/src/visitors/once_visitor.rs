@@ -0,0 +1,395 @@
+//! Recognizes `once` statements and generates the names of the hidden variables that track whether they have run.
+//!
+//! ## Implementation notes
+//!
+//! Our parser is generated from a grammar that predates `once` statements, so they are recognized in two other ways:
+//! - `<<once>>` and `<<endonce>>` on their own line are parsed as regular commands and are recognized by their text.
+//!   The lexer turns an `<<else>>` inside a `<<once>>` block into such a command as well.
+//! - `<<once>>` at the end of a line or shortcut option is rewritten by the [`crate::parser::YarnSpinnerLexer`]
+//!   into a line condition that checks the placeholder variable [`ONCE_CONDITION_MARKER`].
+
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
+use crate::prelude::*;
+use crate::visitors::{HashableInterval, get_node_group_member_name};
+use antlr_rust::parser_rule_context::ParserRuleContext;
+use antlr_rust::token::Token;
+use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat, Tree};
+use better_any::TidExt;
+use std::rc::Rc;
+
+/// The name of the variable used as the condition of lines and options marked with `<<once>>`.
+/// It is never read at runtime, as the code generation replaces it with the line's own once variable.
+pub(crate) const ONCE_CONDITION_MARKER: &str = "$Yarn.Internal.Once";
+
+/// Generates the name of the hidden variable that tracks whether the `<<once>>` block started by the given command has run.
+///
+/// The block is identified by the ID of the first line inside it, just like lines marked with `<<once>>` are,
+/// so that adding or removing other blocks does not apply their saved state to the wrong block.
+/// Blocks without any line are identified by a hash of their contents instead.
+pub(crate) fn generate_unique_once_variable_for_block(
+    once_command: &Command_statementContext,
+    node_name: &str,
+) -> String {
+    let block = get_once_block(once_command);
+    let mut nested_blocks = 0;
+    if let Some(line_id) = block
+        .iter()
+        .find_map(|statement| find_first_line_id(statement.as_ref(), &mut nested_blocks))
+    {
+        // Blocks that start with another block share their first line with it
+        return match nested_blocks {
+            0 => format!("{ONCE_CONDITION_MARKER}.Block.{line_id}"),
+            _ => format!("{ONCE_CONDITION_MARKER}.Block.{line_id}.{nested_blocks}"),
+        };
+    }
+
+    // Identical blocks without lines in the same node are told apart by their order
+    let hash = hash_once_block(&block);
+    let position = once_command.start().get_token_index();
+    let identical_blocks_before = find_enclosing_node(once_command)
+        .map(|node| {
+            let mut once_commands = Vec::new();
+            find_once_commands(node.as_ref(), &mut once_commands);
+            once_commands
+                .into_iter()
+                .filter(|command| command.start().get_token_index() < position)
+                .filter(|command| {
+                    let block = get_once_block(command);
+                    let mut nested_blocks = 0;
+                    let has_lines = block.iter().any(|statement| {
+                        find_first_line_id(statement.as_ref(), &mut nested_blocks).is_some()
+                    });
+                    !has_lines && hash_once_block(&block) == hash
+                })
+                .count()
+        })
+        .unwrap_or_default();
+    match identical_blocks_before {
+        0 => format!("{ONCE_CONDITION_MARKER}.Block.{node_name}.{hash:08x}"),
+        _ => format!(
+            "{ONCE_CONDITION_MARKER}.Block.{node_name}.{hash:08x}.{identical_blocks_before}"
+        ),
+    }
+}
+
+/// Returns the statements between the given `<<once>>` command and its matching `<<else>>` or `<<endonce>>`.
+fn get_once_block<'input>(
+    once_command: &Command_statementContext<'input>,
+) -> Vec<Rc<StatementContext<'input>>> {
+    // The command is wrapped in a statement, whose parent holds the list of statements the command belongs to.
+    let Some(statement) = once_command.get_parent() else {
+        return Vec::new();
+    };
+    let Some(siblings) = statement.get_parent().map(|parent| {
+        parent
+            .get_children()
+            .filter_map(|child| child.downcast_rc::<StatementContext>().ok())
+            .collect::<Vec<_>>()
+    }) else {
+        return Vec::new();
+    };
+    let interval = statement.get_source_interval();
+    let Some(index) = siblings
+        .iter()
+        .position(|sibling| sibling.get_source_interval() == interval)
+    else {
+        return Vec::new();
+    };
+
+    let mut depth = 0_usize;
+    siblings[index + 1..]
+        .iter()
+        .take_while(|sibling| {
+            let command = sibling
+                .command_statement()
+                .and_then(|command| command.command_formatted_text())
+                .and_then(|text| OnceCommand::parse(&text.get_text()));
+            match command {
+                Some(OnceCommand::Once) => depth += 1,
+                Some(OnceCommand::Else | OnceCommand::EndOnce) if depth == 0 => return false,
+                Some(OnceCommand::EndOnce) => depth -= 1,
+                Some(OnceCommand::Else) | None => {}
+            }
+            true
+        })
+        .cloned()
+        .collect()
+}
+
+/// Returns the ID of the first line inside the given context, counting the `<<once>>` commands that come before it.
+fn find_first_line_id<'input>(
+    ctx: &(impl YarnSpinnerParserContext<'input> + ?Sized),
+    once_commands_before: &mut usize,
+) -> Option<String> {
+    for child in ctx.get_children() {
+        let child = match child.downcast_rc::<Line_statementContext>() {
+            // The string table generator has already added an implicit line ID to lines without one.
+            Ok(line) => {
+                return get_line_id_tag(&line.hashtag_all())
+                    .and_then(|tag| tag.text.as_ref().map(|text| text.get_text().to_owned()));
+            }
+            Err(child) => child,
+        };
+        let child = match child.downcast_rc::<Command_statementContext>() {
+            Ok(command) => {
+                if is_once_command(&command) {
+                    *once_commands_before += 1;
+                }
+                continue;
+            }
+            Err(child) => child,
+        };
+        if let Some(line_id) = find_first_line_id(child.as_ref(), once_commands_before) {
+            return Some(line_id);
+        }
+    }
+    None
+}
+
+/// Collects all `<<once>>` commands inside the given context in the order they appear in.
+fn find_once_commands<'input>(
+    ctx: &(impl YarnSpinnerParserContext<'input> + ?Sized),
+    once_commands: &mut Vec<Rc<Command_statementContext<'input>>>,
+) {
+    for child in ctx.get_children() {
+        match child.downcast_rc::<Command_statementContext>() {
+            Ok(command) if is_once_command(&command) => once_commands.push(command),
+            Ok(_) => {}
+            Err(child) => find_once_commands(child.as_ref(), once_commands),
+        }
+    }
+}
+
+fn find_enclosing_node<'input>(
+    ctx: &Command_statementContext<'input>,
+) -> Option<Rc<NodeContext<'input>>> {
+    let mut parent = ctx.get_parent();
+    while let Some(ctx) = parent {
+        match ctx.downcast_rc::<NodeContext>() {
+            Ok(node) => return Some(node),
+            Err(ctx) => parent = ctx.get_parent(),
+        }
+    }
+    None
+}
+
+fn hash_once_block(block: &[Rc<StatementContext>]) -> u32 {
+    let text: String = block.iter().map(|statement| statement.get_text()).collect();
+    stable_hash(&text)
+}
+
+fn is_once_command(ctx: &Command_statementContext) -> bool {
+    ctx.command_formatted_text()
+        .is_some_and(|text| OnceCommand::parse(&text.get_text()) == Some(OnceCommand::Once))
+}
+
+/// Generates the name of the hidden variable that tracks whether a line or option marked with `<<once>>` has been delivered or selected.
+pub(crate) fn generate_unique_once_variable_for_line(line_id: &str) -> String {
+    format!("{ONCE_CONDITION_MARKER}.{line_id}")
+}
+
+/// Returns `true` if the given line statement is marked with `<<once>>`.
+pub(crate) fn has_once_condition(ctx: &Line_statementContext) -> bool {
+    ctx.line_condition()
+        .and_then(|condition| condition.expression())
+        .is_some_and(|expression| expression.get_text() == ONCE_CONDITION_MARKER)
+}
+
+/// The commands that delimit a `once` block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnceCommand {
+    Once,
+    /// Starts the part of the block that is run every time after the first.
+    Else,
+    EndOnce,
+}
+
+impl OnceCommand {
+    pub(crate) fn parse(command_text: &str) -> Option<Self> {
+        match command_text.trim() {
+            "once" => Some(Self::Once),
+            // `else` is a keyword, so this is only found after the lexer rewrote an `<<else>>` inside a `<<once>>` block
+            "else" => Some(Self::Else),
+            "endonce" => Some(Self::EndOnce),
+            command_text if has_once_condition_text(command_text) => Some(Self::Once),
+            _ => None,
+        }
+    }
+}
+
+/// Returns `true` for the text of a `<<once if …>>` command. Conditions on `once` statements are not supported,
+/// but the command is still treated as the start of a block so that it is reported only once.
+pub(crate) fn has_once_condition_text(command_text: &str) -> bool {
+    let mut words = command_text.split_whitespace();
+    words.next() == Some("once") && words.next() == Some("if")
+}
+
+/// A visitor that finds all `once` statements, reports `<<once>>` blocks that are not properly closed,
+/// and collects the names of the hidden variables needed to track them.
+pub(crate) struct OnceVisitor<'input> {
+    /// The names of the hidden variables that need to be declared for the `once` statements found.
+    pub(crate) once_variables: Vec<String>,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    file: FileParseResult<'input>,
+    /// The name of the node that we're currently visiting.
+    current_node_name: String,
+    /// The `<<once>>` blocks in the current node that have not yet been closed by an `<<endonce>>`.
+    open_blocks: Vec<OpenOnceBlock>,
+    _dummy: (),
+}
+
+struct OpenOnceBlock {
+    /// The source interval of the rule containing the `<<once>>` statement.
+    /// The matching `<<else>>` and `<<endonce>>` need to be in the same one.
+    scope: Option<HashableInterval>,
+    has_else: bool,
+    unclosed_diagnostic: Diagnostic,
+}
+
+impl<'input> OnceVisitor<'input> {
+    pub(crate) fn new(file: FileParseResult<'input>) -> Self {
+        Self {
+            file,
+            once_variables: Default::default(),
+            diagnostics: Default::default(),
+            current_node_name: Default::default(),
+            open_blocks: Default::default(),
+            _dummy: Default::default(),
+        }
+    }
+}
+
+impl<'input> ParseTreeVisitorCompat<'input> for OnceVisitor<'input> {
+    type Node = YarnSpinnerParserContextType;
+    type Return = ();
+
+    fn temp_result(&mut self) -> &mut Self::Return {
+        &mut self._dummy
+    }
+}
+
+impl<'input> YarnSpinnerParserVisitorCompat<'input> for OnceVisitor<'input> {
+    fn visit_node(&mut self, ctx: &NodeContext<'input>) -> Self::Return {
+        // Must match the name the node is compiled under, which differs from the title for nodes of a node group
+        let node_group_member_name = get_node_group_member_name(ctx, &self.file);
+        self.current_node_name = node_group_member_name.unwrap_or_else(|| {
+            ctx.header_all()
+                .into_iter()
+                .filter(|header| {
+                    header
+                        .header_key
+                        .as_ref()
+                        .is_some_and(|key| key.get_text() == "title")
+                })
+                .find_map(|header| {
+                    header
+                        .header_value
+                        .as_ref()
+                        .map(|value| value.get_text().to_owned())
+                })
+                .unwrap_or_default()
+        });
+
+        if let Some(body) = ctx.body() {
+            self.visit(body.as_ref());
+        }
+
+        let unclosed_diagnostics = self
+            .open_blocks
+            .drain(..)
+            .map(|block| block.unclosed_diagnostic);
+        self.diagnostics.extend(unclosed_diagnostics);
+    }
+
+    fn visit_line_statement(&mut self, ctx: &Line_statementContext<'input>) -> Self::Return {
+        if !has_once_condition(ctx) {
+            return;
+        }
+        // The string table generator has already added an implicit line ID to lines without one.
+        let line_id_tag = get_line_id_tag(&ctx.hashtag_all())
+            .expect_or_bug("Internal error: line should have an implicit or explicit line ID tag, but none was found.");
+        let line_id = line_id_tag.text.as_ref().unwrap().get_text();
+        self.once_variables
+            .push(generate_unique_once_variable_for_line(line_id));
+    }
+
+    fn visit_command_statement(&mut self, ctx: &Command_statementContext<'input>) -> Self::Return {
+        let Some(command_text) = ctx.command_formatted_text().map(|text| text.get_text()) else {
+            return;
+        };
+        let Some(command) = OnceCommand::parse(&command_text) else {
+            return;
+        };
+        // The command is wrapped in a statement, whose parent holds the list of statements the command belongs to.
+        let scope = ctx
+            .get_parent()
+            .and_then(|statement| statement.get_parent())
+            .map(|parent| HashableInterval::from(parent.get_source_interval()));
+
+        match command {
+            OnceCommand::Once => {
+                if has_once_condition_text(&command_text) {
+                    self.diagnostics.push(
+                        Diagnostic::from_message(
+                            "<<once if>> is not supported, use an <<if>> statement inside the <<once>> block instead",
+                        )
+                        .with_file_name(&self.file.name)
+                        .with_parser_context(ctx, self.file.tokens()),
+                    );
+                }
+                self.once_variables
+                    .push(generate_unique_once_variable_for_block(
+                        ctx,
+                        &self.current_node_name,
+                    ));
+
+                let line = ctx.start().get_line();
+                let unclosed_diagnostic = Diagnostic::from_message(format!(
+                    "Expected an <<endonce>> to match the <<once>> statement on line {line}"
+                ))
+                .with_file_name(&self.file.name)
+                .with_parser_context(ctx, self.file.tokens());
+                self.open_blocks.push(OpenOnceBlock {
+                    scope,
+                    has_else: false,
+                    unclosed_diagnostic,
+                });
+            }
+            OnceCommand::Else => {
+                let message = match self.open_blocks.last_mut() {
+                    Some(block) if block.scope == scope && !block.has_else => {
+                        block.has_else = true;
+                        return;
+                    }
+                    Some(block) if block.scope == scope => {
+                        "A <<once>> block can only have one <<else>>"
+                    }
+                    _ => "Found an <<else>> without a matching <<once>> statement before it",
+                };
+                self.diagnostics.push(
+                    Diagnostic::from_message(message)
+                        .with_file_name(&self.file.name)
+                        .with_parser_context(ctx, self.file.tokens()),
+                );
+            }
+            OnceCommand::EndOnce => {
+                let closes_open_block = self
+                    .open_blocks
+                    .last()
+                    .is_some_and(|block| block.scope == scope);
+                if closes_open_block {
+                    self.open_blocks.pop();
+                } else {
+                    self.diagnostics.push(
+                        Diagnostic::from_message(
+                            "Found an <<endonce>> without a matching <<once>> statement before it",
+                        )
+                        .with_file_name(&self.file.name)
+                        .with_parser_context(ctx, self.file.tokens()),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Recognizes smart variables, i.e. variables declared with an expression that is evaluated every time they are read.
+//!
+//! ## Implementation notes
+//!
+//! A smart variable is declared with an expression instead of a constant value, e.g. `<<declare $can_afford = $gold >= $price>>`,
+//! and is recomputed from that expression every time it is read.
+//!
+//! Our parser is generated from a grammar that only allows constant values in `<<declare>>` statements,
+//! so the [`crate::parser::YarnSpinnerLexer`] rewrites such a declaration into a `<<set>>` statement
+//! whose command token keeps the text `declare`. See [`is_smart_variable_declaration`].
+//!
+//! The expression of every smart variable is compiled into a hidden node named after the variable,
+//! which the runtime runs whenever the variable is read.
+
+use crate::prelude::generated::yarnspinnerlexer;
+use crate::prelude::generated::yarnspinnerparser::*;
+use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
+use crate::prelude::*;
+use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat};
+use std::rc::Rc;
+
+/// The text of the command token of a `<<declare>>` statement.
+const DECLARE_COMMAND: &str = "declare";
+
+/// Returns `true` if the given set statement was written as the `<<declare>>` statement of a smart variable.
+pub(crate) fn is_smart_variable_declaration(ctx: &Set_statementContext) -> bool {
+    ctx.COMMAND_SET()
+        .is_some_and(|command| command.get_text().trim() == DECLARE_COMMAND)
+}
+
+/// Returns `true` if the value of a `<<declare>>` statement, given as the types of its tokens, is a constant
+/// that our grammar can parse. All other values are the expressions of smart variables.
+/// Enum cases count as constants, see [`crate::visitors::EnumCaseReference`].
+pub(crate) fn is_constant_declaration_value(token_types: &[isize]) -> bool {
+    matches!(
+        token_types,
+        [yarnspinnerlexer::NUMBER
+            | yarnspinnerlexer::STRING
+            | yarnspinnerlexer::KEYWORD_TRUE
+            | yarnspinnerlexer::KEYWORD_FALSE
+            | yarnspinnerlexer::KEYWORD_NULL]
+            | [
+                yarnspinnerlexer::FUNC_ID,
+                yarnspinnerlexer::DOT,
+                yarnspinnerlexer::FUNC_ID
+            ]
+            | [yarnspinnerlexer::DOT, yarnspinnerlexer::FUNC_ID]
+    )
+}
+
+/// A smart variable found by the [`crate::visitors::DeclarationVisitor`].
+#[derive(Clone)]
+pub(crate) struct SmartVariableDeclaration<'input> {
+    /// The declaration of the variable. Its type is only known after the expression has been type checked.
+    pub(crate) declaration: Declaration,
+    /// The expression the variable is computed from.
+    pub(crate) expression: Rc<ExpressionContextAll<'input>>,
+}
+
+/// A visitor that collects the names of all variables referenced in an expression.
+#[derive(Default)]
+pub(crate) struct VariableReferenceVisitor {
+    pub(crate) variables: Vec<String>,
+    _dummy: (),
+}
+
+impl<'input> ParseTreeVisitorCompat<'input> for VariableReferenceVisitor {
+    type Node = YarnSpinnerParserContextType;
+    type Return = ();
+
+    fn temp_result(&mut self) -> &mut Self::Return {
+        &mut self._dummy
+    }
+}
+
+impl<'input> YarnSpinnerParserVisitorCompat<'input> for VariableReferenceVisitor {
+    fn visit_variable(&mut self, ctx: &VariableContext<'input>) -> Self::Return {
+        self.variables.push(ctx.get_text());
+    }
+}
@@ -1,6 +1,7 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner.Compiler/StringTableGeneratorVisitor.cs>
 use crate::prelude::generated::{yarnspinnerparser::*, yarnspinnerparservisitor::*};
 use crate::prelude::*;
+use crate::visitors::strip_line_group_arrow;
 use antlr_rust::parser_rule_context::ParserRuleContext;
 use antlr_rust::token::Token;
 use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat, Tree};
@@ -118,7 +119,10 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for StringTableGeneratorVisi
         let line_number = ctx.start().get_line_as_usize();
         let hashtag_texts = get_hashtag_texts(&hashtags);
 
-        let composed_string = generate_formatted_text(&ctx.line_formatted_text().unwrap());
+        let composed_string = generate_formatted_text(
+            &ctx.line_formatted_text().unwrap(),
+            &self.file.format_specifiers,
+        );
 
         let string_id = self.string_table_manager.insert(
             line_id.map(|t| t.get_text().into()),
@@ -142,8 +146,18 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for StringTableGeneratorVisi
 /// `Hi there { some_expression }, how are you { another_expression } doing?`
 /// and turns it into
 /// `Hi there {0}, how are you {1}? doing`
-fn generate_formatted_text(ctx: &Line_formatted_textContext) -> String {
+///
+/// Expressions that had a format specifier keep it, e.g. `{$gold:N0}` turns into `{0:N0}`.
+fn generate_formatted_text(
+    ctx: &Line_formatted_textContext,
+    format_specifiers: &FormatSpecifiers,
+) -> String {
     let mut expression_count = 0;
+    // `EXPRESSION_END_all` returns every terminal of the line, so we need to look for the `}`s ourselves
+    let mut expression_ends = ctx
+        .EXPRESSION_END_all()
+        .into_iter()
+        .filter(|terminal| terminal.symbol.get_token_type() == EXPRESSION_END);
     let mut composed_string = String::new();
     // First, visit all of the nodes, which are either terminal
     // text nodes or expressions. if they're expressions, we
@@ -161,9 +175,16 @@ fn generate_formatted_text(ctx: &Line_formatted_textContext) -> String {
             // the expression count.
             composed_string.push_str(&expression_count.to_string());
             expression_count += 1;
+            let format_specifier = expression_ends
+                .next()
+                .and_then(|end| format_specifiers.get(&(end.symbol.get_start() as usize)));
+            if let Some(format_specifier) = format_specifier {
+                composed_string.push(':');
+                composed_string.push_str(format_specifier);
+            }
         }
     }
-    composed_string.trim().to_owned()
+    strip_line_group_arrow(composed_string.trim()).to_owned()
 }
 
 pub(crate) fn get_hashtag_texts(hashtags: &[Rc<HashtagContext>]) -> Vec<String> {
@@ -223,8 +244,21 @@ A line with {$many} many {(1 -(1 * 2))}{$cool} expressions
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn formats_lines_with_format_specifiers() {
+        let input = "title: Title
+---
+You have {$gold:N0} gold, {$ratio : P1} of it {\"earned:\"} today
+===
+";
+        let result = process_input(input);
+        let expected = "You have {0:N0} gold, {1:P1} of it {2} today";
+        assert_eq!(result, expected);
+    }
+
     fn process_input(input: &str) -> String {
         let lexer = YarnSpinnerLexer::new(InputStream::new(input), "input.yarn".to_owned());
+        let format_specifiers = lexer.format_specifiers.clone();
         let mut parser = YarnSpinnerParser::new(CommonTokenStream::new(lexer));
         let line_formatted_text = parser
             .dialogue()
@@ -239,7 +273,7 @@ A line with {$many} many {(1 -(1 * 2))}{$cool} expressions
             .unwrap()
             .line_formatted_text()
             .unwrap();
-        generate_formatted_text(&line_formatted_text)
+        generate_formatted_text(&line_formatted_text, &format_specifiers.borrow())
     }
 
     #[test]
@@ -5,12 +5,16 @@ use crate::prelude::generated::yarnspinnerlexer;
 use crate::prelude::generated::yarnspinnerparser::*;
 use crate::prelude::generated::yarnspinnerparservisitor::YarnSpinnerParserVisitorCompat;
 use crate::prelude::*;
-use crate::visitors::{CodeGenerationVisitor, KnownTypes};
+use crate::visitors::{
+    CodeGenerationVisitor, EnumCaseReference, KnownTypes, ONCE_CONDITION_MARKER,
+    is_local_variable_declaration, is_smart_variable_declaration,
+};
 use antlr_rust::parser_rule_context::ParserRuleContext;
 use antlr_rust::token::Token;
 use antlr_rust::tree::{ParseTree, ParseTreeVisitorCompat};
 use check_operation::*;
 use std::path::Path;
+use std::rc::Rc;
 use yarnspinner_core::prelude::*;
 use yarnspinner_core::types::*;
 
@@ -40,6 +44,9 @@ pub(crate) struct TypeCheckVisitor<'input> {
     // starting our work
     existing_declarations: Vec<Declaration>,
 
+    /// The enums declared in the Yarn files, which cases in expressions refer to.
+    enums: Vec<EnumType>,
+
     // The name of the node that we're currently visiting.
     current_node_name: Option<String>,
 
@@ -75,11 +82,13 @@ pub(crate) struct TypeCheckVisitor<'input> {
 impl<'input> TypeCheckVisitor<'input> {
     pub(crate) fn new(
         existing_declarations: Vec<Declaration>,
+        enums: Vec<EnumType>,
         file: FileParseResult<'input>,
     ) -> Self {
         Self {
             file,
             existing_declarations,
+            enums,
             diagnostics: Default::default(),
             new_declarations: Default::default(),
             deferred_types: Default::default(),
@@ -105,6 +114,24 @@ impl<'input> TypeCheckVisitor<'input> {
             .iter_mut()
             .chain(self.new_declarations.iter_mut())
     }
+
+    /// Not in the original. Checks a condition that was parsed on its own, see [`parse_expression`],
+    /// such as the one of a `when:` header. Like the condition of an if statement, it must be boolean.
+    pub(crate) fn check_condition(
+        &mut self,
+        ctx: &Rc<ExpressionContextAll<'input>>,
+        file: FileParseResult<'input>,
+    ) -> Option<Type> {
+        self.file = file;
+        let expressions = &[ctx.clone().into()];
+        self.check_operation(
+            ctx.as_ref(),
+            expressions,
+            None,
+            "condition",
+            &[Type::Boolean],
+        )
+    }
 }
 
 impl<'input> ParseTreeVisitorCompat<'input> for TypeCheckVisitor<'input> {
@@ -241,6 +268,9 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
 
     fn visit_valueVar(&mut self, ctx: &ValueVarContext<'input>) -> Self::Return {
         let variable = ctx.variable().unwrap_or_bug();
+        // Lets `.Case` refer to the enum of the variable it is assigned to
+        let hint = self.hints.get(ctx).cloned();
+        self.hints.insert(variable.as_ref(), hint);
         self.visit_variable(&variable)
     }
 
@@ -392,6 +422,29 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
         // this Variable context; here, we'll bail out.
         let var_id = ctx.get_token(yarnspinnerlexer::VAR_ID, 0)?;
         let name = var_id.get_text();
+        if name == ONCE_CONDITION_MARKER {
+            // Placeholder for a `<<once>>` condition, which is always a boolean.
+            return Some(Type::Boolean);
+        }
+        if let Some(enum_case_reference) = EnumCaseReference::parse(&name) {
+            let hint = self.hints.get(ctx).cloned();
+            return match enum_case_reference.resolve(&self.enums, hint.as_ref()) {
+                Ok(enum_type) => {
+                    // The code generation needs to know which enum to take the raw value from
+                    let r#type = Type::Enum(enum_type);
+                    self.known_types.insert(ctx, r#type.clone());
+                    Some(r#type)
+                }
+                Err(message) => {
+                    self.diagnostics.push(
+                        Diagnostic::from_message(message)
+                            .with_file_name(&self.file.name)
+                            .with_parser_context(ctx, self.file.tokens()),
+                    );
+                    None
+                }
+            };
+        }
         if let Some(declaration) = self.declarations().find(|decl| decl.name == name) {
             return Some(declaration.r#type.clone());
         }
@@ -448,12 +501,34 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
         }
         let mut expression_type = self.visit(expression_context.as_ref());
         let variable_name = variable_context.get_text();
+        let is_smart_variable = self
+            .declarations()
+            .any(|decl| decl.is_smart_variable && decl.name == variable_name);
+        if is_smart_variable && !is_smart_variable_declaration(ctx) {
+            self.diagnostics.push(
+                Diagnostic::from_message(format!(
+                    "{variable_name} is a smart variable and cannot be modified"
+                ))
+                .with_file_name(&self.file.name)
+                .with_parser_context(ctx, self.file.tokens()),
+            );
+        }
         let terms: &[Term] = &[
             variable_context.clone().into(),
             expression_context.clone().into(),
         ];
 
         let op = ctx.op.as_ref().unwrap_or_bug();
+        let is_local = is_local_variable_declaration(ctx);
+        if is_local && op.token_type != yarnspinnerlexer::OPERATOR_ASSIGNMENT {
+            self.diagnostics.push(
+                Diagnostic::from_message(format!(
+                    "Local variable {variable_name} must be assigned a value with `=` or `to`"
+                ))
+                .with_file_name(&self.file.name)
+                .with_parser_context(ctx, self.file.tokens()),
+            );
+        }
         match op.token_type {
             yarnspinnerlexer::OPERATOR_ASSIGNMENT => {
                 // Straight assignment supports any assignment, as long
@@ -491,6 +566,7 @@ impl<'input> YarnSpinnerParserVisitorCompat<'input> for TypeCheckVisitor<'input>
                                 .with_source_node_name_optional(self.current_node_name.clone())
                                 .with_range(variable_context.range())
                                 .with_implicit();
+                            let decl = if is_local { decl.with_local() } else { decl };
                             self.new_declarations.push(decl);
                         } else {
                             self.diagnostics.push(
@@ -610,6 +686,8 @@ impl DefaultValue for Type {
             Type::String => Some(YarnValue::String(Default::default())),
             Type::Number => Some(YarnValue::Number(Default::default())),
             Type::Boolean => Some(YarnValue::Boolean(Default::default())),
+            // Enums default to their first case
+            Type::Enum(enum_type) => enum_type.cases.first().map(|case| case.raw_value.clone()),
             _ => None,
         }
     }
@@ -1,9 +1,11 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner.Compiler/DebugInfo.cs>
 
 use crate::prelude::*;
-use std::collections::HashMap;
+use alloc::collections::BTreeMap;
 
 /// Contains debug information for a node in a Yarn file.
+///
+/// Produced by the compiler and consumed by the runtime's `DialogueDebugger`.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "bevy", derive(Reflect))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -21,7 +23,7 @@ pub struct DebugInfo {
 
     /// The mapping of instruction numbers to line and character
     /// information in the file indicated by `file_name`.
-    pub line_positions: HashMap<usize, Option<Position>>,
+    pub line_positions: BTreeMap<usize, Option<Position>>,
 }
 
 impl DebugInfo {
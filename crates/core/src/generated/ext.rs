@@ -3,6 +3,7 @@
 use crate::prelude::*;
 use core::error::Error;
 use core::fmt::{Debug, Display};
+use prost::Message;
 
 impl From<String> for Operand {
     fn from(s: String) -> Self {
@@ -112,39 +113,295 @@ impl Display for InvalidOpCodeError {
     }
 }
 
+/// Decides what [`Program::combine_with_policy`] does when several programs contain a node with the same name
+/// or different initial values for the same variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum MergePolicy {
+    /// Returns a [`ProgramMergeError`] listing every collision.
+    #[default]
+    Error,
+    /// Keeps the node or initial value of the program that comes first.
+    KeepFirst,
+    /// Keeps the node or initial value of the program that comes last, overriding the earlier ones.
+    KeepLast,
+}
+
+/// Returned by [`Program::combine`] when the programs to merge collide.
+/// Each list is sorted and contains every name at most once.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Default))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct ProgramMergeError {
+    /// The names of the nodes that are contained in more than one program.
+    pub conflicting_nodes: Vec<String>,
+    /// The names of the variables that have different initial values in different programs.
+    /// Variables declared with the same initial value in several programs are not conflicts.
+    pub conflicting_initial_values: Vec<String>,
+}
+
+impl ProgramMergeError {
+    fn is_empty(&self) -> bool {
+        self.conflicting_nodes.is_empty() && self.conflicting_initial_values.is_empty()
+    }
+}
+
+impl Error for ProgramMergeError {}
+
+impl Display for ProgramMergeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Failed to merge programs.")?;
+        if !self.conflicting_nodes.is_empty() {
+            write!(
+                f,
+                " More than one program contains the nodes {}.",
+                self.conflicting_nodes.join(", ")
+            )?;
+        }
+        if !self.conflicting_initial_values.is_empty() {
+            write!(
+                f,
+                " The programs declare different initial values for the variables {}.",
+                self.conflicting_initial_values.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
 impl Program {
     /// Creates a new Program by merging multiple Programs together.
     ///
     /// The new program will contain every node from every input program.
     /// Returns [`None`] if the input is empty.
-    pub fn combine(programs: Vec<Program>) -> Option<Self> {
+    ///
+    /// Returns a [`ProgramMergeError`] if two programs contain a node with the same name or different initial values for the same variable.
+    /// Hidden nodes, e.g. those of smart variables, are not conflicts if they are identical.
+    /// Otherwise, they are reported as conflicting initial values, since they hold the expression of a variable.
+    /// Use [`Program::combine_with_policy`] to resolve such collisions instead.
+    pub fn combine(programs: Vec<Program>) -> Result<Option<Self>, ProgramMergeError> {
+        Self::combine_with_policy(programs, MergePolicy::Error)
+    }
+
+    /// Like [`Program::combine`], but resolves collisions according to the given [`MergePolicy`].
+    pub fn combine_with_policy(
+        programs: Vec<Program>,
+        policy: MergePolicy,
+    ) -> Result<Option<Self>, ProgramMergeError> {
         if programs.is_empty() {
-            return None;
+            return Ok(None);
         }
         let mut output = Program::default();
+        let mut error = ProgramMergeError::default();
         for program in programs {
             for (node_name, node) in program.nodes {
-                assert!(
-                    !output.nodes.contains_key(&node_name),
-                    "This program already contains a node named {node_name}",
-                );
-                output.nodes.insert(node_name, node);
+                let Some(existing_node) = output.nodes.get(&node_name) else {
+                    output.nodes.insert(node_name, node);
+                    continue;
+                };
+                if node.is_hidden() && *existing_node == node {
+                    continue;
+                }
+                match policy {
+                    MergePolicy::Error if node.is_hidden() => {
+                        error.conflicting_initial_values.push(node_name)
+                    }
+                    MergePolicy::Error => error.conflicting_nodes.push(node_name),
+                    MergePolicy::KeepFirst => {}
+                    MergePolicy::KeepLast => {
+                        output.nodes.insert(node_name, node);
+                    }
+                }
+            }
+            for (variable_name, value) in program.initial_values {
+                let Some(existing_value) = output.initial_values.get(&variable_name) else {
+                    output.initial_values.insert(variable_name, value);
+                    continue;
+                };
+                if *existing_value == value {
+                    continue;
+                }
+                match policy {
+                    MergePolicy::Error => error.conflicting_initial_values.push(variable_name),
+                    MergePolicy::KeepFirst => {}
+                    MergePolicy::KeepLast => {
+                        output.initial_values.insert(variable_name, value);
+                    }
+                }
             }
-            output.initial_values.extend(program.initial_values);
         }
-        Some(output)
+        if error.is_empty() {
+            return Ok(Some(output));
+        }
+        for names in [
+            &mut error.conflicting_nodes,
+            &mut error.conflicting_initial_values,
+        ] {
+            names.sort_unstable();
+            names.dedup();
+        }
+        Err(error)
+    }
+
+    /// Computes a stable 64-bit checksum of this program's encoded contents.
+    ///
+    /// Two programs that encode to the same bytes produce the same checksum.
+    /// This is used to verify that saved dialogue state is restored against the same program it was created from.
+    #[must_use]
+    pub fn checksum(&self) -> u64 {
+        // FNV-1a, chosen because it is tiny, `no_std` friendly and stable across platforms and versions.
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        self.encode_to_vec()
+            .into_iter()
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+            })
+    }
+}
+
+impl Node {
+    /// The header that the compiler adds to the node it generates for a node group, i.e. for nodes sharing a title and having `when:` headers.
+    /// Its value is the title of the node group.
+    pub const NODE_GROUP_HUB_HEADER: &'static str = "$Yarn.Internal.NodeGroupHub";
+
+    /// The header that the compiler adds to the node it generates for a smart variable, i.e. a variable declared with an expression.
+    /// Its value is the name of the variable, which is also the name of the node.
+    pub const SMART_VARIABLE_HEADER: &'static str = "$Yarn.Internal.SmartVariable";
+
+    /// The header that the compiler adds to nodes that only exist for the runtime's internal use, such as the nodes of smart variables.
+    /// Hidden nodes cannot be run by name and are left out of the node names a dialogue reports.
+    pub const HIDDEN_HEADER: &'static str = "$Yarn.Internal.Hidden";
+
+    /// Returns the value of the first header with the given key, if any.
+    #[must_use]
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.key == key)
+            .map(|header| header.value.as_str())
+    }
+
+    /// Returns `true` if this node was generated by the compiler to select one of the nodes of a node group.
+    #[must_use]
+    pub fn is_node_group_hub(&self) -> bool {
+        self.header(Self::NODE_GROUP_HUB_HEADER).is_some()
+    }
+
+    /// Returns `true` if this node was generated by the compiler to evaluate a smart variable.
+    #[must_use]
+    pub fn is_smart_variable(&self) -> bool {
+        self.header(Self::SMART_VARIABLE_HEADER).is_some()
+    }
+
+    /// Returns `true` if this node was generated by the compiler for internal use and must not be run by name.
+    #[must_use]
+    pub fn is_hidden(&self) -> bool {
+        self.header(Self::HIDDEN_HEADER).is_some()
     }
 }
 
 impl Instruction {
-    pub fn read_operand<T>(&self, index: usize) -> T
+    /// Returns the operand at the given index, or `None` if the instruction has no such operand or it has a different type.
+    pub fn read_operand<T>(&self, index: usize) -> Option<T>
     where
         T: TryFrom<Operand>,
-        <T as TryFrom<Operand>>::Error: Debug,
     {
-        self.operands[index]
-            .clone()
-            .try_into()
-            .unwrap_or_else(|e| panic!("Failed to convert operand {index}: {e:?}",))
+        self.operands.get(index)?.clone().try_into().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(node_names: &[&str], initial_values: &[(&str, f32)]) -> Program {
+        Program {
+            nodes: node_names
+                .iter()
+                .map(|name| {
+                    let node = Node {
+                        name: name.to_string(),
+                        ..Default::default()
+                    };
+                    (name.to_string(), node)
+                })
+                .collect(),
+            initial_values: initial_values
+                .iter()
+                .map(|(name, value)| (name.to_string(), Operand::from(*value)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn combining_reports_collisions() {
+        let programs = vec![
+            program(&["Start", "Shop"], &[("$gold", 1.0), ("$day", 1.0)]),
+            program(&["Shop", "End"], &[("$gold", 2.0), ("$day", 1.0)]),
+            program(&["Start"], &[]),
+        ];
+
+        let error = Program::combine(programs.clone()).unwrap_err();
+        assert_eq!(vec!["Shop", "Start"], error.conflicting_nodes);
+        assert_eq!(vec!["$gold"], error.conflicting_initial_values);
+
+        let first = Program::combine_with_policy(programs.clone(), MergePolicy::KeepFirst)
+            .unwrap()
+            .unwrap();
+        assert_eq!(3, first.nodes.len());
+        assert_eq!(Operand::from(1.0), first.initial_values["$gold"]);
+
+        let last = Program::combine_with_policy(programs, MergePolicy::KeepLast)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Operand::from(2.0), last.initial_values["$gold"]);
+    }
+
+    #[test]
+    fn combining_merges_identical_hidden_nodes() {
+        let smart_variable = |opcode: OpCode| {
+            let mut program = program(&[], &[]);
+            let node = Node {
+                name: "$can_afford".to_owned(),
+                headers: vec![Header {
+                    key: Node::HIDDEN_HEADER.to_owned(),
+                    value: String::new(),
+                }],
+                instructions: vec![Instruction {
+                    opcode: opcode as i32,
+                    operands: Vec::new(),
+                }],
+                ..Default::default()
+            };
+            program.nodes.insert(node.name.clone(), node);
+            program
+        };
+
+        let combined = Program::combine(vec![
+            smart_variable(OpCode::PushNull),
+            smart_variable(OpCode::PushNull),
+        ]);
+        assert_eq!(1, combined.unwrap().unwrap().nodes.len());
+
+        let error = Program::combine(vec![
+            smart_variable(OpCode::PushNull),
+            smart_variable(OpCode::Pop),
+        ])
+        .unwrap_err();
+        assert!(error.conflicting_nodes.is_empty());
+        assert_eq!(vec!["$can_afford"], error.conflicting_initial_values);
     }
 }
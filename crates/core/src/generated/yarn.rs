@@ -81,153 +81,14 @@ use crate::prelude::*;
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Instruction {
     /// The operation that this instruction will perform.
-    #[prost(enumeration = "instruction::OpCode", tag = "1")]
+    #[prost(enumeration = "crate::op_code::OpCode", tag = "1")]
     pub opcode: i32,
     /// The list of operands, if any, that this instruction uses.
     #[prost(message, repeated, tag = "2")]
     pub operands: ::prost::alloc::vec::Vec<Operand>,
 }
 /// Nested message and enum types in `Instruction`.
-pub mod instruction {
-    /// The type of instruction that this is.
-    use crate::prelude::*;
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-    #[cfg_attr(feature = "bevy", derive(Reflect))]
-    #[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
-    #[cfg_attr(
-        all(feature = "bevy", feature = "serde"),
-        reflect(Serialize, Deserialize)
-    )]
-    #[derive(
-        Clone,
-        Copy,
-        Debug,
-        PartialEq,
-        Eq,
-        Hash,
-        PartialOrd,
-        Ord,
-        ::prost::Enumeration
-    )]
-    #[repr(i32)]
-    pub enum OpCode {
-        /// Jumps to a named position in the node.
-        /// opA = string: label name
-        JumpTo = 0,
-        /// Peeks a string from stack, and jumps to that named position in
-        /// the node.
-        /// No operands.
-        Jump = 1,
-        /// Delivers a string ID to the client.
-        /// opA = string: string ID
-        RunLine = 2,
-        /// Delivers a command to the client.
-        /// opA = string: command text
-        RunCommand = 3,
-        /// Adds an entry to the option list (see ShowOptions).
-        /// - opA = string: string ID for option to add
-        /// - opB = string: destination to go to if this option is selected
-        /// - opC = number: number of expressions on the stack to insert
-        ///    into the line
-        /// - opD = bool: whether the option has a condition on it (in which
-        ///    case a value should be popped off the stack and used to signal
-        ///    the game that the option should be not available)
-        AddOption = 4,
-        /// Presents the current list of options to the client, then clears
-        /// the list. The most recently selected option will be on the top
-        /// of the stack when execution resumes.
-        /// No operands.
-        ShowOptions = 5,
-        /// Pushes a string onto the stack.
-        /// opA = string: the string to push to the stack.
-        PushString = 6,
-        /// Pushes a floating point number onto the stack.
-        /// opA = float: number to push to stack
-        PushFloat = 7,
-        /// Pushes a boolean onto the stack.
-        /// opA = bool: the bool to push to stack
-        PushBool = 8,
-        /// Pushes a null value onto the stack.
-        /// No operands.
-        PushNull = 9,
-        /// Jumps to the named position in the the node, if the top of the
-        /// stack is not null, zero or false.
-        /// opA = string: label name
-        JumpIfFalse = 10,
-        /// Discards top of stack.
-        /// No operands.
-        Pop = 11,
-        /// Calls a function in the client. Pops as many arguments as the
-        /// client indicates the function receives, and the result (if any)
-        /// is pushed to the stack.		
-        /// opA = string: name of the function
-        CallFunc = 12,
-        /// Pushes the contents of a variable onto the stack.
-        /// opA = name of variable
-        PushVariable = 13,
-        /// Stores the contents of the top of the stack in the named
-        /// variable.
-        /// opA = name of variable
-        StoreVariable = 14,
-        /// Stops execution of the program.
-        /// No operands.
-        Stop = 15,
-        /// Pops a string off the top of the stack, and runs the node with
-        /// that name.
-        /// No operands.
-        RunNode = 16,
-    }
-    impl OpCode {
-        /// String value of the enum field names used in the ProtoBuf definition.
-        ///
-        /// The values are not transformed in any way and thus are considered stable
-        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
-        pub fn as_str_name(&self) -> &'static str {
-            match self {
-                OpCode::JumpTo => "JUMP_TO",
-                OpCode::Jump => "JUMP",
-                OpCode::RunLine => "RUN_LINE",
-                OpCode::RunCommand => "RUN_COMMAND",
-                OpCode::AddOption => "ADD_OPTION",
-                OpCode::ShowOptions => "SHOW_OPTIONS",
-                OpCode::PushString => "PUSH_STRING",
-                OpCode::PushFloat => "PUSH_FLOAT",
-                OpCode::PushBool => "PUSH_BOOL",
-                OpCode::PushNull => "PUSH_NULL",
-                OpCode::JumpIfFalse => "JUMP_IF_FALSE",
-                OpCode::Pop => "POP",
-                OpCode::CallFunc => "CALL_FUNC",
-                OpCode::PushVariable => "PUSH_VARIABLE",
-                OpCode::StoreVariable => "STORE_VARIABLE",
-                OpCode::Stop => "STOP",
-                OpCode::RunNode => "RUN_NODE",
-            }
-        }
-        /// Creates an enum from field names used in the ProtoBuf definition.
-        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
-            match value {
-                "JUMP_TO" => Some(Self::JumpTo),
-                "JUMP" => Some(Self::Jump),
-                "RUN_LINE" => Some(Self::RunLine),
-                "RUN_COMMAND" => Some(Self::RunCommand),
-                "ADD_OPTION" => Some(Self::AddOption),
-                "SHOW_OPTIONS" => Some(Self::ShowOptions),
-                "PUSH_STRING" => Some(Self::PushString),
-                "PUSH_FLOAT" => Some(Self::PushFloat),
-                "PUSH_BOOL" => Some(Self::PushBool),
-                "PUSH_NULL" => Some(Self::PushNull),
-                "JUMP_IF_FALSE" => Some(Self::JumpIfFalse),
-                "POP" => Some(Self::Pop),
-                "CALL_FUNC" => Some(Self::CallFunc),
-                "PUSH_VARIABLE" => Some(Self::PushVariable),
-                "STORE_VARIABLE" => Some(Self::StoreVariable),
-                "STOP" => Some(Self::Stop),
-                "RUN_NODE" => Some(Self::RunNode),
-                _ => None,
-            }
-        }
-    }
-}
+pub mod instruction {}
 /// A value used by an Instruction.
 use crate::prelude::*;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
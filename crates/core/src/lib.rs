@@ -13,11 +13,13 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+mod debug_info;
 mod feature_gates;
 mod generated;
 mod internal_value;
 mod library;
 mod line_id;
+mod op_code;
 mod operator;
 mod position;
 pub mod types;
@@ -40,13 +42,15 @@ pub mod prelude {
     };
 
     pub use crate::{
+        debug_info::*,
         generated::{
-            Header, Instruction, InvalidOpCodeError, Node, Operand, Program, instruction::OpCode,
-            operand::Value as OperandValue,
+            Header, Instruction, InvalidOpCodeError, MergePolicy, Node, Operand, Program,
+            ProgramMergeError, operand::Value as OperandValue,
         },
         internal_value::*,
         library::*,
         line_id::*,
+        op_code::*,
         operator::*,
         position::*,
         types::Type,
@@ -5,6 +5,8 @@ use alloc::borrow::Cow;
 use core::fmt::Display;
 
 use hashbrown::hash_map;
+use libm::{ceilf, fabsf, floorf, powf, roundf, truncf};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// A collection of functions that can be called from Yarn scripts.
 ///
@@ -57,6 +59,36 @@ impl Library {
         self.0.get(name)
     }
 
+    /// Checks whether the arguments can be converted to the parameter types of the function registered under `name`.
+    /// Calling a function with arguments that fail this check panics.
+    /// Parameters of type [`YarnValue`] accept any argument.
+    /// Returns `Ok` if there is no function with the given name.
+    pub fn check_arguments(
+        &self,
+        name: &str,
+        arguments: &[YarnValue],
+    ) -> Result<(), YarnValueCastError> {
+        let Some(function) = self.get(name) else {
+            return Ok(());
+        };
+        let parameter_types = function.parameter_types();
+        for (index, argument) in arguments.iter().enumerate() {
+            let r#type = parameter_types
+                .get(index)
+                .and_then(|type_id| Type::try_from(*type_id).ok());
+            match r#type {
+                Some(Type::Number) => {
+                    f32::try_from(argument)?;
+                }
+                Some(Type::Boolean) => {
+                    bool::try_from(argument)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Generates a unique tracking variable name.
     /// This is intended to be used to generate names for visiting.
     /// Ideally these will very reproducible and sensible.
@@ -70,19 +102,102 @@ impl Library {
     /// - `string`: Converts a value to a string.
     /// - `number`: Converts a value to a number.
     /// - `bool`: Converts a value to a boolean.
+    /// - `round`, `floor`, `ceil` and `int`: Convert a number to an integer by rounding it, rounding it down, rounding it up or truncating it.
+    /// - `round_places`: Rounds a number to the given number of decimal places.
+    /// - `inc` and `dec`: Increment or decrement a number to the next integer.
+    /// - `decimal`: Gets the fractional part of a number.
+    /// - `abs`: Gets the absolute value of a number.
+    /// - `min` and `max`: Get the smaller or larger of two numbers.
+    /// - `format_invariant`: Converts a number to a string independently of the current language.
     /// - Comparison operators for numbers, strings, and booleans. (`==`, `!=`, `<`, `<=`, `>`, `>=`)
+    /// - Equality operators for enums. (`==`, `!=`)
     pub fn standard_library() -> Self {
         let mut library = yarn_library!(
             "string" => <String as From<YarnValue >>::from,
-            "number" => |value: YarnValue| f32::try_from(value).expect("Failed to convert a Yarn value to a number"),
-            "bool" => |value: YarnValue| bool::try_from(value).expect("Failed to convert a Yarn value to a bool"),
+            "number" => |value: YarnValue| f32::try_from(value),
+            "bool" => |value: YarnValue| bool::try_from(value),
         );
-        for r#type in [Type::Number, Type::String, Type::Boolean] {
+        for r#type in [
+            Type::Number,
+            Type::String,
+            Type::Boolean,
+            Type::Enum(Default::default()),
+        ] {
             library.add_methods(r#type);
         }
+        library
+            .add_function("min", |a: f32, b: f32| a.min(b))
+            .add_function("max", |a: f32, b: f32| a.max(b))
+            .add_function("format_invariant", |num: f32| num.to_string());
+        library.add_rounding_functions();
         library
     }
 
+    /// Creates a [`Library`] with functions for manipulating strings. Not part of [`Library::standard_library`], so it has to be imported explicitly:
+    /// ```
+    /// # use yarnspinner_core::prelude::*;
+    /// let mut library = Library::standard_library();
+    /// library.import(Library::string_library());
+    /// ```
+    /// The functions are:
+    /// - `length`: Gets the number of characters in a string.
+    /// - `upper` and `lower`: Convert a string to uppercase or lowercase.
+    /// - `contains` and `starts_with`: Check whether a string contains or starts with another string.
+    /// - `substring`: Gets the given number of characters of a string, starting at the given character index.
+    /// - `replace`: Replaces all occurrences of a string with another string.
+    /// - `trim`: Removes whitespace from the start and end of a string.
+    ///
+    /// A character is an extended grapheme cluster, i.e. what a reader perceives as a single character.
+    /// This means that e.g. `length("é")` is 1 no matter if the `é` is stored as one code point or as an `e` followed by a combining accent.
+    /// Out of range indices of `substring` are clamped to the string.
+    ///
+    /// ## Implementation Notes
+    ///
+    /// The original implementation has no equivalent of this library.
+    pub fn string_library() -> Self {
+        yarn_library!(
+            "length" => |string: &str| string.graphemes(true).count(),
+            "upper" => |string: &str| string.to_uppercase(),
+            "lower" => |string: &str| string.to_lowercase(),
+            "contains" => |string: &str, pattern: &str| find_graphemes(&graphemes(string), &graphemes(pattern)).is_some(),
+            "starts_with" => |string: &str, pattern: &str| graphemes(string).starts_with(&graphemes(pattern)),
+            "substring" => |string: &str, start: f32, length: f32| {
+                // Float to integer casts saturate, so negative numbers become 0
+                string.graphemes(true).skip(start as usize).take(length as usize).collect::<String>()
+            },
+            "replace" => replace_graphemes,
+            "trim" => |string: &str| string.trim().to_owned(),
+        )
+    }
+
+    /// Registers the functions of the standard library that round numbers.
+    /// They use `libm`, so that they are also available without the `std` feature.
+    fn add_rounding_functions(&mut self) {
+        self.add_function("round", |num: f32| roundf(num) as i32)
+            .add_function("round_places", |num: f32, places: f32| {
+                num.round_places(places)
+            })
+            .add_function("floor", |num: f32| floorf(num) as i32)
+            .add_function("ceil", |num: f32| ceilf(num) as i32)
+            .add_function("inc", |num: f32| {
+                if let Some(num) = num.as_int() {
+                    num + 1
+                } else {
+                    ceilf(num) as i32
+                }
+            })
+            .add_function("dec", |num: f32| {
+                if let Some(num) = num.as_int() {
+                    num - 1
+                } else {
+                    floorf(num) as i32
+                }
+            })
+            .add_function("decimal", |num: f32| num - truncf(num))
+            .add_function("int", |num: f32| truncf(num) as i32)
+            .add_function("abs", |num: f32| fabsf(num));
+    }
+
     /// Adds a new function to the registry. See [`YarnFn`]'s documentation for what kinds of functions are allowed.
     ///
     /// ## Examples
@@ -139,7 +254,7 @@ impl Library {
     where
         Marker: 'static,
         F: YarnFn<Marker> + 'static + Clone,
-        F::Out: IntoYarnValueFromNonYarnValue + 'static + Clone,
+        F::Out: IntoYarnValueFromNonYarnValue + 'static,
     {
         self.0.register_function(name, function);
         self
@@ -211,3 +326,134 @@ macro_rules! yarn_library {
     };
 }
 pub use yarn_library;
+
+fn graphemes(string: &str) -> Vec<&str> {
+    string.graphemes(true).collect()
+}
+
+/// Finds the index of the first occurrence of `pattern` in `graphemes`. An empty pattern is found at index 0.
+fn find_graphemes(graphemes: &[&str], pattern: &[&str]) -> Option<usize> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    graphemes
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+}
+
+/// Replaces all occurrences of `from` with `to`, without splitting graphemes. An empty `from` leaves the string unchanged.
+fn replace_graphemes(string: &str, from: &str, to: &str) -> String {
+    let from = graphemes(from);
+    if from.is_empty() {
+        return string.to_owned();
+    }
+    let mut remaining = &graphemes(string)[..];
+    let mut result = String::with_capacity(string.len());
+    while let Some(index) = find_graphemes(remaining, &from) {
+        result.extend(remaining[..index].iter().copied());
+        result.push_str(to);
+        remaining = &remaining[index + from.len()..];
+    }
+    result.extend(remaining.iter().copied());
+    result
+}
+
+trait FloatExt: Copy {
+    fn as_int(self) -> Option<i32>;
+    fn round_places(self, places: Self) -> Result<Self, String>;
+}
+
+impl FloatExt for f32 {
+    fn as_int(self) -> Option<i32> {
+        (fabsf(self - truncf(self)) <= f32::EPSILON).then_some(self as i32)
+    }
+
+    fn round_places(self, places: Self) -> Result<Self, String> {
+        if places < 0.0 || truncf(places) != places {
+            return Err(format!(
+                "The number of decimal places must be a whole number that is not negative, but was {places}"
+            ));
+        }
+        let factor = powf(10.0, places);
+        let rounded = roundf(self * factor) / factor;
+        // More places than an `f32` can represent leave the number unchanged
+        Ok(if rounded.is_finite() { rounded } else { self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_places() {
+        for (num, places, expected) in [
+            (1.0, 0, 1.0),
+            (1.2, 1, 1.2),
+            (0.4, 0, 0.0),
+            (43.132, 0, 43.0),
+            (1.1, 2, 1.1),
+            (123.123, 3, 123.123),
+            (-10.3, 1, -10.3),
+            (-11.99, 1, -12.0),
+            (1.5, 10, 1.5),
+            (1.5, 40, 1.5),
+            (1.5, u32::MAX, 1.5),
+        ] {
+            assert_eq!(Ok(expected), num.round_places(places as f32));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_places() {
+        for places in [-1.0, 0.5, f32::NAN] {
+            assert!(
+                1.5.round_places(places).is_err(),
+                "Accepted {places} places"
+            );
+        }
+    }
+
+    #[test]
+    fn standard_library_contains_math_functions() {
+        let library = Library::standard_library();
+        for name in [
+            "round",
+            "round_places",
+            "floor",
+            "ceil",
+            "inc",
+            "dec",
+            "decimal",
+            "int",
+            "min",
+            "max",
+            "abs",
+            "format_invariant",
+        ] {
+            assert!(library.contains_function(name), "Missing function {name}");
+        }
+    }
+
+    #[test]
+    fn replaces_whole_graphemes() {
+        for (string, from, to, expected) in [
+            ("a-b-c", "-", "+", "a+b+c"),
+            ("aaa", "aa", "b", "ba"),
+            ("abc", "", "x", "abc"),
+            ("e\u{301}e", "e", "i", "e\u{301}i"),
+            ("e\u{301}e", "e\u{301}", "i", "ie"),
+        ] {
+            assert_eq!(expected, replace_graphemes(string, from, to));
+        }
+    }
+
+    #[test]
+    fn finds_whole_graphemes() {
+        let string = graphemes("ae\u{301}e");
+        assert_eq!(Some(2), find_graphemes(&string, &["e"]));
+        assert_eq!(Some(1), find_graphemes(&string, &["e\u{301}", "e"]));
+        assert_eq!(Some(0), find_graphemes(&string, &[]));
+        assert_eq!(None, find_graphemes(&string, &["b"]));
+    }
+}
@@ -0,0 +1,112 @@
+//! The type of enums declared in Yarn scripts with `<<enum>>` statements.
+
+use crate::prelude::*;
+use crate::types::{Type, TypeProperties, TypedValue};
+use core::hash::{Hash, Hasher};
+
+/// Enums are compared by their raw values, so every enum shares the same methods.
+pub(crate) fn enum_type_properties(enum_type: &EnumType) -> TypeProperties {
+    TypeProperties::from_name("Enum")
+        .with_description(format!("Enum {}", enum_type.name))
+        .with_methods(yarn_library! {
+            Operator::EqualTo => |a: YarnValue, b: YarnValue| a == b,
+            Operator::NotEqualTo => |a: YarnValue, b: YarnValue| a != b,
+        })
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Default, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+/// A type that represents an enum declared in a Yarn script, e.g.
+/// ```text
+/// <<enum Food>>
+///     <<case Apple>>
+///     <<case Orange>>
+/// <<endenum>>
+/// ```
+///
+/// Values of an enum are represented by the raw value of their case, which is either a number or a string.
+/// This is also what gets stored in the variable storage.
+/// Two enums are the same type if they have the same name.
+pub struct EnumType {
+    /// The name of the enum, e.g. `Food`.
+    pub name: String,
+    /// The cases of the enum, in the order they were declared.
+    pub cases: Vec<EnumCase>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+/// A case of an [`EnumType`].
+pub struct EnumCase {
+    /// The name of the case, e.g. `Apple`.
+    pub name: String,
+    /// The value that represents this case at runtime. Either a [`YarnValue::Number`] or a [`YarnValue::String`].
+    pub raw_value: YarnValue,
+}
+
+impl PartialEq for EnumType {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for EnumType {}
+
+impl Hash for EnumType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl From<EnumType> for Type {
+    fn from(enum_type: EnumType) -> Self {
+        Type::Enum(enum_type)
+    }
+}
+
+impl EnumType {
+    /// Creates a new enum without any cases.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            cases: Vec::new(),
+        }
+    }
+
+    /// Adds a case to the enum.
+    pub fn with_case(mut self, name: impl Into<String>, raw_value: impl Into<YarnValue>) -> Self {
+        self.cases.push(EnumCase {
+            name: name.into(),
+            raw_value: raw_value.into(),
+        });
+        self
+    }
+
+    /// Gets a case by its name.
+    pub fn case(&self, name: &str) -> Option<&EnumCase> {
+        self.cases.iter().find(|case| case.name == name)
+    }
+
+    /// Gets the case represented by the given raw value, e.g. one read from the variable storage.
+    pub fn case_for_raw_value(&self, raw_value: &YarnValue) -> Option<&EnumCase> {
+        self.cases.iter().find(|case| &case.raw_value == raw_value)
+    }
+
+    /// The type of the raw values of the cases, i.e. either [`Type::Number`] or [`Type::String`].
+    /// Returns `None` if the enum has no cases.
+    pub fn raw_type(&self) -> Option<Type> {
+        self.cases.first().map(|case| case.raw_value.r#type())
+    }
+}
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 use crate::types::any::any_type_properties;
 use crate::types::boolean::boolean_type_properties;
+use crate::types::r#enum::enum_type_properties;
 use crate::types::number::number_type_properties;
 use crate::types::string::string_type_properties;
 use crate::types::*;
@@ -36,6 +37,8 @@ pub enum Type {
     Any,
     /// The type representing booleans
     Boolean,
+    /// The type representing an enum declared in a Yarn script
+    Enum(EnumType),
     /// The type representing functions
     Function(FunctionType),
     /// The type representing numbers
@@ -49,6 +52,7 @@ impl Display for Type {
         let name = self.name();
         match self {
             Type::Function(function) => Display::fmt(function, f),
+            Type::Enum(enum_type) => write!(f, "{}", enum_type.name),
             _ => write!(f, "{name}"),
         }
     }
@@ -96,6 +100,7 @@ impl Type {
         match self {
             Type::Any => any_type_properties(),
             Type::Boolean => boolean_type_properties(),
+            Type::Enum(enum_type) => enum_type_properties(enum_type),
             Type::Function(function_type) => function_type_properties(function_type),
             Type::Number => number_type_properties(),
             Type::String => string_type_properties(),
@@ -122,7 +127,8 @@ impl Type {
         Type::Number,
         Type::String,
         Type::Boolean,
-        // Functions are not explicitly constructable
+        // Functions are not explicitly constructable,
+        // and enums are constructed from their cases
     ];
 }
 
@@ -42,7 +42,7 @@ impl YarnFnRegistry {
     where
         Marker: 'static,
         F: YarnFn<Marker> + 'static + Clone,
-        F::Out: IntoYarnValueFromNonYarnValue + 'static + Clone,
+        F::Out: IntoYarnValueFromNonYarnValue + 'static,
     {
         let name = name.into();
         let wrapped = YarnFnWrapper::from(function);
@@ -112,7 +112,7 @@ mod tests {
         let result = function.call_with_world(params, &mut World::default());
         #[cfg(not(feature = "bevy"))]
         let result = function.call(params);
-        let result: bool = result.try_into().unwrap();
+        let result: bool = result.unwrap().try_into().unwrap();
 
         assert!(result);
     }
@@ -128,7 +128,7 @@ mod tests {
         let result = function.call_with_world(params, &mut World::default());
         #[cfg(not(feature = "bevy"))]
         let result = function.call(params);
-        let result: f32 = result.try_into().unwrap();
+        let result: f32 = result.unwrap().try_into().unwrap();
 
         assert_eq!(result, 1.0);
     }
@@ -160,7 +160,7 @@ mod tests {
         let result = function1.call_with_world(params, &mut world);
         #[cfg(not(feature = "bevy"))]
         let result = function1.call(params);
-        let result: bool = result.try_into().unwrap();
+        let result: bool = result.unwrap().try_into().unwrap();
         assert!(result);
     }
 
@@ -190,12 +190,12 @@ mod tests {
         let result1 = function1.call_with_world(params1, &mut world);
         #[cfg(not(feature = "bevy"))]
         let result1 = function1.call(params1);
-        let result1: bool = result1.try_into().unwrap();
+        let result1: bool = result1.unwrap().try_into().unwrap();
         #[cfg(feature = "bevy")]
         let result2 = function2.call_with_world(params2, &mut world);
         #[cfg(not(feature = "bevy"))]
         let result2 = function2.call(params2);
-        let result2: f32 = result2.try_into().unwrap();
+        let result2: f32 = result2.unwrap().try_into().unwrap();
 
         assert!(result1);
         assert_eq!(result2, 1.0);
@@ -235,22 +235,22 @@ mod tests {
         let result1 = function1.call_with_world(params1, &mut world);
         #[cfg(not(feature = "bevy"))]
         let result1 = function1.call(params1);
-        let result1: bool = result1.try_into().unwrap();
+        let result1: bool = result1.unwrap().try_into().unwrap();
         #[cfg(feature = "bevy")]
         let result2 = function2.call_with_world(params2, &mut world);
         #[cfg(not(feature = "bevy"))]
         let result2 = function2.call(params2);
-        let result2: f32 = result2.try_into().unwrap();
+        let result2: f32 = result2.unwrap().try_into().unwrap();
         #[cfg(feature = "bevy")]
         let result3 = function3.call_with_world(params3, &mut world);
         #[cfg(not(feature = "bevy"))]
         let result3 = function3.call(params3);
-        let result3: f32 = result3.try_into().unwrap();
+        let result3: f32 = result3.unwrap().try_into().unwrap();
         #[cfg(feature = "bevy")]
         let result4 = function4.call_with_world(params4, &mut world);
         #[cfg(not(feature = "bevy"))]
         let result4 = function4.call(params4);
-        let result4: String = result4.into();
+        let result4: String = result4.unwrap().into();
 
         assert!(result1);
         assert_eq!(result2, 3.0);
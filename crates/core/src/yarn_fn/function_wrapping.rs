@@ -21,6 +21,7 @@ use variadics_please::all_tuples;
 ///   - [`bool`]
 ///   - A numeric type, i.e. one of [`f32`], [`f64`], [`i8`], [`i16`], [`i32`], [`i64`], [`i128`], [`u8`], [`u16`], [`u32`], [`u64`], [`u128`], [`usize`], [`isize`]
 ///   - [`String`]
+///   - A [`Result`] of one of the above types, whose error implements [`Display`]. Returning an `Err` stops the dialogue with an error.
 ///
 /// If the `bevy` feature is active then it is also possible to register a Bevy `System` and call it from Yarn. The `System` will receive the parameters passed to the yarn
 /// as it's input. The `System`'s input must adhere to the same rules as given above for regular function parameters with the exception that System functions cannot accept
@@ -75,7 +76,7 @@ pub trait YarnFn<Marker>: Clone + Send + Sync {
     fn parameter_types(&self) -> Vec<TypeId>;
     /// The [`TypeId`] of the return type of this function.
     fn return_type(&self) -> TypeId {
-        TypeId::of::<Self::Out>()
+        Self::Out::value_type_id()
     }
 }
 
@@ -83,10 +84,14 @@ pub trait YarnFn<Marker>: Clone + Send + Sync {
 /// See its documentation for more information about what kind of functions are allowed.
 pub trait UntypedYarnFn: Debug + Display + Send + Sync {
     #[doc(hidden)]
-    fn call(&self, input: Vec<YarnValue>) -> YarnValue;
+    fn call(&self, input: Vec<YarnValue>) -> Result<YarnValue, String>;
     #[cfg(feature = "bevy")]
     #[doc(hidden)]
-    fn call_with_world(&self, input: Vec<YarnValue>, world: &mut World) -> YarnValue;
+    fn call_with_world(
+        &self,
+        input: Vec<YarnValue>,
+        world: &mut World,
+    ) -> Result<YarnValue, String>;
     #[doc(hidden)]
     fn clone_box(&self) -> Box<dyn UntypedYarnFn>;
     /// The [`TypeId`]s of the parameters of this function.
@@ -105,14 +110,18 @@ impl<Marker, F> UntypedYarnFn for YarnFnWrapper<Marker, F>
 where
     Marker: 'static,
     F: YarnFn<Marker> + 'static + Clone,
-    F::Out: IntoYarnValueFromNonYarnValue + 'static + Clone,
+    F::Out: IntoYarnValueFromNonYarnValue + 'static,
 {
-    fn call(&self, input: Vec<YarnValue>) -> YarnValue {
+    fn call(&self, input: Vec<YarnValue>) -> Result<YarnValue, String> {
         self.function.call(input).into_yarn_value()
     }
 
     #[cfg(feature = "bevy")]
-    fn call_with_world(&self, input: Vec<YarnValue>, world: &mut World) -> YarnValue {
+    fn call_with_world(
+        &self,
+        input: Vec<YarnValue>,
+        world: &mut World,
+    ) -> Result<YarnValue, String> {
         self.function
             .call_with_world(input, world)
             .into_yarn_value()
@@ -204,8 +213,8 @@ impl Eq for Box<dyn UntypedYarnFn> {}
 /// This is useful when registering functions in a [`Library`] with [`Library::add_function`].
 #[macro_export]
 macro_rules! yarn_fn_type {
-    (impl Fn($($param:ty),+) -> $ret:ty) => {
-        impl $crate::prelude::YarnFn<fn($($param),+) -> $ret, Out = $ret>
+    (impl Fn($($param:ty),*) -> $ret:ty) => {
+        impl $crate::prelude::YarnFn<fn($($param),*) -> $ret, Out = $ret>
     };
 }
 pub use yarn_fn_type;
@@ -1,5 +1,6 @@
 //! Implements a subset of dotnet's [`Convert`](https://learn.microsoft.com/en-us/dotnet/api/system.convert?view=net-8.0) type.
 use crate::prelude::*;
+use core::any::TypeId;
 use core::error::Error;
 use core::fmt::{Display, Formatter};
 
@@ -34,9 +35,33 @@ pub enum YarnValue {
 ///
 /// Needed to ensure that the return type of a registered function is
 /// able to be turned into a [`YarnValue`], but not a [`YarnValue`] itself.
+///
+/// A function may also return a [`Result`] of such a value. An `Err` stops the dialogue with an error carrying the displayed message.
 pub trait IntoYarnValueFromNonYarnValue {
     #[doc(hidden)]
-    fn into_yarn_value(self) -> YarnValue;
+    fn into_yarn_value(self) -> Result<YarnValue, String>;
+
+    #[doc(hidden)]
+    fn value_type_id() -> TypeId
+    where
+        Self: Sized + 'static,
+    {
+        TypeId::of::<Self>()
+    }
+}
+
+impl<T, E> IntoYarnValueFromNonYarnValue for Result<T, E>
+where
+    T: IntoYarnValueFromNonYarnValue + 'static,
+    E: Display,
+{
+    fn into_yarn_value(self) -> Result<YarnValue, String> {
+        self.map_err(|error| error.to_string())?.into_yarn_value()
+    }
+
+    fn value_type_id() -> TypeId {
+        T::value_type_id()
+    }
 }
 
 impl YarnValue {
@@ -91,8 +116,8 @@ macro_rules! impl_floating_point {
 
 
             impl IntoYarnValueFromNonYarnValue for $from_type {
-                fn into_yarn_value(self) -> YarnValue {
-                    self.into()
+                fn into_yarn_value(self) -> Result<YarnValue, String> {
+                    Ok(self.into())
                 }
             }
         )*
@@ -127,8 +152,8 @@ macro_rules! impl_whole_number {
             }
 
             impl IntoYarnValueFromNonYarnValue for $from_type {
-                fn into_yarn_value(self) -> YarnValue {
-                    self.into()
+                fn into_yarn_value(self) -> Result<YarnValue, String> {
+                    Ok(self.into())
                 }
             }
         )*
@@ -168,8 +193,8 @@ impl From<&str> for YarnValue {
 }
 
 impl IntoYarnValueFromNonYarnValue for String {
-    fn into_yarn_value(self) -> YarnValue {
-        self.into()
+    fn into_yarn_value(self) -> Result<YarnValue, String> {
+        Ok(self.into())
     }
 }
 
@@ -200,8 +225,8 @@ impl From<bool> for YarnValue {
 }
 
 impl IntoYarnValueFromNonYarnValue for bool {
-    fn into_yarn_value(self) -> YarnValue {
-        self.into()
+    fn into_yarn_value(self) -> Result<YarnValue, String> {
+        Ok(self.into())
     }
 }
 
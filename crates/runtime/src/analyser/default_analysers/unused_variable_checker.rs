@@ -23,7 +23,7 @@ impl CompiledProgramAnalyser for UnusedVariableChecker {
             node.instructions
                 .iter()
                 .filter_map(|instruction| match instruction.opcode() {
-                    OpCode::PushVariable | OpCode::StoreVariable => {
+                    OpCode::PushVariable | OpCode::StoreVariable | OpCode::StoreLocalVariable => {
                         Some((instruction.opcode(), instruction.operands[0].clone()))
                     }
                     _ => None,
@@ -35,7 +35,7 @@ impl CompiledProgramAnalyser for UnusedVariableChecker {
                 OpCode::PushVariable => {
                     self.read_variables.insert(variable);
                 }
-                OpCode::StoreVariable => {
+                OpCode::StoreVariable | OpCode::StoreLocalVariable => {
                     self.written_variables.insert(variable);
                 }
                 _ => unreachable!(),
@@ -22,7 +22,7 @@ impl CompiledProgramAnalyser for VariableLister {
             node.instructions
                 .iter()
                 .filter_map(|instruction| match instruction.opcode() {
-                    OpCode::PushVariable | OpCode::StoreVariable => {
+                    OpCode::PushVariable | OpCode::StoreVariable | OpCode::StoreLocalVariable => {
                         Some(instruction.operands[0].clone())
                     }
                     _ => None,
@@ -0,0 +1,329 @@
+//! Contains [`DialogueDebugger`], which runs a [`Dialogue`] step by step and pauses it at [`Breakpoint`]s.
+//!
+//! ## Implementation notes
+//!
+//! This has no equivalent in the original implementation. The source positions of instructions
+//! are passed in from the compiler's output with [`DialogueDebugger::add_debug_info`].
+
+use crate::Result;
+use crate::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::World;
+use bevy_platform::collections::HashMap;
+
+/// A place where a [`DialogueDebugger`] pauses execution.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Breakpoint {
+    /// Pauses before the first instruction of the node with this name.
+    Node(String),
+    /// Pauses before the line with this ID is delivered, or before it is added as an option.
+    Line(LineId),
+    /// Pauses before the first instruction generated for a line of a Yarn file.
+    /// Only works for nodes whose debug info was passed to [`DialogueDebugger::add_debug_info`].
+    SourceLine {
+        /// The name of the file, as passed to the compiler.
+        file_name: String,
+        /// The zero-indexed line in the file, like [`Position::line`].
+        line: usize,
+    },
+}
+
+/// Runs a [`Dialogue`] one instruction or statement at a time, pauses it at [`Breakpoint`]s,
+/// and lets you inspect the virtual machine while it is paused.
+///
+/// Use [`DialogueDebugger::continue_`] in place of [`Dialogue::continue_`]. When it returns because a breakpoint was hit,
+/// [`DialogueDebugger::hit_breakpoint`] tells you which one, and the instruction at the breakpoint has not been run yet.
+/// Calling any of the running methods again resumes from there.
+///
+/// ```rust
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// # fn debug(dialogue: Dialogue) -> yarnspinner_runtime::Result<()> {
+/// let mut debugger = DialogueDebugger::new(dialogue);
+/// debugger.add_breakpoint(Breakpoint::Node("Shop".to_owned()));
+/// debugger.continue_()?;
+/// if debugger.hit_breakpoint().is_some() {
+///     println!("Paused in {:?} with the stack {:?}", debugger.dialogue().current_node(), debugger.value_stack());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DialogueDebugger {
+    dialogue: Dialogue,
+    breakpoints: Vec<Breakpoint>,
+    debug_info: HashMap<String, DebugInfo>,
+    hit_breakpoint: Option<Breakpoint>,
+    /// The node name and program counter where execution last paused.
+    paused_at: Option<(String, usize)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepMode {
+    /// Runs until a breakpoint is hit or the dialogue stops on its own.
+    Continue,
+    /// Runs a single instruction.
+    Instruction,
+    /// Runs until the next instruction belongs to a different line of the source file.
+    Statement,
+}
+
+impl DialogueDebugger {
+    /// Creates a new debugger that runs the given [`Dialogue`].
+    #[must_use]
+    pub fn new(dialogue: Dialogue) -> Self {
+        Self {
+            dialogue,
+            breakpoints: Default::default(),
+            debug_info: Default::default(),
+            hit_breakpoint: Default::default(),
+            paused_at: Default::default(),
+        }
+    }
+
+    /// The [`Dialogue`] being debugged.
+    #[must_use]
+    pub fn dialogue(&self) -> &Dialogue {
+        &self.dialogue
+    }
+
+    /// The [`Dialogue`] being debugged. Use this to e.g. call [`Dialogue::set_node`] or [`Dialogue::set_selected_option`].
+    #[must_use]
+    pub fn dialogue_mut(&mut self) -> &mut Dialogue {
+        &mut self.dialogue
+    }
+
+    /// Stops debugging and returns the [`Dialogue`].
+    #[must_use]
+    pub fn into_dialogue(self) -> Dialogue {
+        self.dialogue
+    }
+
+    /// Adds a breakpoint. Adding the same breakpoint twice has no effect.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> &mut Self {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+        self
+    }
+
+    /// Removes a breakpoint. Returns `true` if it was present.
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let breakpoint_count = self.breakpoints.len();
+        self.breakpoints.retain(|other| other != breakpoint);
+        self.breakpoints.len() != breakpoint_count
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) -> &mut Self {
+        self.breakpoints.clear();
+        self
+    }
+
+    /// The breakpoints that were added, in the order they were added in.
+    #[must_use]
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Registers where the instructions of a node come from, which is needed for [`Breakpoint::SourceLine`] and [`DialogueDebugger::step_statement`].
+    /// Takes the [`DebugInfo`] the compiler produced for the node, so you can register a whole compilation with
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use yarnspinner_core::prelude::*;
+    /// # use yarnspinner_runtime::prelude::*;
+    /// # fn register(debugger: &mut DialogueDebugger, compilation_debug_info: &HashMap<String, DebugInfo>) {
+    /// // `compilation_debug_info` is the `debug_info` of the compiler's `Compilation`
+    /// for debug_info in compilation_debug_info.values() {
+    ///     debugger.add_debug_info(debug_info.clone());
+    /// }
+    /// # }
+    /// ```
+    pub fn add_debug_info(&mut self, debug_info: DebugInfo) -> &mut Self {
+        self.debug_info
+            .insert(debug_info.node_name.clone(), debug_info);
+        self
+    }
+
+    /// The breakpoint that paused execution, if the last call to a running method returned because of one.
+    #[must_use]
+    pub fn hit_breakpoint(&self) -> Option<&Breakpoint> {
+        self.hit_breakpoint.as_ref()
+    }
+
+    /// The index of the next instruction that will be run in [`Dialogue::current_node`].
+    #[must_use]
+    pub fn instruction_index(&self) -> Option<usize> {
+        self.dialogue.vm.program_counter()
+    }
+
+    /// The file name and position of the statement the next instruction was generated from,
+    /// if debug info was registered for the current node.
+    #[must_use]
+    pub fn source_position(&self) -> Option<(&str, Position)> {
+        let node_name = self.dialogue.vm.current_node()?;
+        let instruction_index = self.instruction_index()?;
+        source_position_of(&self.debug_info, &node_name, instruction_index)
+    }
+
+    /// The values on the virtual machine's stack, with the top of the stack last.
+    #[must_use]
+    pub fn value_stack(&self) -> Vec<YarnValue> {
+        self.dialogue.vm.value_stack()
+    }
+
+    /// The options that were added so far and will be delivered by the next [`DialogueEvent::Options`],
+    /// or that are waiting for a selection if that event was already delivered.
+    #[must_use]
+    pub fn pending_options(&self) -> &[DialogueOption] {
+        self.dialogue.vm.current_options()
+    }
+
+    /// Starts or resumes execution like [`Dialogue::continue_`], but pauses when a breakpoint is hit.
+    /// Returns the events that happened until then.
+    pub fn continue_(&mut self) -> Result<Vec<DialogueEvent>> {
+        self.run(StepMode::Continue, |function, parameters| {
+            function.call(parameters)
+        })
+    }
+
+    /// Runs a single instruction of the virtual machine.
+    pub fn step_instruction(&mut self) -> Result<Vec<DialogueEvent>> {
+        self.run(StepMode::Instruction, |function, parameters| {
+            function.call(parameters)
+        })
+    }
+
+    /// Runs all instructions generated for the current statement, i.e. until the next instruction comes from another line of the Yarn file.
+    /// Behaves like [`DialogueDebugger::step_instruction`] in nodes without debug info.
+    pub fn step_statement(&mut self) -> Result<Vec<DialogueEvent>> {
+        self.run(StepMode::Statement, |function, parameters| {
+            function.call(parameters)
+        })
+    }
+
+    #[cfg(feature = "bevy")]
+    /// The Bevy version of [`DialogueDebugger::continue_`].
+    pub fn continue_with_world(&mut self, world: &mut World) -> Result<Vec<DialogueEvent>> {
+        self.run(StepMode::Continue, |function, parameters| {
+            function.call_with_world(parameters, world)
+        })
+    }
+
+    #[cfg(feature = "bevy")]
+    /// The Bevy version of [`DialogueDebugger::step_instruction`].
+    pub fn step_instruction_with_world(&mut self, world: &mut World) -> Result<Vec<DialogueEvent>> {
+        self.run(StepMode::Instruction, |function, parameters| {
+            function.call_with_world(parameters, world)
+        })
+    }
+
+    #[cfg(feature = "bevy")]
+    /// The Bevy version of [`DialogueDebugger::step_statement`].
+    pub fn step_statement_with_world(&mut self, world: &mut World) -> Result<Vec<DialogueEvent>> {
+        self.run(StepMode::Statement, |function, parameters| {
+            function.call_with_world(parameters, world)
+        })
+    }
+
+    fn run(
+        &mut self,
+        mode: StepMode,
+        mut function_call_fn: impl FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> FunctionResult,
+    ) -> Result<Vec<DialogueEvent>> {
+        let start_line = self
+            .source_position()
+            .map(|(file_name, position)| (file_name.to_owned(), position.line));
+        let resumed_at = self.paused_at.take();
+        let mut is_first_instruction = true;
+        let mut hit_breakpoint = None;
+        let mut paused_at = None;
+
+        let Self {
+            dialogue,
+            breakpoints,
+            debug_info,
+            ..
+        } = self;
+        let events = dialogue.vm.continue_until(
+            |vm, instruction| vm.run_instruction(instruction, &mut function_call_fn),
+            |node, program_counter| {
+                let is_first = core::mem::replace(&mut is_first_instruction, false);
+                let should_pause = match mode {
+                    StepMode::Continue => {
+                        // Don't pause again where we paused last time
+                        let is_resuming = is_first
+                            && resumed_at.as_ref().is_some_and(|(node_name, index)| {
+                                *node_name == node.name && *index == program_counter
+                            });
+                        hit_breakpoint = (!is_resuming)
+                            .then(|| breakpoint_at(breakpoints, debug_info, node, program_counter))
+                            .flatten()
+                            .cloned();
+                        hit_breakpoint.is_some()
+                    }
+                    StepMode::Instruction => !is_first,
+                    StepMode::Statement => {
+                        let line = source_position_of(debug_info, &node.name, program_counter)
+                            .map(|(file_name, position)| (file_name.to_owned(), position.line));
+                        // Instructions without a position belong to the statement before them
+                        !is_first
+                            && (start_line.is_none()
+                                || line.is_some_and(|line| Some(line) != start_line))
+                    }
+                };
+                if should_pause {
+                    paused_at = Some((node.name.clone(), program_counter));
+                }
+                should_pause
+            },
+        );
+        if let Ok(events) = &events {
+            dialogue.record_delivered_lines(events);
+        }
+        self.hit_breakpoint = hit_breakpoint;
+        self.paused_at = paused_at;
+        events
+    }
+}
+
+fn breakpoint_at<'a>(
+    breakpoints: &'a [Breakpoint],
+    debug_info: &HashMap<String, DebugInfo>,
+    node: &Node,
+    program_counter: usize,
+) -> Option<&'a Breakpoint> {
+    let instruction = node.instructions.get(program_counter)?;
+    breakpoints.iter().find(|breakpoint| match breakpoint {
+        Breakpoint::Node(node_name) => *node_name == node.name && program_counter == 0,
+        Breakpoint::Line(line_id) => {
+            [OpCode::RunLine as i32, OpCode::AddOption as i32].contains(&instruction.opcode)
+                && instruction.read_operand::<String>(0).as_ref() == Some(&line_id.0)
+        }
+        Breakpoint::SourceLine { file_name, line } => {
+            let is_on_line = |index| {
+                source_position_of(debug_info, &node.name, index).is_some_and(
+                    |(other_file_name, position)| {
+                        other_file_name == file_name && position.line == *line
+                    },
+                )
+            };
+            // Only pause at the first of the instructions generated for the line
+            is_on_line(program_counter)
+                && (program_counter == 0 || !is_on_line(program_counter - 1))
+        }
+    })
+}
+
+fn source_position_of<'a>(
+    debug_info: &'a HashMap<String, DebugInfo>,
+    node_name: &str,
+    instruction_index: usize,
+) -> Option<(&'a str, Position)> {
+    let debug_info = debug_info.get(node_name)?;
+    let position = debug_info
+        .line_positions
+        .get(&instruction_index)?
+        .as_ref()?;
+    Some((debug_info.file_name.as_str(), *position))
+}
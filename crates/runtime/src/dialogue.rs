@@ -15,8 +15,10 @@ use yarnspinner_core::prelude::*;
 /// The main functions of interest are [`Dialogue::continue_`] and [`Dialogue::set_selected_option`].
 #[derive(Debug, Clone)]
 pub struct Dialogue {
-    vm: VirtualMachine,
+    pub(crate) vm: VirtualMachine,
     language_code: Option<Language>,
+    history: Option<DialogueHistory>,
+    seen_line_tracker: Option<SeenLineTracker>,
 }
 
 #[allow(missing_docs)]
@@ -46,6 +48,69 @@ pub enum DialogueError {
         function_name: String,
         library: Library,
     },
+    UnsupportedSnapshotVersion {
+        version: u32,
+        supported_version: u32,
+    },
+    SnapshotProgramMismatch {
+        snapshot_checksum: u64,
+        program_checksum: u64,
+    },
+    NotANodeGroup {
+        node_name: String,
+    },
+    NotASmartVariable {
+        variable_name: String,
+    },
+    StackUnderflow {
+        node_name: String,
+        instruction_index: usize,
+    },
+    InvalidOperand {
+        node_name: String,
+        instruction_index: usize,
+        operand_index: usize,
+    },
+    UnknownOpCode {
+        node_name: String,
+        instruction_index: usize,
+        opcode: i32,
+    },
+    UnknownLabel {
+        node_name: String,
+        instruction_index: usize,
+        label_name: String,
+    },
+    TypeConversionFailed {
+        node_name: String,
+        instruction_index: usize,
+        message: String,
+    },
+    FunctionParameterCountMismatch {
+        node_name: String,
+        instruction_index: usize,
+        function_name: String,
+        expected_parameter_count: usize,
+        actual_parameter_count: usize,
+    },
+    FunctionFailed {
+        node_name: String,
+        instruction_index: usize,
+        function_name: String,
+        message: String,
+    },
+    InstructionBudgetExceeded {
+        node_name: String,
+        instruction_budget: usize,
+        /// The nodes the dialogue jumped through since it last entered `node_name`, ending with `node_name`.
+        /// Contains only `node_name` if the dialogue loops within a single node.
+        jump_cycle: Vec<String>,
+    },
+    RewindOutOfRange {
+        steps: usize,
+        recorded_lines: usize,
+    },
+    ProgramMergeError(ProgramMergeError),
 }
 
 impl Error for DialogueError {
@@ -54,6 +119,7 @@ impl Error for DialogueError {
         match self {
             MarkupParseError(e) => e.source(),
             VariableStorageError(e) => e.source(),
+            ProgramMergeError(e) => e.source(),
             _ => None,
         }
     }
@@ -73,6 +139,20 @@ impl Display for DialogueError {
             InvalidNode { node_name } => write!(f, "No node named \"{node_name}\" has been loaded."),
             VariableStorageError(e) => Display::fmt(e, f),
             FunctionNotFound { function_name, library } => write!(f, "Function \"{function_name}\" not found in library: {library}"),
+            UnsupportedSnapshotVersion { version, supported_version } => write!(f, "Cannot restore a dialogue snapshot with version {version}. Only version {supported_version} is supported."),
+            SnapshotProgramMismatch { snapshot_checksum, program_checksum } => write!(f, "Cannot restore a dialogue snapshot taken from a different program (snapshot program checksum: {snapshot_checksum:#018x}, loaded program checksum: {program_checksum:#018x})."),
+            NotANodeGroup { node_name } => write!(f, "The node \"{node_name}\" is not a node group."),
+            NotASmartVariable { variable_name } => write!(f, "The variable \"{variable_name}\" is not a smart variable."),
+            StackUnderflow { node_name, instruction_index } => write!(f, "Instruction {instruction_index} of node \"{node_name}\" tried to take a value from the stack, but the stack was empty. The program may be malformed; try re-compiling it."),
+            InvalidOperand { node_name, instruction_index, operand_index } => write!(f, "Operand {operand_index} of instruction {instruction_index} of node \"{node_name}\" is missing or has the wrong type. The program may have been compiled with an incompatible compiler; try re-compiling it."),
+            UnknownOpCode { node_name, instruction_index, opcode } => write!(f, "Instruction {instruction_index} of node \"{node_name}\" has the op code {opcode}, which is unknown or no longer supported. Try re-compiling the program."),
+            UnknownLabel { node_name, instruction_index, label_name } => write!(f, "Instruction {instruction_index} of node \"{node_name}\" jumps to the unknown label \"{label_name}\"."),
+            TypeConversionFailed { node_name, instruction_index, message } => write!(f, "Instruction {instruction_index} of node \"{node_name}\" failed to convert a value: {message}"),
+            FunctionParameterCountMismatch { node_name, instruction_index, function_name, expected_parameter_count, actual_parameter_count } => write!(f, "Instruction {instruction_index} of node \"{node_name}\" called the function \"{function_name}\" with {actual_parameter_count} parameters, but it expects {expected_parameter_count}."),
+            FunctionFailed { node_name, instruction_index, function_name, message } => write!(f, "Instruction {instruction_index} of node \"{node_name}\" called the function \"{function_name}\", which failed: {message}"),
+            InstructionBudgetExceeded { node_name, instruction_budget, jump_cycle } => write!(f, "Ran {instruction_budget} instructions without delivering any content and stopped in node \"{node_name}\". The dialogue is probably stuck in a loop through the nodes {}.", jump_cycle.join(" -> ")),
+            RewindOutOfRange { steps, recorded_lines } => write!(f, "Cannot rewind by {steps} lines, because only {recorded_lines} delivered lines are recorded. Rewinding by 0 lines returns to the last one of them."),
+            ProgramMergeError(e) => Display::fmt(e, f),
         }
     }
 }
@@ -89,6 +169,12 @@ impl From<VariableStorageError> for DialogueError {
     }
 }
 
+impl From<ProgramMergeError> for DialogueError {
+    fn from(source: ProgramMergeError) -> Self {
+        DialogueError::ProgramMergeError(source)
+    }
+}
+
 impl Dialogue {
     /// Creates a new [`Dialogue`] instance with the given [`VariableStorage`] and [`TextProvider`].
     /// - The [`TextProvider`] is used to retrieve the text of lines and options.
@@ -100,10 +186,14 @@ impl Dialogue {
         variable_storage: Box<dyn VariableStorage>,
         text_provider: Box<dyn TextProvider>,
     ) -> Self {
+        let rng = DialogueRng::new();
         let mut library = Library::standard_library();
         library
             .add_function("visited", visited(variable_storage.clone()))
-            .add_function("visited_count", visited_count(variable_storage.clone()));
+            .add_function("visited_count", visited_count(variable_storage.clone()))
+            .add_function("random", random(rng.clone()))
+            .add_function("random_range", random_range(rng.clone()))
+            .add_function("dice", dice(rng.clone()));
 
         let dialogue_text_processor = Box::new(DialogueTextProcessor::new());
         let line_parser = LineParser::new()
@@ -112,8 +202,10 @@ impl Dialogue {
             .register_marker_processor("ordinal", dialogue_text_processor);
 
         Self {
-            vm: VirtualMachine::new(library, variable_storage, line_parser, text_provider),
+            vm: VirtualMachine::new(library, variable_storage, line_parser, text_provider, rng),
             language_code: Default::default(),
+            history: None,
+            seen_line_tracker: None,
         }
     }
 }
@@ -140,6 +232,22 @@ fn visited_count(storage: Box<dyn VariableStorage>) -> yarn_fn_type! { impl Fn(S
     }
 }
 
+fn random(rng: DialogueRng) -> yarn_fn_type! { impl Fn() -> f32 } {
+    move || rng.random()
+}
+
+fn random_range(rng: DialogueRng) -> yarn_fn_type! { impl Fn(f32, f32) -> f32 } {
+    move |min: f32, max: f32| rng.random_range(min, max)
+}
+
+fn dice(rng: DialogueRng) -> yarn_fn_type! { impl Fn(f32) -> core::result::Result<u32, String> } {
+    // Casting truncates fractions and turns negative numbers and NaN into 0, which is rejected
+    move |sides: f32| {
+        rng.dice(sides as u32)
+            .ok_or_else(|| format!("A die needs at least 1 side, but had {sides}"))
+    }
+}
+
 // Accessors
 impl Dialogue {
     /// The [`Dialogue`]'s locale, as an IETF BCP 47 code.
@@ -160,13 +268,97 @@ impl Dialogue {
 
     /// Sets the [`Dialogue`]'s language. A value of `None` means that you are using the base language, i.e. the one the Yarn files are written in.
     /// Returns the last language code.
+    ///
+    /// If a [`DialogueHistory`] is recorded and the [`TextProvider`] already has the lines of the new language available,
+    /// the history is re-localized as well. See [`Dialogue::relocalize_history`].
     pub fn set_language_code(
         &mut self,
         language_code: impl Into<Option<Language>>,
     ) -> Option<Language> {
         let language_code = language_code.into();
         self.vm.set_language_code(language_code.clone());
-        core::mem::replace(&mut self.language_code, language_code)
+        let previous_language_code = core::mem::replace(&mut self.language_code, language_code);
+        self.relocalize_history_if_lines_are_available();
+        previous_language_code
+    }
+
+    /// Gets the [`DialogueHistory`] that records the delivered lines and selected options, if recording was enabled with [`Dialogue::set_history`].
+    #[must_use]
+    pub fn history(&self) -> Option<&DialogueHistory> {
+        self.history.as_ref()
+    }
+
+    /// See [`Dialogue::history`].
+    #[must_use]
+    pub fn history_mut(&mut self) -> Option<&mut DialogueHistory> {
+        self.history.as_mut()
+    }
+
+    /// Sets the [`DialogueHistory`] that records every [`DialogueEvent::Line`] returned by [`Dialogue::continue_`] or a [`DialogueDebugger`] and every option
+    /// selected with [`Dialogue::set_selected_option`], e.g. a new one or one restored from a save game.
+    /// `None` stops recording and discards the current history. The default is `None`.
+    ///
+    /// Returns the previous history.
+    pub fn set_history(
+        &mut self,
+        history: impl Into<Option<DialogueHistory>>,
+    ) -> Option<DialogueHistory> {
+        let previous_history = core::mem::replace(&mut self.history, history.into());
+        self.relocalize_history_if_lines_are_available();
+        previous_history
+    }
+
+    /// Gets the [`SeenLineTracker`] that records the delivered lines, if one was set with [`Dialogue::set_seen_line_tracker`].
+    #[must_use]
+    pub fn seen_line_tracker(&self) -> Option<&SeenLineTracker> {
+        self.seen_line_tracker.as_ref()
+    }
+
+    /// Sets the [`SeenLineTracker`] that records every [`DialogueEvent::Line`] returned by [`Dialogue::continue_`] or a [`DialogueDebugger`].
+    /// Lines delivered again by [`Dialogue::rewind`] are not recorded. `None` stops recording. The default is `None`.
+    ///
+    /// Returns the previous tracker.
+    pub fn set_seen_line_tracker(
+        &mut self,
+        seen_line_tracker: impl Into<Option<SeenLineTracker>>,
+    ) -> Option<SeenLineTracker> {
+        core::mem::replace(&mut self.seen_line_tracker, seen_line_tracker.into())
+    }
+
+    /// Produces the text of every entry of the [`DialogueHistory`] again in the current language, using the [`Line::id`] and [`Line::values`]
+    /// stored in it. Does nothing if no history is recorded.
+    ///
+    /// This happens automatically when calling [`Dialogue::set_language_code`] or [`Dialogue::set_history`], unless the [`TextProvider`]
+    /// has not yet loaded the lines of the language, as indicated by [`TextProvider::are_lines_available`]. Call this once it has.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error encountered while producing a line. The entries of lines that could not be produced are left unchanged.
+    pub fn relocalize_history(&mut self) -> Result<&mut Self> {
+        if let Some(history) = self.history.as_mut() {
+            let vm = &mut self.vm;
+            history.relocalize(self.language_code.clone(), |line| {
+                vm.prepare_line(line.id.clone(), &line.substitution_values())
+            })?;
+        }
+        Ok(self)
+    }
+
+    /// Returns `true` if a [`DialogueHistory`] is recorded and its entries are not in the current language.
+    #[must_use]
+    pub fn is_history_outdated(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|history| history.language() != self.language_code.as_ref())
+    }
+
+    fn relocalize_history_if_lines_are_available(&mut self) {
+        if !self.is_history_outdated() || !self.text_provider().are_lines_available() {
+            return;
+        }
+        if let Err(e) = self.relocalize_history() {
+            error!("Failed to re-localize dialogue history: {e}");
+        }
     }
 
     /// Gets the [`Library`] that this Dialogue uses to locate functions.
@@ -198,6 +390,73 @@ impl Dialogue {
         self
     }
 
+    /// Gets how many jumps away from the current node [`DialogueEvent::LineHints`] look for lines.
+    /// The default is 0, which means that only the lines of the current node are hinted at.
+    #[must_use]
+    pub fn line_hints_lookahead(&self) -> usize {
+        self.vm.line_hints_lookahead
+    }
+
+    /// Sets how many jumps away from the current node [`DialogueEvent::LineHints`] look for lines.
+    /// With a lookahead of 1, the hints also contain the lines of every node that the current node jumps or detours to, and so on.
+    /// This gives a [`TextProvider`] the chance to prepare for a node before it is entered. Only takes effect if [`Dialogue::line_hints_enabled`] is set.
+    pub fn set_line_hints_lookahead(&mut self, lookahead: usize) -> &mut Self {
+        self.vm.line_hints_lookahead = lookahead;
+        self
+    }
+
+    /// Gets the maximum number of instructions a single call to [`Dialogue::continue_`] may run.
+    /// If it runs out, [`Dialogue::continue_`] returns [`DialogueError::InstructionBudgetExceeded`] instead of looping forever,
+    /// and calling it again resumes with a fresh budget.
+    /// The default is `None`, which means there is no limit.
+    #[must_use]
+    pub fn instruction_budget(&self) -> Option<usize> {
+        self.vm.instruction_budget
+    }
+
+    /// Sets the maximum number of instructions a single call to [`Dialogue::continue_`] may run. See [`Dialogue::instruction_budget`].
+    pub fn set_instruction_budget(
+        &mut self,
+        instruction_budget: impl Into<Option<usize>>,
+    ) -> &mut Self {
+        self.vm.instruction_budget = instruction_budget.into();
+        self
+    }
+
+    /// Gets the random number generator used by the `random`, `random_range` and `dice` functions.
+    /// Pass a clone of it to [`RandomSaliencyStrategy::new`] to make random line group selections reproducible as well.
+    /// Seed it or restore its state to make these functions produce the same numbers again.
+    #[must_use]
+    pub fn rng(&self) -> &DialogueRng {
+        &self.vm.rng
+    }
+
+    /// Mutably gets the random number generator used by the `random`, `random_range` and `dice` functions. See [`Dialogue::rng`].
+    #[must_use]
+    pub fn rng_mut(&mut self) -> &mut DialogueRng {
+        &mut self.vm.rng
+    }
+
+    /// Gets the [`SaliencyStrategy`] used to select which variant of a line group is run.
+    /// The default is [`LeastRecentlyViewedSaliencyStrategy`].
+    pub fn saliency_strategy(&self) -> &dyn SaliencyStrategy {
+        self.vm.saliency_strategy.as_ref()
+    }
+
+    /// Mutable gets the [`SaliencyStrategy`] used to select which variant of a line group is run.
+    pub fn saliency_strategy_mut(&mut self) -> &mut dyn SaliencyStrategy {
+        self.vm.saliency_strategy.as_mut()
+    }
+
+    /// Sets the [`SaliencyStrategy`] used to select which variant of a line group is run.
+    pub fn set_saliency_strategy(
+        &mut self,
+        saliency_strategy: impl SaliencyStrategy + 'static,
+    ) -> &mut Self {
+        self.vm.saliency_strategy = Box::new(saliency_strategy);
+        self
+    }
+
     /// Gets the currently registered [`TextProvider`].
     pub fn text_provider(&self) -> &dyn TextProvider {
         self.vm.text_provider()
@@ -245,11 +504,13 @@ impl Dialogue {
             "Called `continue_` on a dialogue that was compiled with the `bevy` feature. Did you mean to call `continue_with_world` instead?"
         );
 
-        self.vm.continue_(|vm, instruction| {
+        let events = self.vm.continue_(|vm, instruction| {
             vm.run_instruction(instruction, |function, parameters| {
                 function.call(parameters)
             })
-        })
+        })?;
+        self.record_delivered_lines(&events);
+        Ok(events)
     }
 
     #[cfg(feature = "bevy")]
@@ -271,11 +532,30 @@ impl Dialogue {
     /// Specifically, we cannot guarantee [`Send`] and [`Sync`] properly without a lot of [`std::sync::RwLock`] boilerplate. The original implementation
     /// also allows unsound parallel mutation of [`Dialogue`]'s state, which would result in a deadlock in our case.
     pub fn continue_with_world(&mut self, world: &mut World) -> Result<Vec<DialogueEvent>> {
-        self.vm.continue_(move |vm, instruction| {
+        let events = self.vm.continue_(move |vm, instruction| {
             vm.run_instruction(instruction, |function, parameters| {
                 function.call_with_world(parameters, world)
             })
-        })
+        })?;
+        self.record_delivered_lines(&events);
+        Ok(events)
+    }
+
+    /// Passes the delivered lines to the [`DialogueHistory`] and the [`SeenLineTracker`], if any.
+    /// Everything that runs the virtual machine on behalf of the caller must call this, including the [`DialogueDebugger`].
+    pub(crate) fn record_delivered_lines(&mut self, events: &[DialogueEvent]) {
+        if let Some(history) = self.history.as_mut() {
+            for event in events {
+                history.record(event);
+            }
+        }
+        if let Some(seen_line_tracker) = self.seen_line_tracker.as_mut() {
+            for event in events {
+                if let DialogueEvent::Line(line) = event {
+                    seen_line_tracker.mark_seen(line.id.clone());
+                }
+            }
+        }
     }
 
     /// Returns true if the [`Dialogue`] is in a state where [`Dialogue::continue_`] can be called.
@@ -300,22 +580,69 @@ impl Dialogue {
     pub fn replace_program(&mut self, program: Program) -> &mut Self {
         self.vm.program.replace(program.clone());
         self.vm.reset_state();
+        self.vm.clear_rewind_points();
         self.extend_variable_storage_from(&program);
         self
     }
 
     /// Merges the currently set [`Program`] with the given one. If there is no program set, the given one is set.
-    pub fn add_program(&mut self, program: Program) -> &mut Self {
+    ///
+    /// Returns [`DialogueError::ProgramMergeError`] and leaves the current program untouched
+    /// if both programs contain a node with the same name or different initial values for the same variable.
+    /// Use [`Dialogue::add_program_with_policy`] to resolve such collisions instead.
+    pub fn add_program(&mut self, program: Program) -> Result<&mut Self> {
+        self.add_program_with_policy(program, MergePolicy::Error)
+    }
+
+    /// Like [`Dialogue::add_program`], but resolves collisions according to the given [`MergePolicy`].
+    pub fn add_program_with_policy(
+        &mut self,
+        mut program: Program,
+        policy: MergePolicy,
+    ) -> Result<&mut Self> {
         if let Some(existing_program) = self.vm.program.as_mut() {
-            *existing_program =
-                Program::combine(vec![existing_program.clone(), program.clone()]).unwrap();
+            *existing_program = Program::combine_with_policy(
+                vec![existing_program.clone(), program.clone()],
+                policy,
+            )?
+            .unwrap();
+            // Initial values that lost against the existing program must not override its variables
+            program
+                .initial_values
+                .retain(|name, value| existing_program.initial_values.get(name) == Some(value));
         } else {
             self.vm.program.replace(program.clone());
             self.vm.reset_state();
         }
         self.extend_variable_storage_from(&program);
 
-        self
+        Ok(self)
+    }
+
+    /// Replaces the current [`Program`] while keeping the dialogue where it is, e.g. after editing a Yarn file during playtesting.
+    /// Unlike [`Dialogue::replace_program`], the values of all variables are kept. Only variables that were newly declared are initialized.
+    ///
+    /// The current position is mapped to the new program by the name of the current node and the ID of the nearest line before it.
+    /// The same is done for every node waiting for a `<<detour>>` to return. If the dialogue is waiting for an option selection,
+    /// the options that still exist are kept and returned again as a [`DialogueEvent::Options`] if they changed, e.g. because some of them were removed.
+    ///
+    /// If the position cannot be mapped, the current node is restarted without the nodes waiting for it to return,
+    /// and a [`DialogueEvent::HotSwapFallback`] explaining why is returned.
+    /// If the current node no longer exists, the dialogue is stopped instead and the fallback is followed by a [`DialogueEvent::DialogueComplete`].
+    /// The [`DialogueEvent`]s that are returned should be handled by the caller.
+    pub fn hot_swap_program(&mut self, program: Program) -> Result<Vec<DialogueEvent>> {
+        let new_declarations = Program {
+            initial_values: program
+                .initial_values
+                .iter()
+                .filter(|(name, _)| !self.variable_storage().contains(name))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        let events = self.vm.hot_swap_program(program)?;
+        self.extend_variable_storage_from(&new_declarations);
+        Ok(events)
     }
 
     /// Prepares the [`Dialogue`] that the user intends to start running a node.
@@ -330,6 +657,10 @@ impl Dialogue {
     /// Returns an error if no node with the value of `node_name` has been loaded.
     pub fn set_node(&mut self, node_name: impl Into<String>) -> Result<&mut Self> {
         self.vm.set_node(node_name)?;
+        self.vm.clear_rewind_points();
+        if self.vm.rollback_on_stop {
+            self.vm.begin_transaction(true);
+        }
         Ok(self)
     }
 
@@ -347,22 +678,128 @@ impl Dialogue {
     /// Immediately stops the [`Dialogue`]
     ///
     /// Returns unfinished [`DialogueEvent`]s that should be handled by the caller. The last is guaranteed to be [`DialogueEvent::DialogueComplete`].
+    /// If [`Dialogue::rollback_on_stop`] is enabled, the current transaction is rolled back first, see [`Dialogue::rollback_transaction`].
     pub fn stop(&mut self) -> Vec<DialogueEvent> {
+        self.vm.clear_rewind_points();
         self.vm.stop()
     }
 
+    /// Sets a variable in the [`VariableStorage`]. Prefer this over calling [`VariableStorage::set`] on [`Dialogue::variable_storage_mut`] directly
+    /// when anything observes [`DialogueEvent::VariableChanged`].
+    ///
+    /// Returns [`DialogueEvent`]s that should be handled by the caller, which is a [`DialogueEvent::VariableChanged`] if the value changed and nothing otherwise.
+    pub fn set_variable(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<YarnValue>,
+    ) -> Result<Vec<DialogueEvent>> {
+        let event = self.vm.set_variable(name.into(), value.into())?;
+        Ok(event.into_iter().collect())
+    }
+
+    /// Begins a transaction: From now on, the previous value of every variable written by the program or by [`Dialogue::set_variable`] is recorded,
+    /// so that the writes can be undone with [`Dialogue::rollback_transaction`] or kept with [`Dialogue::commit_transaction`].
+    /// Writes made directly through [`Dialogue::variable_storage_mut`] are not recorded, and neither are `<<local>>` variables.
+    ///
+    /// Transactions cannot be nested; does nothing if a transaction is already in progress.
+    pub fn begin_transaction(&mut self) -> &mut Self {
+        self.vm.begin_transaction(false);
+        self
+    }
+
+    /// Keeps all variable writes since [`Dialogue::begin_transaction`] and ends the transaction. Does nothing if no transaction is in progress.
+    pub fn commit_transaction(&mut self) -> &mut Self {
+        self.vm.commit_transaction();
+        self
+    }
+
+    /// Undoes all variable writes since [`Dialogue::begin_transaction`] and ends the transaction.
+    /// Variables that did not exist before the transaction are removed from the [`VariableStorage`].
+    ///
+    /// Returns [`DialogueEvent`]s that should be handled by the caller, which are a [`DialogueEvent::VariableChanged`] for every variable whose value changed back.
+    /// Does nothing if no transaction is in progress.
+    pub fn rollback_transaction(&mut self) -> Result<Vec<DialogueEvent>> {
+        self.vm.rollback_transaction()
+    }
+
+    /// Returns `true` if a transaction was begun and not yet committed or rolled back.
+    #[must_use]
+    pub fn is_in_transaction(&self) -> bool {
+        self.vm.is_in_transaction()
+    }
+
+    /// Gets whether [`Dialogue::stop`] rolls back the variable writes of the current run. See [`Dialogue::set_rollback_on_stop`].
+    #[must_use]
+    pub fn rollback_on_stop(&self) -> bool {
+        self.vm.rollback_on_stop
+    }
+
+    /// Sets whether [`Dialogue::stop`] rolls back the variable writes of the current run, e.g. so that cancelling a trade halfway undoes it.
+    /// The default is `false`.
+    ///
+    /// If enabled, [`Dialogue::set_node`] begins a transaction unless one is already in progress. A transaction begun this way is committed when the
+    /// dialogue reaches its end and rolled back when it is stopped with [`Dialogue::stop`]. Transactions begun with [`Dialogue::begin_transaction`]
+    /// are also rolled back by [`Dialogue::stop`], but are not committed automatically.
+    pub fn set_rollback_on_stop(&mut self, rollback_on_stop: bool) -> &mut Self {
+        self.vm.rollback_on_stop = rollback_on_stop;
+        self
+    }
+
+    /// Gets how many of the most recently delivered lines [`Dialogue::rewind`] can return to.
+    /// The default is 0, which disables rewinding.
+    #[must_use]
+    pub fn rewind_depth(&self) -> usize {
+        self.vm.rewind_depth()
+    }
+
+    /// Sets how many of the most recently delivered lines [`Dialogue::rewind`] can return to, forgetting the oldest ones if more were recorded.
+    ///
+    /// For every recorded line, the execution state and the values of all variables are kept in memory, so keep this as low as your game allows.
+    pub fn set_rewind_depth(&mut self, rewind_depth: usize) -> &mut Self {
+        self.vm.set_rewind_depth(rewind_depth);
+        self
+    }
+
+    /// Gets the lines that [`Dialogue::rewind`] can return to, oldest first. The last one is the line that was delivered last.
+    ///
+    /// Lines are recorded since the last call to [`Dialogue::set_node`] or [`Dialogue::stop`], up to [`Dialogue::rewind_depth`] of them.
+    /// This includes lines of other nodes that were jumped or detoured to in the meantime.
+    pub fn rewindable_lines(&self) -> impl DoubleEndedIterator<Item = &Line> + ExactSizeIterator {
+        self.vm.rewindable_lines()
+    }
+
+    /// Returns to the moment the line `steps` lines before the last delivered one was delivered, e.g. `1` to go back one line.
+    /// `0` returns to the last delivered line, e.g. to present it again after an option was selected.
+    ///
+    /// The execution state and the values of all variables are restored to what they were at that moment, and the lines after it are forgotten.
+    /// Afterwards, call [`Dialogue::continue_`] to continue from that line.
+    ///
+    /// Returns [`DialogueEvent`]s that should be handled by the caller, which are a [`DialogueEvent::VariableChanged`] for every variable whose value changed back,
+    /// followed by the [`DialogueEvent::Line`] in the current language.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if fewer than `steps + 1` lines are recorded, see [`Dialogue::rewindable_lines`].
+    pub fn rewind(&mut self, steps: usize) -> Result<Vec<DialogueEvent>> {
+        self.vm.rewind(steps)
+    }
+
     /// Unloads all nodes from the Dialogue.
     pub fn unload_all(&mut self) {
         self.vm.unload_programs()
     }
 
     /// Gets the names of the nodes in the currently loaded Program, if there is one.
+    /// The hidden nodes the compiler generates for internal use, e.g. for smart variables, are left out.
     #[must_use]
     pub fn node_names(&self) -> Option<impl Iterator<Item = &str>> {
-        self.vm
-            .program
-            .as_ref()
-            .map(|program| program.nodes.keys().map(|s| s.as_str()))
+        self.vm.program.as_ref().map(|program| {
+            program
+                .nodes
+                .iter()
+                .filter(|(_, node)| !node.is_hidden())
+                .map(|(name, _)| name.as_str())
+        })
     }
 
     /// Returns the line ID that contains the original, uncompiled source
@@ -414,13 +851,110 @@ impl Dialogue {
     pub fn node_exists(&self, node_name: &str) -> bool {
         // Not calling `get_node_logging_errors` because this method does not write errors when there are no nodes.
         if let Some(program) = self.vm.program.as_ref() {
-            program.nodes.contains_key(node_name)
+            program
+                .nodes
+                .get(node_name)
+                .is_some_and(|node| !node.is_hidden())
         } else {
             error!("Tried to call NodeExists, but no program has been loaded");
             false
         }
     }
 
+    /// Gets a value indicating whether a specified node is a node group, i.e. stands in for several nodes that share its title and have `when:` headers.
+    /// Calling [`Dialogue::set_node`] with the name of a node group runs one of its nodes, selected by the [`SaliencyStrategy`].
+    #[must_use]
+    pub fn is_node_group(&self, node_name: &str) -> bool {
+        self.vm
+            .program
+            .as_ref()
+            .and_then(|program| program.nodes.get(node_name))
+            .is_some_and(Node::is_node_group_hub)
+    }
+
+    /// Returns the nodes of the node group `node_group_name` whose `when:` conditions currently pass,
+    /// i.e. the nodes that the [`SaliencyStrategy`] can select from when the node group is run.
+    /// None of the nodes are run and the [`SaliencyStrategy`] is not consulted.
+    ///
+    /// The [`SaliencyCandidate::content_id`] of each candidate is the name of the node.
+    ///
+    /// Evaluating the conditions does not change any variables or the results of later random functions,
+    /// but any functions you registered in the [`Library`] that the conditions call are run.
+    ///
+    /// Note that when compiling with the `bevy` feature, you should use [`Dialogue::saliency_candidates_for_node_group_with_world`] instead.
+    pub fn saliency_candidates_for_node_group(
+        &mut self,
+        node_group_name: &str,
+    ) -> Result<Vec<SaliencyCandidate>> {
+        self.vm
+            .saliency_candidates_for_node_group(node_group_name, |function, parameters| {
+                function.call(parameters)
+            })
+    }
+
+    #[cfg(feature = "bevy")]
+    /// The Bevy version of [`Dialogue::saliency_candidates_for_node_group`].
+    pub fn saliency_candidates_for_node_group_with_world(
+        &mut self,
+        node_group_name: &str,
+        world: &mut World,
+    ) -> Result<Vec<SaliencyCandidate>> {
+        self.vm
+            .saliency_candidates_for_node_group(node_group_name, |function, parameters| {
+                function.call_with_world(parameters, world)
+            })
+    }
+
+    /// Returns how many nodes of the node group `node_group_name` currently pass their `when:` conditions.
+    /// See [`Dialogue::saliency_candidates_for_node_group`].
+    ///
+    /// Note that when compiling with the `bevy` feature, you should use [`Dialogue::node_group_candidate_count_with_world`] instead.
+    pub fn node_group_candidate_count(&mut self, node_group_name: &str) -> Result<usize> {
+        self.saliency_candidates_for_node_group(node_group_name)
+            .map(|candidates| candidates.len())
+    }
+
+    #[cfg(feature = "bevy")]
+    /// The Bevy version of [`Dialogue::node_group_candidate_count`].
+    pub fn node_group_candidate_count_with_world(
+        &mut self,
+        node_group_name: &str,
+        world: &mut World,
+    ) -> Result<usize> {
+        self.saliency_candidates_for_node_group_with_world(node_group_name, world)
+            .map(|candidates| candidates.len())
+    }
+
+    /// Gets a value indicating whether a specified variable is a smart variable, i.e. was declared with an expression like `<<declare $can_afford = $gold >= $price>>`.
+    /// Smart variables are not stored in the [`VariableStorage`], but computed from their expression every time they are read.
+    #[must_use]
+    pub fn is_smart_variable(&self, variable_name: &str) -> bool {
+        self.vm.is_smart_variable(variable_name)
+    }
+
+    /// Evaluates the expression of the smart variable `variable_name` with the current contents of the [`VariableStorage`] and returns its value.
+    ///
+    /// Note that when compiling with the `bevy` feature, you should use [`Dialogue::evaluate_smart_variable_with_world`] instead.
+    pub fn evaluate_smart_variable(&mut self, variable_name: &str) -> Result<YarnValue> {
+        self.vm
+            .evaluate_smart_variable(variable_name, &mut |function, parameters| {
+                function.call(parameters)
+            })
+    }
+
+    #[cfg(feature = "bevy")]
+    /// The Bevy version of [`Dialogue::evaluate_smart_variable`].
+    pub fn evaluate_smart_variable_with_world(
+        &mut self,
+        variable_name: &str,
+        world: &mut World,
+    ) -> Result<YarnValue> {
+        self.vm
+            .evaluate_smart_variable(variable_name, &mut |function, parameters| {
+                function.call_with_world(parameters, world)
+            })
+    }
+
     /// Gets the name of the node that this Dialogue is currently executing.
     ///
     /// If [`Dialogue::continue_`] has never been called, this value will be [`None`].
@@ -429,6 +963,16 @@ impl Dialogue {
         self.vm.current_node()
     }
 
+    /// Gets the names of the nodes that entered the current node through a `<<detour>>` and are waiting for it to return,
+    /// with the node that detoured to [`Dialogue::current_node`] last.
+    ///
+    /// A detoured node returns when it runs `<<return>>` or reaches its end. If it `<<jump>>`s to another node instead,
+    /// that node returns in its place.
+    #[must_use]
+    pub fn call_stack(&self) -> Vec<String> {
+        self.vm.call_stack()
+    }
+
     /// Analyses the currently loaded Yarn program with the given [`Context`]. Call [`Context::finish_analysis`] afterwards to get the results.
     pub fn analyse(&self, context: &mut Context) -> &Self {
         let program = self
@@ -445,7 +989,11 @@ impl Dialogue {
             if program.nodes.is_empty() {
                 error!("No nodes are loaded");
                 None
-            } else if let Some(node) = program.nodes.get(node_name) {
+            } else if let Some(node) = program
+                .nodes
+                .get(node_name)
+                .filter(|node| !node.is_hidden())
+            {
                 Some(node.clone())
             } else {
                 error!("No node named {node_name}");
@@ -471,7 +1019,41 @@ impl Dialogue {
     /// ## See Also
     /// - [`Dialogue::continue_`]
     pub fn set_selected_option(&mut self, selected_option_id: OptionId) -> Result<&mut Self> {
-        self.vm.set_selected_option(selected_option_id)?;
+        let selected_option = self.vm.set_selected_option(selected_option_id)?;
+        if let Some(history) = self.history.as_mut() {
+            history.record_selected_option(&selected_option);
+        }
+        Ok(self)
+    }
+
+    /// Captures the current execution state of the [`Dialogue`] so that it can later be resumed with [`Dialogue::restore`].
+    ///
+    /// The snapshot does not include the values of variables, which should be persisted via the [`VariableStorage`].
+    /// See [`DialogueSnapshot`] for details.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no program has been loaded.
+    pub fn snapshot(&self) -> Result<DialogueSnapshot> {
+        self.vm.snapshot()
+    }
+
+    /// Resumes the [`Dialogue`] from a [`DialogueSnapshot`] previously created by [`Dialogue::snapshot`].
+    ///
+    /// The program the snapshot was taken from must already be loaded. Any events that were not yet returned by [`Dialogue::continue_`] are discarded.
+    /// If the snapshot was taken while waiting for an option selection, call [`Dialogue::set_selected_option`] next. Otherwise, call [`Dialogue::continue_`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if
+    /// - the snapshot was created by an incompatible version of the runtime,
+    /// - no program has been loaded,
+    /// - the loaded program is not the one the snapshot was taken from, or
+    /// - the snapshot's current node does not exist in the loaded program.
+    pub fn restore(&mut self, snapshot: DialogueSnapshot) -> Result<&mut Self> {
+        let language_code = snapshot.language_code.clone();
+        self.vm.restore(snapshot)?;
+        self.language_code = language_code;
         Ok(self)
     }
 
@@ -500,5 +1082,31 @@ mod tests {
         accept_send_sync(dialogue);
     }
 
+    #[test]
+    fn rollback_restores_variables_written_in_transaction() {
+        let mut variable_storage = MemoryVariableStorage::new();
+        variable_storage.set("$gold".to_owned(), 10.into()).unwrap();
+        let text_provider = Box::new(StringTableTextProvider::new());
+        let mut dialogue = Dialogue::new(Box::new(variable_storage.clone()), text_provider);
+
+        dialogue.begin_transaction();
+        dialogue.set_variable("$gold", 5).unwrap();
+        dialogue.set_variable("$gold", 0).unwrap();
+        dialogue.set_variable("$sword", true).unwrap();
+        let events = dialogue.rollback_transaction().unwrap();
+
+        assert!(!dialogue.is_in_transaction());
+        assert_eq!(
+            vec![DialogueEvent::VariableChanged {
+                name: "$gold".to_owned(),
+                old: Some(0.into()),
+                new: 10.into(),
+            }],
+            events
+        );
+        assert_eq!(YarnValue::from(10), variable_storage.get("$gold").unwrap());
+        assert!(!variable_storage.contains("$sword"));
+    }
+
     fn accept_send_sync(_: impl Send + Sync) {}
 }
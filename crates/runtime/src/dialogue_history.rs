@@ -0,0 +1,202 @@
+//! Contains [`DialogueHistory`], a scrollback log of the lines and options a [`Dialogue`] delivered.
+//!
+//! ## Implementation notes
+//!
+//! This has no equivalent in the original implementation, which leaves keeping a backlog to the game.
+
+use crate::prelude::*;
+use alloc::collections::VecDeque;
+
+/// A bounded log of every line a [`Dialogue`] delivered and every option the user selected, oldest first,
+/// e.g. for the backlog screen of a visual novel.
+///
+/// Recording is opt-in: pass a history to [`Dialogue::set_history`] and read it back with [`Dialogue::history`].
+/// Once [`DialogueHistory::capacity`] entries have been recorded, the oldest entry is dropped for every new one.
+///
+/// Since the [`Line`]s of the entries keep their [`Line::id`] and [`Line::values`], changing the language with
+/// [`Dialogue::set_language_code`] produces their text again in the new language. See [`Dialogue::relocalize_history`].
+///
+/// When compiling with the `serde` feature, a history can be serialized and stored as part of a save game.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DialogueHistory {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+    language: Option<Language>,
+}
+
+/// An entry of a [`DialogueHistory`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum HistoryEntry {
+    /// A line that was delivered through [`DialogueEvent::Line`].
+    Line(Line),
+    /// The line of the [`DialogueOption`] that was selected through [`Dialogue::set_selected_option`].
+    SelectedOption(Line),
+}
+
+impl HistoryEntry {
+    /// The line of this entry, including the character name and markup.
+    #[must_use]
+    pub fn line(&self) -> &Line {
+        match self {
+            Self::Line(line) | Self::SelectedOption(line) => line,
+        }
+    }
+
+    fn line_mut(&mut self) -> &mut Line {
+        match self {
+            Self::Line(line) | Self::SelectedOption(line) => line,
+        }
+    }
+}
+
+impl Default for DialogueHistory {
+    /// Creates an empty history that holds up to [`DialogueHistory::DEFAULT_CAPACITY`] entries.
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl DialogueHistory {
+    /// The capacity of a history created with [`DialogueHistory::default`].
+    pub const DEFAULT_CAPACITY: usize = 100;
+
+    /// Creates an empty history that holds up to `capacity` entries.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+            language: None,
+        }
+    }
+
+    /// The maximum number of entries this history holds.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Sets the maximum number of entries this history holds, dropping the oldest entries if there are more.
+    pub fn set_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.capacity = capacity;
+        self.truncate();
+        self
+    }
+
+    /// The recorded entries, oldest first.
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    /// The most recently recorded entry, if any.
+    #[must_use]
+    pub fn last(&self) -> Option<&HistoryEntry> {
+        self.entries.back()
+    }
+
+    /// The number of recorded entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no entries have been recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The language the text of the entries was produced in, as set by the last [`Dialogue::relocalize_history`].
+    /// `None` means the base language.
+    #[must_use]
+    pub fn language(&self) -> Option<&Language> {
+        self.language.as_ref()
+    }
+
+    /// Records the entry for the given event, if there is one. Only [`DialogueEvent::Line`]s are recorded.
+    pub fn record(&mut self, event: &DialogueEvent) {
+        if let DialogueEvent::Line(line) = event {
+            self.push(HistoryEntry::Line(line.clone()));
+        }
+    }
+
+    /// Records the selection of the given option.
+    pub fn record_selected_option(&mut self, option: &DialogueOption) {
+        self.push(HistoryEntry::SelectedOption(option.line.clone()));
+    }
+
+    /// Adds an entry, dropping the oldest entry if the history is full.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        self.entries.push_back(entry);
+        self.truncate();
+    }
+
+    fn truncate(&mut self) {
+        let excess = self.entries.len().saturating_sub(self.capacity);
+        self.entries.drain(..excess);
+    }
+
+    /// Replaces the line of every entry with the one produced by `localize_line`.
+    /// Entries whose line cannot be produced keep their previous line, and the first error is returned.
+    pub(crate) fn relocalize(
+        &mut self,
+        language: Option<Language>,
+        mut localize_line: impl FnMut(&Line) -> crate::Result<Line>,
+    ) -> crate::Result<()> {
+        let mut first_error = None;
+        for entry in &mut self.entries {
+            match localize_line(entry.line()) {
+                Ok(line) => *entry.line_mut() = line,
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        self.language = language;
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_entries_when_full() {
+        let mut history = DialogueHistory::new(2);
+        for id in ["a", "b", "c"] {
+            history.record(&DialogueEvent::Line(line(id)));
+        }
+        history.record(&DialogueEvent::DialogueComplete);
+
+        let ids: Vec<_> = history.entries().map(|entry| &entry.line().id.0).collect();
+        assert_eq!(vec!["b", "c"], ids);
+
+        history.set_capacity(1);
+        assert_eq!(Some(&HistoryEntry::Line(line("c"))), history.last());
+        assert_eq!(1, history.len());
+    }
+
+    fn line(id: &str) -> Line {
+        Line {
+            id: id.into(),
+            text: id.to_owned(),
+            attributes: vec![],
+            substitutions: vec![],
+            values: vec![],
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Contains [`DialogueSnapshot`], a serializable capture of a [`Dialogue`]'s execution state.
+//!
+//! ## Implementation notes
+//!
+//! This has no equivalent in the original implementation, which leaves saving and loading dialogue state to the game.
+
+use crate::prelude::*;
+
+/// A capture of everything a [`Dialogue`] needs to resume execution exactly where it left off.
+///
+/// Created by [`Dialogue::snapshot`] and consumed by [`Dialogue::restore`].
+/// When compiling with the `serde` feature, a snapshot can be serialized and stored as part of a save game.
+///
+/// A snapshot contains the virtual machine's position in the current node, its value stack, its local variables,
+/// the nodes waiting for a detour to return, any options that are pending selection, the execution state, the language code
+/// and the state of the [`DialogueRng`].
+/// It does *not* contain the values of Yarn variables, since those are owned by the [`VariableStorage`]
+/// and should be persisted through it.
+///
+/// A snapshot records a checksum of the [`Program`] it was taken from,
+/// so that it can only be restored into a [`Dialogue`] running the same program.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DialogueSnapshot {
+    pub(crate) version: u32,
+    pub(crate) program_checksum: u64,
+    pub(crate) current_node_name: Option<String>,
+    pub(crate) execution_state: ExecutionState,
+    pub(crate) state: State,
+    pub(crate) language_code: Option<Language>,
+    pub(crate) random_state: u64,
+}
+
+impl DialogueSnapshot {
+    /// The snapshot format version produced by this version of the runtime.
+    /// [`Dialogue::restore`] refuses snapshots with a different version.
+    pub const CURRENT_VERSION: u32 = 4;
+
+    /// The format version this snapshot was created with.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The checksum of the [`Program`] that was loaded when this snapshot was created. See [`Program::checksum`].
+    #[must_use]
+    pub fn program_checksum(&self) -> u64 {
+        self.program_checksum
+    }
+
+    /// The name of the node that was being executed when this snapshot was created, if any.
+    #[must_use]
+    pub fn current_node(&self) -> Option<&str> {
+        self.current_node_name.as_deref()
+    }
+
+    /// The names of the nodes that were waiting for a `<<detour>>` to return when this snapshot was created,
+    /// with the node that detoured to [`DialogueSnapshot::current_node`] last.
+    #[must_use]
+    pub fn call_stack(&self) -> Vec<&str> {
+        self.state
+            .call_stack
+            .iter()
+            .map(|caller| caller.node_name.as_str())
+            .collect()
+    }
+
+    /// The language code the [`Dialogue`] was using when this snapshot was created.
+    #[must_use]
+    pub fn language_code(&self) -> Option<&Language> {
+        self.language_code.as_ref()
+    }
+
+    /// The state of the [`DialogueRng`] when this snapshot was created. See [`DialogueRng::state`].
+    #[must_use]
+    pub fn random_state(&self) -> u64 {
+        self.random_state
+    }
+
+    /// The index of the next instruction that will be run in [`DialogueSnapshot::current_node`].
+    #[must_use]
+    pub fn program_counter(&self) -> usize {
+        self.state.program_counter
+    }
+
+    /// The options that were waiting for a selection when this snapshot was created.
+    #[must_use]
+    pub fn current_options(&self) -> &[DialogueOption] {
+        &self.state.current_options
+    }
+
+    /// Returns `true` if the [`Dialogue`] was waiting for an option selection when this snapshot was created.
+    #[must_use]
+    pub fn is_waiting_for_option_selection(&self) -> bool {
+        self.execution_state == ExecutionState::WaitingOnOptionSelection
+    }
+}
@@ -32,6 +32,9 @@ pub enum DialogueEvent {
     /// A library wrapping Yarn Spinner for a game engine should specify this.
     Command(Command),
     /// The node with the given name was completed.
+    ///
+    /// A node that detours to another node is not completed until the detour returns,
+    /// so the events of detoured nodes are nested inside the events of their callers.
     NodeComplete(String),
     /// The node with the given name was entered.
     NodeStart(String),
@@ -40,10 +43,42 @@ pub enum DialogueEvent {
     /// A hint that the contained line IDs might be encountered while progressing the dialogue.
     /// These are not guaranteed to run, but give a caller the chance to pre-load resources for them if they want.
     ///
+    /// The line IDs are ordered by priority: The lines of the node that was just entered come first, in the order they appear in the node.
+    /// If [`Dialogue::set_line_hints_lookahead`] was used, they are followed by the lines of the nodes it jumps to, nearest first.
+    ///
     /// ## Implementation note
     ///
     /// Corresponds to Yarn Spinner's `PrepareForLinesHandler`
     LineHints(Vec<LineId>),
+    /// The variable with the given name was set to a different value, either by the dialogue or by [`Dialogue::set_variable`].
+    /// `old` is the value before the change, which is `None` if the variable had neither been set nor declared with an initial value.
+    ///
+    /// Not emitted when a variable is set to the value it already has, or when a declared variable is first read and its initial value is stored.
+    ///
+    /// ## Implementation note
+    ///
+    /// Not part of the original implementation, whose `VariableStorage` offers no way of observing changes.
+    VariableChanged {
+        /// The name of the variable, including the leading `$`.
+        name: String,
+        /// The value of the variable before the change.
+        old: Option<YarnValue>,
+        /// The new value of the variable.
+        new: YarnValue,
+    },
+    /// [`Dialogue::hot_swap_program`] could not find the current position in the new program, so the node with the given name was restarted instead.
+    /// The restarted node's [`DialogueEvent::NodeStart`] is returned by the next call to [`Dialogue::continue_`].
+    /// If the node was removed from the program, the dialogue was stopped and this event is followed by a [`DialogueEvent::DialogueComplete`].
+    ///
+    /// ## Implementation note
+    ///
+    /// Not part of the original implementation, which has no way of replacing a running program.
+    HotSwapFallback {
+        /// The name of the restarted or removed node.
+        node_name: String,
+        /// Why the current position could not be kept.
+        reason: String,
+    },
     /// The dialogue was completed. Set it to a new node via [`Dialogue::set_node`] before calling [`Dialogue::continue_`] again.
     DialogueComplete,
 }
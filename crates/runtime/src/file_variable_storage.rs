@@ -0,0 +1,223 @@
+//! Contains [`FileVariableStorage`], a [`VariableStorage`] that persists its variables to a JSON file.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashMap;
+use core::any::Any;
+use log::error;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A [`VariableStorage`] that keeps all variables in memory like a [`MemoryVariableStorage`] and persists them to a JSON file.
+///
+/// Writes go to a temporary file next to the target, which is then renamed over it, so that a crash while saving never leaves a half-written file behind.
+/// The values keep their [`YarnValue`] variant, so a number saved as `1` is loaded as a number and not as a string or boolean.
+///
+/// By default, the variables are only written when calling [`FileVariableStorage::flush`].
+/// Enable [`FileVariableStorage::set_autosave`] to write them after every change instead.
+///
+/// Only available with the `std` and `serde` features.
+#[derive(Debug, Clone)]
+pub struct FileVariableStorage {
+    storage: MemoryVariableStorage,
+    path: PathBuf,
+    autosave: bool,
+}
+
+impl FileVariableStorage {
+    /// Creates an empty storage that persists to the file at `path`. Does not read the file, see [`FileVariableStorage::open`] for that.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            storage: MemoryVariableStorage::new(),
+            path: path.into(),
+            autosave: false,
+        }
+    }
+
+    /// Creates a storage that persists to the file at `path` and loads the variables saved in it, if the file exists.
+    /// Fails with a [`VariableStorageError::InternalError`] if the file cannot be read or is corrupt.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let mut storage = Self::new(path);
+        if storage.path.exists() {
+            storage.reload()?;
+        }
+        Ok(storage)
+    }
+
+    /// Gets the path of the file the variables are persisted to.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets whether the variables are written to the file after every change. Defaults to `false`.
+    #[must_use]
+    pub fn autosave(&self) -> bool {
+        self.autosave
+    }
+
+    /// Sets whether the variables are written to the file after every change.
+    /// Errors while autosaving are returned by the method that changed the variables, except for [`VariableStorage::clear`], which logs them.
+    /// In both cases, the variables in memory keep the change.
+    pub fn set_autosave(&mut self, autosave: bool) -> &mut Self {
+        self.autosave = autosave;
+        self
+    }
+
+    /// Writes all variables to the file.
+    pub fn flush(&self) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(&self.storage.variables()).map_err(internal_error)?;
+        let mut temporary_path = OsString::from(self.path.as_os_str());
+        temporary_path.push(".tmp");
+        fs::write(&temporary_path, json).map_err(internal_error)?;
+        fs::rename(&temporary_path, &self.path).map_err(internal_error)
+    }
+
+    /// Replaces all variables with the ones saved in the file.
+    /// Fails with a [`VariableStorageError::InternalError`] if the file cannot be read or is corrupt, in which case the variables are left unchanged.
+    pub fn reload(&mut self) -> Result<()> {
+        let json = fs::read_to_string(&self.path).map_err(internal_error)?;
+        let variables: HashMap<String, YarnValue> =
+            serde_json::from_str(&json).map_err(internal_error)?;
+        for name in variables.keys() {
+            MemoryVariableStorage::validate_name(name)?;
+        }
+        self.storage.clear();
+        self.storage.extend(variables)
+    }
+
+    fn autosave_if_enabled(&self) -> Result<()> {
+        if self.autosave { self.flush() } else { Ok(()) }
+    }
+}
+
+impl VariableStorage for FileVariableStorage {
+    fn clone_shallow(&self) -> Box<dyn VariableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, name: String, value: YarnValue) -> Result<()> {
+        self.storage.set(name, value)?;
+        self.autosave_if_enabled()
+    }
+
+    fn get(&self, name: &str) -> Result<YarnValue> {
+        self.storage.get(name)
+    }
+
+    fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
+        self.storage.extend(values)?;
+        self.autosave_if_enabled()
+    }
+
+    fn variables(&self) -> HashMap<String, YarnValue> {
+        self.storage.variables()
+    }
+
+    fn clear(&mut self) {
+        self.storage.clear();
+        if let Err(e) = self.autosave_if_enabled() {
+            error!(
+                "Failed to save cleared variable storage to {}: {e}",
+                self.path.display()
+            );
+        }
+    }
+
+    fn remove(&mut self, name: &str) -> Result<()> {
+        self.storage.remove(name)?;
+        self.autosave_if_enabled()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn internal_error(
+    error: impl Into<Box<dyn core::error::Error + Send + Sync>>,
+) -> VariableStorageError {
+    VariableStorageError::InternalError {
+        error: error.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_round_trip_with_their_type() {
+        let path = temporary_path("round_trip");
+        let mut storage = FileVariableStorage::new(&path);
+        storage.set_autosave(true);
+        storage.set("$number".to_owned(), 1.into()).unwrap();
+        storage.set("$string".to_owned(), "1".into()).unwrap();
+        storage.set("$bool".to_owned(), true.into()).unwrap();
+
+        let loaded = FileVariableStorage::open(&path).unwrap();
+        assert_eq!(storage.variables(), loaded.variables());
+        assert_eq!(YarnValue::Number(1.0), loaded.get("$number").unwrap());
+        assert_eq!(
+            YarnValue::String("1".to_owned()),
+            loaded.get("$string").unwrap()
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_file_is_an_internal_error() {
+        let path = temporary_path("corrupt");
+        fs::write(&path, "{ not json").unwrap();
+
+        let error = FileVariableStorage::open(&path).unwrap_err();
+        assert!(matches!(error, VariableStorageError::InternalError { .. }));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_file_leaves_variables_unchanged() {
+        let path = temporary_path("invalid_name");
+        fs::write(
+            &path,
+            r#"{ "$valid": { "Number": 1.0 }, "invalid": { "Number": 2.0 } }"#,
+        )
+        .unwrap();
+        let mut storage = FileVariableStorage::new(&path);
+        storage.set("$existing".to_owned(), true.into()).unwrap();
+
+        let error = storage.reload().unwrap_err();
+        assert!(matches!(
+            error,
+            VariableStorageError::InvalidVariableName { .. }
+        ));
+        assert_eq!(
+            HashMap::from_iter([("$existing".to_owned(), true.into())]),
+            storage.variables()
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn failing_autosave_while_clearing_is_not_fatal() {
+        let path = temporary_path("missing_directory").join("variables.json");
+        let mut storage = FileVariableStorage::new(&path);
+        storage.set("$existing".to_owned(), true.into()).unwrap();
+        storage.set_autosave(true);
+
+        storage.clear();
+        assert!(storage.variables().is_empty());
+    }
+
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "yarnspinner_file_variable_storage_{name}_{}.json",
+            std::process::id()
+        ))
+    }
+}
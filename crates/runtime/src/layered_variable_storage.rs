@@ -0,0 +1,215 @@
+//! Contains [`LayeredVariableStorage`], a [`VariableStorage`] that keeps local, conversation and global variables in separate layers.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashMap;
+use core::any::Any;
+
+/// A [`VariableStorage`] that keeps the variables of each [`VariableScope`] in a layer of its own.
+///
+/// Variables are looked up in the local layer first, then in the conversation layer and finally in the global layer,
+/// so that a local variable shadows a global one of the same name for as long as it exists.
+/// [`VariableStorage::set`] writes to the layer a variable is already stored in, and to the global layer for new variables.
+///
+/// Since the layers are kept apart, only the global layer needs to be persisted in a save game:
+/// ```
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut storage = LayeredVariableStorage::default();
+/// storage.set_in_scope("$talked_to_guard".to_owned(), true.into(), VariableScope::Conversation)?;
+/// storage.set("$gold".to_owned(), 10.into())?;
+///
+/// assert_eq!(Some(VariableScope::Conversation), storage.scope("$talked_to_guard"));
+/// assert_eq!(1, storage.layer(VariableScope::Global).variables().len());
+/// # Ok::<(), VariableStorageError>(())
+/// ```
+///
+/// Clones and shallow clones share their layers.
+#[derive(Debug, Clone)]
+pub struct LayeredVariableStorage {
+    local: Box<dyn VariableStorage>,
+    conversation: Box<dyn VariableStorage>,
+    global: Box<dyn VariableStorage>,
+}
+
+impl Default for LayeredVariableStorage {
+    /// Creates a storage whose layers are all [`MemoryVariableStorage`]s.
+    fn default() -> Self {
+        Self::new(Box::new(MemoryVariableStorage::new()))
+    }
+}
+
+impl LayeredVariableStorage {
+    /// Creates a storage that uses the given storage as its global layer, e.g. a `FileVariableStorage`.
+    /// The local and conversation layers are [`MemoryVariableStorage`]s.
+    pub fn new(global: Box<dyn VariableStorage>) -> Self {
+        Self {
+            local: Box::new(MemoryVariableStorage::new()),
+            conversation: Box::new(MemoryVariableStorage::new()),
+            global,
+        }
+    }
+
+    /// Gets the layer that holds the variables of the given scope.
+    #[must_use]
+    pub fn layer(&self, scope: VariableScope) -> &dyn VariableStorage {
+        match scope {
+            VariableScope::Local => self.local.as_ref(),
+            VariableScope::Conversation => self.conversation.as_ref(),
+            VariableScope::Global => self.global.as_ref(),
+        }
+    }
+
+    /// Gets the layer that holds the variables of the given scope mutably.
+    pub fn layer_mut(&mut self, scope: VariableScope) -> &mut dyn VariableStorage {
+        match scope {
+            VariableScope::Local => self.local.as_mut(),
+            VariableScope::Conversation => self.conversation.as_mut(),
+            VariableScope::Global => self.global.as_mut(),
+        }
+    }
+
+    /// The layers in the order variables are looked up in.
+    fn layers(&self) -> [(VariableScope, &dyn VariableStorage); 3] {
+        [
+            VariableScope::Local,
+            VariableScope::Conversation,
+            VariableScope::Global,
+        ]
+        .map(|scope| (scope, self.layer(scope)))
+    }
+}
+
+impl VariableStorage for LayeredVariableStorage {
+    fn clone_shallow(&self) -> Box<dyn VariableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, name: String, value: YarnValue) -> Result<()> {
+        let scope = self.scope(&name).unwrap_or(VariableScope::Global);
+        self.layer_mut(scope).set(name, value)
+    }
+
+    fn get(&self, name: &str) -> Result<YarnValue> {
+        for (_, layer) in self.layers() {
+            match layer.get(name) {
+                Err(VariableStorageError::VariableNotFound { .. }) => continue,
+                result => return result,
+            }
+        }
+        Err(VariableStorageError::VariableNotFound {
+            name: name.to_owned(),
+        })
+    }
+
+    fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
+        if let Some(name) = values.keys().find(|name| !name.starts_with('$')) {
+            return Err(VariableStorageError::InvalidVariableName { name: name.clone() });
+        }
+        let mut values_by_scope: HashMap<VariableScope, HashMap<String, YarnValue>> =
+            HashMap::default();
+        for (name, value) in values {
+            let scope = self.scope(&name).unwrap_or(VariableScope::Global);
+            values_by_scope
+                .entry(scope)
+                .or_default()
+                .insert(name, value);
+        }
+        for (scope, values) in values_by_scope {
+            self.layer_mut(scope).extend(values)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the variables of all layers. If a variable is stored in several layers, the value that [`VariableStorage::get`] returns wins.
+    fn variables(&self) -> HashMap<String, YarnValue> {
+        self.layers()
+            .into_iter()
+            .rev()
+            .flat_map(|(_, layer)| layer.variables())
+            .collect()
+    }
+
+    fn clear(&mut self) {
+        self.local.clear();
+        self.conversation.clear();
+        self.global.clear();
+    }
+
+    /// Removes the variable from all layers.
+    fn remove(&mut self, name: &str) -> Result<()> {
+        self.local.remove(name)?;
+        self.conversation.remove(name)?;
+        self.global.remove(name)
+    }
+
+    fn scope(&self, name: &str) -> Option<VariableScope> {
+        self.layers()
+            .into_iter()
+            .find(|(_, layer)| layer.contains(name))
+            .map(|(scope, _)| scope)
+    }
+
+    fn set_in_scope(&mut self, name: String, value: YarnValue, scope: VariableScope) -> Result<()> {
+        self.layer_mut(scope).set(name, value)
+    }
+
+    fn clear_scope(&mut self, scope: VariableScope) {
+        self.layer_mut(scope).clear();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_local_then_conversation_then_global() {
+        let mut storage = LayeredVariableStorage::default();
+        storage.set("$x".to_owned(), "global".into()).unwrap();
+        storage
+            .set_in_scope(
+                "$x".to_owned(),
+                "conversation".into(),
+                VariableScope::Conversation,
+            )
+            .unwrap();
+        assert_eq!(YarnValue::from("conversation"), storage.get("$x").unwrap());
+        storage
+            .set_in_scope("$x".to_owned(), "local".into(), VariableScope::Local)
+            .unwrap();
+        assert_eq!(YarnValue::from("local"), storage.get("$x").unwrap());
+        assert_eq!(Some(VariableScope::Local), storage.scope("$x"));
+        assert_eq!(YarnValue::from("local"), storage.variables()["$x"]);
+
+        storage.clear_scope(VariableScope::Local);
+        storage.clear_scope(VariableScope::Conversation);
+        assert_eq!(YarnValue::from("global"), storage.get("$x").unwrap());
+        assert_eq!(Some(VariableScope::Global), storage.scope("$x"));
+    }
+
+    #[test]
+    fn set_writes_to_the_layer_of_the_variable() {
+        let mut storage = LayeredVariableStorage::default();
+        storage
+            .set_in_scope("$met".to_owned(), false.into(), VariableScope::Conversation)
+            .unwrap();
+        storage.set("$met".to_owned(), true.into()).unwrap();
+        storage.set("$gold".to_owned(), 1.into()).unwrap();
+
+        assert_eq!(Some(VariableScope::Conversation), storage.scope("$met"));
+        assert_eq!(Some(VariableScope::Global), storage.scope("$gold"));
+        assert!(!storage.layer(VariableScope::Global).contains("$met"));
+        assert_eq!(None, storage.scope("$unknown"));
+        assert!(matches!(
+            storage.get("$unknown"),
+            Err(VariableStorageError::VariableNotFound { .. })
+        ));
+    }
+}
@@ -15,14 +15,25 @@ extern crate std;
 
 mod analyser;
 mod command;
+mod debugger;
 mod dialogue;
+mod dialogue_history;
 mod dialogue_option;
+mod dialogue_snapshot;
 mod events;
+#[cfg(all(feature = "std", feature = "serde"))]
+mod file_variable_storage;
 mod language;
+mod layered_variable_storage;
 mod line;
 pub mod markup;
+mod number_formatting;
 mod pluralization;
+mod random;
+mod saliency;
+mod seen_line_tracker;
 mod text_provider;
+mod typed_variable_storage;
 mod variable_storage;
 mod virtual_machine;
 
@@ -40,19 +51,29 @@ pub mod prelude {
         vec::Vec,
     };
 
+    #[cfg(all(feature = "std", feature = "serde"))]
+    pub use crate::file_variable_storage::*;
     pub use crate::{
         analyser::*,
         command::*,
+        debugger::*,
         dialogue::{Dialogue, DialogueError},
+        dialogue_history::*,
         dialogue_option::*,
+        dialogue_snapshot::*,
         events::*,
         language::*,
+        layered_variable_storage::*,
         line::*,
         markup::MarkupParseError,
+        random::*,
+        saliency::*,
+        seen_line_tracker::*,
         text_provider::*,
+        typed_variable_storage::*,
         variable_storage::*,
     };
-    pub(crate) use crate::{pluralization::*, virtual_machine::*};
+    pub(crate) use crate::{number_formatting::*, pluralization::*, virtual_machine::*};
     pub(crate) use yarnspinner_core::prelude::*;
     pub(crate) use yarnspinner_internal_shared::prelude::*;
 }
@@ -38,6 +38,14 @@ pub struct Line {
     pub text: String,
     /// The list of [`MarkupAttribute`] in this parse result.
     pub attributes: Vec<MarkupAttribute>,
+    /// The text of the expressions in the line, in the order they appear in it.
+    /// These are written without the format specifiers of the line, e.g. `{$gold:N0}`, which only apply to [`Line::text`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub substitutions: Vec<String>,
+    /// The values of the expressions in the line, in the order they appear in it.
+    /// Together with [`Line::id`], these are enough to produce the line again in another language, including its formatted numbers.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub values: Vec<YarnValue>,
 }
 
 impl Line {
@@ -68,6 +76,8 @@ impl Line {
     /// #        properties: HashMap::from([("name".to_owned(), "Alice".into())]),
     /// #        source_position: 0,
     /// #    }],
+    /// #    substitutions: vec![],
+    /// #    values: vec![],
     /// # };
     /// assert_eq!("Alice: Hello! How are you today?", line.text);
     /// assert_eq!(Some("Alice"), line.character_name());
@@ -83,6 +93,8 @@ impl Line {
     /// #    id: "line".into(),
     /// #    text: "Great, thanks".to_owned(),
     /// #    attributes: vec![],
+    /// #    substitutions: vec![],
+    /// #    values: vec![],
     /// # };
     /// assert_eq!("Great, thanks", line.text);
     /// assert!(line.character_name().is_none());
@@ -117,6 +129,8 @@ impl Line {
     /// #        properties: HashMap::from([("name".to_owned(), "Alice".into())]),
     /// #        source_position: 0,
     /// #    }],
+    /// #    substitutions: vec![],
+    /// #    values: vec![],
     /// # };
     /// assert_eq!("Alice: Hello! How are you today?", line.text);
     /// assert_eq!("Hello! How are you today?", &line.text_without_character_name());
@@ -132,6 +146,8 @@ impl Line {
     /// #    id: "line".into(),
     /// #    text: "Great, thanks".to_owned(),
     /// #    attributes: vec![],
+    /// #    substitutions: vec![],
+    /// #    values: vec![],
     /// # };
     /// assert_eq!("Great, thanks", line.text);
     /// assert_eq!("Great, thanks", &line.text_without_character_name());
@@ -198,6 +214,8 @@ impl Line {
                 id: self.id.clone(),
                 text: self.text.to_string(),
                 attributes,
+                substitutions: self.substitutions.clone(),
+                values: self.values.clone(),
             };
         }
         let deletion_start = attribute_to_delete.position;
@@ -273,6 +291,21 @@ impl Line {
             id: self.id.clone(),
             text: edited_substring,
             attributes,
+            substitutions: self.substitutions.clone(),
+            values: self.values.clone(),
+        }
+    }
+
+    /// Returns the [`Line::values`], falling back to the [`Line::substitutions`] for lines that were serialized before the values were recorded.
+    pub(crate) fn substitution_values(&self) -> Vec<YarnValue> {
+        if self.values.is_empty() {
+            self.substitutions
+                .iter()
+                .cloned()
+                .map(YarnValue::from)
+                .collect()
+        } else {
+            self.values.clone()
         }
     }
 }
@@ -394,6 +394,8 @@ mod tests {
                 id: "test".into(),
                 text: self.text.clone(),
                 attributes: self.attributes.clone(),
+                substitutions: vec![],
+                values: vec![],
             }
         }
     }
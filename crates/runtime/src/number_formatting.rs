@@ -0,0 +1,228 @@
+//! Contains [`NumberFormat`], which writes the value of an inline expression according to its format specifier, e.g. `N0` in `{$gold:N0}`.
+//!
+//! ## Implementation notes
+//!
+//! The original implementation leaves formatting to .NET, so the specifiers follow its numeric format strings. We use ICU to get the separators
+//! and the percent sign of the [`Dialogue`]'s language instead.
+
+use crate::prelude::*;
+use core::str::FromStr;
+use fixed_decimal::{FixedDecimal, Sign};
+use icu_decimal::FixedDecimalFormatter;
+use icu_decimal::options::GroupingStrategy;
+use icu_experimental::dimension::provider::percent::PercentEssentialsV1Marker;
+use icu_provider::prelude::*;
+
+/// How a number is written when its inline expression has a format specifier.
+///
+/// Format specifiers follow the numeric format strings of .NET, which the original implementation uses for its own string formatting:
+/// - `N` or `N2`: grouped digits with the given number of decimals, e.g. `1,234.50`. The default is 2 decimals.
+/// - `F` or `F2`: like `N`, but without grouping.
+/// - `D` or `D3`: a whole number with at least the given number of digits, e.g. `007`.
+/// - `P` or `P1`: the number multiplied by 100 with a percent sign, e.g. `12.5%`. The default is 2 decimals.
+///   Where the sign goes depends on the language, e.g. `12,5 %` in French and `%12,5` in Turkish.
+/// - Custom patterns made of `0`, `#`, `,` and `.`, e.g. `0.00` or `#,##0.#`. A `0` is a digit that is always written,
+///   a `#` is a decimal that is only written if it is not zero, and a `,` in front of the `.` turns on grouping.
+///
+/// Letters are case-insensitive. Separators and digits are chosen by ICU according to the language of the [`Dialogue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NumberFormat {
+    min_integer_digits: i16,
+    min_fraction_digits: i16,
+    max_fraction_digits: i16,
+    grouping: bool,
+    percent: bool,
+}
+
+impl NumberFormat {
+    /// The most digits a format specifier may ask for, so that a typo cannot produce absurdly long text.
+    const MAX_DIGITS: i16 = 99;
+
+    /// Parses a format specifier, returning `None` if it is not one of the supported formats.
+    pub(crate) fn parse(format_specifier: &str) -> Option<Self> {
+        let mut chars = format_specifier.chars();
+        let kind = chars.next()?;
+        if kind.is_ascii_alphabetic() {
+            Self::parse_standard(kind, chars.as_str())
+        } else {
+            Self::parse_custom(format_specifier)
+        }
+    }
+
+    fn parse_standard(kind: char, precision: &str) -> Option<Self> {
+        let precision = match precision {
+            "" => None,
+            precision => Some(
+                precision
+                    .parse::<i16>()
+                    .ok()
+                    .filter(|precision| (0..=Self::MAX_DIGITS).contains(precision))?,
+            ),
+        };
+        let fixed = |grouping, percent| {
+            let fraction_digits = precision.unwrap_or(2);
+            Self {
+                min_integer_digits: 1,
+                min_fraction_digits: fraction_digits,
+                max_fraction_digits: fraction_digits,
+                grouping,
+                percent,
+            }
+        };
+        match kind.to_ascii_uppercase() {
+            'N' => Some(fixed(true, false)),
+            'F' => Some(fixed(false, false)),
+            'P' => Some(fixed(true, true)),
+            'D' => Some(Self {
+                min_integer_digits: precision.unwrap_or(1),
+                min_fraction_digits: 0,
+                max_fraction_digits: 0,
+                grouping: false,
+                percent: false,
+            }),
+            _ => None,
+        }
+    }
+
+    fn parse_custom(pattern: &str) -> Option<Self> {
+        if !pattern.chars().all(|c| matches!(c, '0' | '#' | ',' | '.')) {
+            return None;
+        }
+        let (integer_part, fraction_part) = pattern.split_once('.').unwrap_or((pattern, ""));
+        if fraction_part.contains(['.', ',']) {
+            return None;
+        }
+        let count = |part: &str, digit: char| part.chars().filter(|&c| c == digit).count();
+        let min_fraction_digits = count(fraction_part, '0');
+        let max_fraction_digits = min_fraction_digits + count(fraction_part, '#');
+        let min_integer_digits = count(integer_part, '0');
+        let digits = [min_integer_digits, max_fraction_digits].map(|digits| {
+            i16::try_from(digits)
+                .ok()
+                .filter(|&d| d <= Self::MAX_DIGITS)
+        });
+        let [Some(min_integer_digits), Some(max_fraction_digits)] = digits else {
+            return None;
+        };
+        Some(Self {
+            min_integer_digits,
+            min_fraction_digits: min_fraction_digits as i16,
+            max_fraction_digits,
+            grouping: integer_part.contains(','),
+            percent: false,
+        })
+    }
+
+    /// Formats the number for the given language. Returns `None` for numbers that cannot be written as decimals, e.g. `NaN`.
+    pub(crate) fn format(&self, value: f32, language: &Language) -> Option<String> {
+        // Going through the shortest text that represents the `f32` keeps us from rounding its binary approximation,
+        // e.g. 2.675 is stored as 2.67499995 but should still be rounded to 2.68
+        let mut decimal = FixedDecimal::from_str(&value.to_string()).ok()?;
+        if self.percent {
+            decimal.multiply_pow10(2);
+        }
+        // Round half away from zero, just like .NET
+        decimal.half_expand(-self.max_fraction_digits);
+        decimal.trim_end();
+        decimal.pad_end(-self.min_fraction_digits);
+        decimal.pad_start(self.min_integer_digits);
+
+        let grouping_strategy = if self.grouping {
+            GroupingStrategy::Auto
+        } else {
+            GroupingStrategy::Never
+        };
+        // Implementation note: no need to fiddle with locales here because ICU already does fallbacks for us.
+        let locale = (&language.0).into();
+        let formatter = FixedDecimalFormatter::try_new(&locale, grouping_strategy.into()).ok()?;
+        if !self.percent {
+            return Some(formatter.format_to_string(&decimal));
+        }
+
+        let percent: DataPayload<PercentEssentialsV1Marker> = icu_experimental::provider::Baked
+            .load(DataRequest {
+                locale: &locale,
+                metadata: Default::default(),
+            })
+            .and_then(DataResponse::take_payload)
+            .ok()?;
+        let percent = percent.get();
+        let percent_sign = format!(
+            "{}{}{}",
+            percent.percent_sign_affixes.prefix,
+            percent.percent_sign_symbol,
+            percent.percent_sign_affixes.suffix
+        );
+        if percent.percent_symbol_index > percent.number_index {
+            return Some(formatter.format_to_string(&decimal) + &percent_sign);
+        }
+        // The minus sign stays in front of a leading percent sign, e.g. `-%50` in Turkish
+        let text = formatter.format_to_string(&decimal);
+        decimal.set_sign(Sign::None);
+        let magnitude = formatter.format_to_string(&decimal);
+        let minus_sign = text.strip_suffix(magnitude.as_str()).unwrap_or_default();
+        Some(format!("{minus_sign}{percent_sign}{magnitude}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(value: f32, format_specifier: &str, language: &str) -> Option<String> {
+        NumberFormat::parse(format_specifier)?.format(value, &Language::new(language))
+    }
+
+    #[test]
+    fn formats_standard_specifiers() {
+        let tests = [
+            (1234.5_f32, "N0", "en", "1,235"),
+            (1234.5, "N", "en", "1,234.50"),
+            (1234.5, "n1", "de", "1.234,5"),
+            (3.5000002, "F2", "en", "3.50"),
+            (2.675, "F2", "en", "2.68"),
+            (-2.5, "F0", "en", "-3"),
+            (7.0, "D3", "en", "007"),
+            (0.125, "P1", "en", "12.5%"),
+            (0.5, "P0", "en", "50%"),
+            (0.5, "P0", "fr", "50\u{a0}%"),
+            (0.125, "P1", "tr", "%12,5"),
+            (-0.5, "P0", "tr", "-%50"),
+        ];
+        for (value, format_specifier, language, expected) in tests {
+            assert_eq!(
+                Some(expected),
+                format(value, format_specifier, language).as_deref(),
+                "formatting {value} as {format_specifier} in {language}"
+            );
+        }
+    }
+
+    #[test]
+    fn formats_custom_patterns() {
+        let tests = [
+            (1.23456_f32, "0.00", "en", "1.23"),
+            (3.0, "0.##", "en", "3"),
+            (3.0, "0.0#", "en", "3.0"),
+            (3.125, "0.0#", "en", "3.13"),
+            (12345.678, "#,##0.#", "en", "12,345.7"),
+            (12345.678, "#,##0.#", "fr", "12\u{202f}345,7"),
+            (5.0, "000", "en", "005"),
+        ];
+        for (value, format_specifier, language, expected) in tests {
+            assert_eq!(
+                Some(expected),
+                format(value, format_specifier, language).as_deref(),
+                "formatting {value} as {format_specifier} in {language}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_specifiers() {
+        for format_specifier in ["", "X", "N-1", "N100", "0.0.0", "0.0,0", "abc"] {
+            assert_eq!(None, NumberFormat::parse(format_specifier));
+        }
+        assert_eq!(None, format(f32::NAN, "N0", "en"));
+    }
+}
@@ -0,0 +1,163 @@
+//! Contains [`DialogueRng`], the random number generator behind the `random`, `random_range` and `dice` functions and the [`RandomSaliencyStrategy`](crate::prelude::RandomSaliencyStrategy).
+//!
+//! ## Implementation notes
+//!
+//! The original implementation registers these functions in `Dialogue.cs` using an unseeded `System.Random`.
+//! We use a generator whose whole state is a single number, so that it can be saved and restored.
+
+use bevy_platform::sync::{Arc, RwLock};
+use rand::{Rng, RngCore};
+
+/// The random number generator used by the `random`, `random_range` and `dice` functions of a [`Dialogue`](crate::prelude::Dialogue),
+/// as well as by a [`RandomSaliencyStrategy`](crate::prelude::RandomSaliencyStrategy) created from it.
+///
+/// The generator's state is a single number that can be read with [`DialogueRng::state`] and restored with [`DialogueRng::set_state`],
+/// so that replays, automated tests and saved games take the same branches. It is also part of every [`DialogueSnapshot`](crate::prelude::DialogueSnapshot).
+/// Clones share their state, just like clones of a [`MemoryVariableStorage`](crate::prelude::MemoryVariableStorage).
+#[derive(Debug, Clone)]
+pub struct DialogueRng(Arc<RwLock<u64>>);
+
+impl Default for DialogueRng {
+    /// Creates a generator with a random seed when compiling with the `std` feature and a fixed seed otherwise.
+    fn default() -> Self {
+        Self::from_seed(default_seed())
+    }
+}
+
+impl DialogueRng {
+    /// Creates a generator with a random seed. See [`DialogueRng::default`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a generator that always produces the same numbers for the same `seed`.
+    pub fn from_seed(seed: u64) -> Self {
+        Self(Arc::new(RwLock::new(seed)))
+    }
+
+    /// Reseeds the generator. Same as [`DialogueRng::set_state`], since the seed is simply the initial state.
+    pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+        self.set_state(seed)
+    }
+
+    /// Gets the current state of the generator, e.g. to save it.
+    #[must_use]
+    pub fn state(&self) -> u64 {
+        *self.0.read().unwrap()
+    }
+
+    /// Restores a state previously returned by [`DialogueRng::state`].
+    pub fn set_state(&mut self, state: u64) -> &mut Self {
+        *self.0.write().unwrap() = state;
+        self
+    }
+
+    /// Returns a random number between 0 (inclusive) and 1 (exclusive). Backs the `random` function.
+    pub fn random(&self) -> f32 {
+        self.with_rng(|rng| rng.random_range(0.0..1.0))
+    }
+
+    /// Returns a random number between `min` and `max`. Backs the `random_range` function.
+    ///
+    /// If both bounds are integers, the result is an integer and `max` is inclusive. Otherwise, `max` is exclusive.
+    /// Returns `min` if the range is empty.
+    pub fn random_range(&self, min: f32, max: f32) -> f32 {
+        let is_integer = |number: f32| number as i32 as f32 == number;
+        if is_integer(min) && is_integer(max) {
+            let (min, max) = (min as i32, max as i32);
+            if max < min {
+                return min as f32;
+            }
+            return self.with_rng(|rng| rng.random_range(min..=max)) as f32;
+        }
+        if max <= min {
+            return min;
+        }
+        self.with_rng(|rng| rng.random_range(min..max))
+    }
+
+    /// Returns the result of rolling a die with the given number of sides, i.e. a number between 1 and `sides`.
+    /// Returns `None` for a die with no sides. Backs the `dice` function.
+    pub fn dice(&self, sides: u32) -> Option<u32> {
+        (sides > 0).then(|| self.with_rng(|rng| rng.random_range(1..=sides)))
+    }
+
+    /// Returns a random index into a collection of `len` elements. Backs the [`RandomSaliencyStrategy`](crate::prelude::RandomSaliencyStrategy).
+    pub(crate) fn index(&self, len: usize) -> usize {
+        self.with_rng(|rng| rng.random_range(0..len))
+    }
+
+    fn with_rng<T>(&self, f: impl FnOnce(&mut SplitMix64) -> T) -> T {
+        let mut state = self.0.write().unwrap();
+        f(&mut SplitMix64(&mut state))
+    }
+}
+
+/// The SplitMix64 generator, see <https://prng.di.unimi.it/splitmix64.c>.
+struct SplitMix64<'a>(&'a mut u64);
+
+impl RngCore for SplitMix64<'_> {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        *self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = *self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        rand::rand_core::impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+#[cfg(feature = "std")]
+fn default_seed() -> u64 {
+    use core::hash::BuildHasher;
+    std::hash::RandomState::new().hash_one(0_u8)
+}
+
+#[cfg(not(feature = "std"))]
+fn default_seed() -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn same_seed_produces_same_numbers() {
+        let first = DialogueRng::from_seed(42);
+        let second = DialogueRng::from_seed(42);
+        for _ in 0..10 {
+            assert_eq!(first.random(), second.random());
+            assert_eq!(first.dice(6), second.dice(6));
+        }
+    }
+
+    #[test]
+    fn restoring_state_repeats_numbers() {
+        let mut rng = DialogueRng::from_seed(7);
+        rng.random();
+        let state = rng.state();
+        let expected: Vec<_> = (0..5).map(|_| rng.random_range(1.0, 10.0)).collect();
+
+        rng.set_state(state);
+        let actual: Vec<_> = (0..5).map(|_| rng.random_range(1.0, 10.0)).collect();
+        assert_eq!(expected, actual);
+        assert!(actual.iter().all(|number| (1.0..=10.0).contains(number)));
+        assert!(actual.iter().all(|number| *number as i32 as f32 == *number));
+    }
+
+    #[test]
+    fn dice_needs_at_least_one_side() {
+        let rng = DialogueRng::from_seed(3);
+        assert_eq!(None, rng.dice(0));
+        assert_eq!(Some(1), rng.dice(1));
+    }
+}
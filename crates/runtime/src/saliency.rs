@@ -0,0 +1,292 @@
+//! Contains the [`SaliencyStrategy`] trait and its built-in implementations, which decide which variant of a line group is run.
+//!
+//! ## Implementation notes
+//!
+//! Corresponds to the content saliency strategies of Yarn Spinner 3, which are not present at the commit we are porting from.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashMap;
+use bevy_platform::sync::{Arc, RwLock};
+use core::any::Any;
+use core::cmp::Reverse;
+use core::fmt::Debug;
+
+/// A piece of content that a [`SaliencyStrategy`] can select, e.g. a variant of a line group whose condition passed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub struct SaliencyCandidate {
+    /// The ID of the content. For the variants of a line group, this is their line ID.
+    pub content_id: String,
+
+    /// How specific the condition of this candidate is.
+    /// A candidate without a condition has a score of 0, and every clause of the condition adds 1,
+    /// so `<<if $met_guard and $has_sword>>` scores 2.
+    pub complexity_score: usize,
+
+    /// The label the [`Dialogue`] jumps to when this candidate is selected.
+    pub destination: String,
+}
+
+/// Selects which of several [`SaliencyCandidate`]s should be run, e.g. which variant of a line group is delivered.
+/// Set the strategy used by a [`Dialogue`] with [`Dialogue::set_saliency_strategy`].
+///
+/// The built-in strategies are:
+/// - [`FirstSaliencyStrategy`]: always selects the first candidate.
+/// - [`RandomSaliencyStrategy`]: selects a random candidate.
+/// - [`LeastRecentlyViewedSaliencyStrategy`]: selects the candidate that was seen the longest time ago. This is the default.
+/// - [`BestSaliencyStrategy`]: selects the candidate with the most specific condition.
+pub trait SaliencyStrategy: Debug + Send + Sync {
+    /// Creates a shallow clone of this strategy, i.e. a clone that
+    /// shares the same underlying state and will thus be perfectly in sync
+    /// with the original instance.
+    fn clone_shallow(&self) -> Box<dyn SaliencyStrategy>;
+    /// Returns the candidate that should be run, or `None` if none of them should be run.
+    /// Only candidates whose conditions passed are passed to this method, in the order they appear in the Yarn script.
+    /// `candidates` is never empty.
+    fn query_best_content<'a>(
+        &mut self,
+        candidates: &'a [SaliencyCandidate],
+    ) -> Option<&'a SaliencyCandidate>;
+    /// Called when the candidate returned by [`SaliencyStrategy::query_best_content`] is about to be run.
+    /// Strategies that want to avoid repetition should record the candidate as seen here.
+    fn content_was_selected(&mut self, candidate: &SaliencyCandidate);
+    /// Gets the [`SaliencyStrategy`] as a trait object.
+    /// This allows retrieving the concrete type by downcasting, using the `downcast_ref` method available through the `Any` trait.
+    fn as_any(&self) -> &dyn Any;
+    /// Gets the [`SaliencyStrategy`] as a mutable trait object.
+    /// This allows retrieving the concrete type by downcasting, using the `downcast_mut` method available through the `Any` trait.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl Clone for Box<dyn SaliencyStrategy> {
+    fn clone(&self) -> Self {
+        self.clone_shallow()
+    }
+}
+
+/// A [`SaliencyStrategy`] that always selects the first candidate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstSaliencyStrategy;
+
+impl SaliencyStrategy for FirstSaliencyStrategy {
+    fn clone_shallow(&self) -> Box<dyn SaliencyStrategy> {
+        Box::new(*self)
+    }
+
+    fn query_best_content<'a>(
+        &mut self,
+        candidates: &'a [SaliencyCandidate],
+    ) -> Option<&'a SaliencyCandidate> {
+        candidates.first()
+    }
+
+    fn content_was_selected(&mut self, _candidate: &SaliencyCandidate) {}
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A [`SaliencyStrategy`] that selects the candidate with the highest [`SaliencyCandidate::complexity_score`],
+/// i.e. the one whose condition most specifically matches the current state of the game.
+/// Ties are broken by selecting the first of the tied candidates.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BestSaliencyStrategy;
+
+impl SaliencyStrategy for BestSaliencyStrategy {
+    fn clone_shallow(&self) -> Box<dyn SaliencyStrategy> {
+        Box::new(*self)
+    }
+
+    fn query_best_content<'a>(
+        &mut self,
+        candidates: &'a [SaliencyCandidate],
+    ) -> Option<&'a SaliencyCandidate> {
+        candidates
+            .iter()
+            .min_by_key(|candidate| Reverse(candidate.complexity_score))
+    }
+
+    fn content_was_selected(&mut self, _candidate: &SaliencyCandidate) {}
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A [`SaliencyStrategy`] that selects the candidate that was selected the longest time ago, preferring candidates that were never selected.
+/// Ties are broken by selecting the candidate with the highest [`SaliencyCandidate::complexity_score`], and then the first of those.
+///
+/// This is the default strategy of a [`Dialogue`], since it cycles through all variants of a line group before repeating any of them.
+#[derive(Debug, Clone, Default)]
+pub struct LeastRecentlyViewedSaliencyStrategy(Arc<RwLock<ViewHistory>>);
+
+#[derive(Debug, Clone, Default)]
+struct ViewHistory {
+    /// Incremented on every selection, so that a higher number means a more recent view.
+    views: u64,
+    last_viewed: HashMap<String, u64>,
+}
+
+impl LeastRecentlyViewedSaliencyStrategy {
+    /// Creates a new [`LeastRecentlyViewedSaliencyStrategy`] that has not seen any content yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets which content has been seen.
+    pub fn clear(&mut self) {
+        *self.0.write().unwrap() = Default::default();
+    }
+}
+
+impl SaliencyStrategy for LeastRecentlyViewedSaliencyStrategy {
+    fn clone_shallow(&self) -> Box<dyn SaliencyStrategy> {
+        Box::new(self.clone())
+    }
+
+    fn query_best_content<'a>(
+        &mut self,
+        candidates: &'a [SaliencyCandidate],
+    ) -> Option<&'a SaliencyCandidate> {
+        let history = self.0.read().unwrap();
+        candidates.iter().min_by_key(|candidate| {
+            let last_viewed = history.last_viewed.get(&candidate.content_id).copied();
+            (last_viewed, Reverse(candidate.complexity_score))
+        })
+    }
+
+    fn content_was_selected(&mut self, candidate: &SaliencyCandidate) {
+        let mut history = self.0.write().unwrap();
+        history.views += 1;
+        let views = history.views;
+        history
+            .last_viewed
+            .insert(candidate.content_id.clone(), views);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A [`SaliencyStrategy`] that selects a random candidate.
+///
+/// Pass it the [`Dialogue::rng`] of the dialogue it is used in, so that its selections are part of the generator's state
+/// and are repeated after restoring a [`DialogueSnapshot`], loading a saved game or rewinding:
+/// ```rust
+/// # use yarnspinner_runtime::prelude::*;
+/// # fn set_strategy(dialogue: &mut Dialogue) {
+/// let strategy = RandomSaliencyStrategy::new(dialogue.rng().clone());
+/// dialogue.set_saliency_strategy(strategy);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RandomSaliencyStrategy(DialogueRng);
+
+impl RandomSaliencyStrategy {
+    /// Creates a new [`RandomSaliencyStrategy`] that draws from `rng`. Since clones of a [`DialogueRng`] share their state,
+    /// passing a clone of [`Dialogue::rng`] makes the strategy advance the dialogue's generator.
+    pub fn new(rng: DialogueRng) -> Self {
+        Self(rng)
+    }
+}
+
+impl SaliencyStrategy for RandomSaliencyStrategy {
+    fn clone_shallow(&self) -> Box<dyn SaliencyStrategy> {
+        Box::new(self.clone())
+    }
+
+    fn query_best_content<'a>(
+        &mut self,
+        candidates: &'a [SaliencyCandidate],
+    ) -> Option<&'a SaliencyCandidate> {
+        if candidates.is_empty() {
+            return None;
+        }
+        candidates.get(self.0.index(candidates.len()))
+    }
+
+    fn content_was_selected(&mut self, _candidate: &SaliencyCandidate) {}
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<SaliencyCandidate> {
+        [("line:a", 0), ("line:b", 2), ("line:c", 2)]
+            .into_iter()
+            .map(|(content_id, complexity_score)| SaliencyCandidate {
+                content_id: content_id.to_owned(),
+                complexity_score,
+                destination: format!("L{content_id}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn best_strategy_selects_first_most_complex_candidate() {
+        let candidates = candidates();
+        let selected = BestSaliencyStrategy.query_best_content(&candidates);
+        assert_eq!("line:b", selected.unwrap().content_id);
+    }
+
+    #[test]
+    fn least_recently_viewed_strategy_cycles_through_candidates() {
+        let candidates = candidates();
+        let mut strategy = LeastRecentlyViewedSaliencyStrategy::new();
+        let selected: Vec<_> = (0..4)
+            .map(|_| {
+                let candidate = strategy.query_best_content(&candidates).unwrap().clone();
+                strategy.content_was_selected(&candidate);
+                candidate.content_id
+            })
+            .collect();
+        assert_eq!(vec!["line:b", "line:c", "line:a", "line:b"], selected);
+    }
+
+    #[test]
+    fn random_strategy_is_reproducible() {
+        let candidates = candidates();
+        let select = |seed| {
+            let mut strategy = RandomSaliencyStrategy::new(DialogueRng::from_seed(seed));
+            (0..8)
+                .map(|_| {
+                    strategy
+                        .query_best_content(&candidates)
+                        .unwrap()
+                        .content_id
+                        .clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(select(42), select(42));
+    }
+}
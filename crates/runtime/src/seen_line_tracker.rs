@@ -0,0 +1,117 @@
+//! Contains [`SeenLineTracker`], which counts how often each line was delivered across dialogue runs.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashMap;
+use bevy_platform::sync::{Arc, RwLock};
+
+/// Records how often each line was delivered, e.g. to mark read text or to skip it in a visual novel.
+///
+/// Seen lines are kept apart from the [`VariableStorage`], since they usually belong to the player rather than to a single save slot.
+/// Pass a tracker to [`Dialogue::set_seen_line_tracker`] to record every [`DialogueEvent::Line`] returned by [`Dialogue::continue_`],
+/// and keep a clone of it around to query and persist it. Clones share their state, just like clones of a [`MemoryVariableStorage`].
+///
+/// When compiling with the `serde` feature, a tracker is serialized as a map from [`LineId`] to the number of times the line was seen:
+/// ```
+/// # use yarnspinner_core::prelude::*;
+/// # use yarnspinner_runtime::prelude::*;
+/// let mut tracker = SeenLineTracker::new();
+/// assert_eq!(1, tracker.mark_seen(LineId::from("line:intro")));
+/// assert_eq!(2, tracker.mark_seen(LineId::from("line:intro")));
+///
+/// assert!(tracker.is_seen(&LineId::from("line:intro")));
+/// assert!(!tracker.is_seen(&LineId::from("line:outro")));
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "HashMap<LineId, usize>", into = "HashMap<LineId, usize>")
+)]
+pub struct SeenLineTracker(Arc<RwLock<HashMap<LineId, usize>>>);
+
+impl SeenLineTracker {
+    /// Creates a new tracker that has not seen any lines yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the line was delivered and returns how often it has been seen, including this time.
+    pub fn mark_seen(&mut self, line_id: LineId) -> usize {
+        let mut seen_lines = self.0.write().unwrap();
+        let times_seen = seen_lines.entry(line_id).or_default();
+        *times_seen += 1;
+        *times_seen
+    }
+
+    /// Returns how often the line was delivered.
+    #[must_use]
+    pub fn times_seen(&self, line_id: &LineId) -> usize {
+        self.0
+            .read()
+            .unwrap()
+            .get(line_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if the line was delivered at least once.
+    #[must_use]
+    pub fn is_seen(&self, line_id: &LineId) -> bool {
+        self.times_seen(line_id) > 0
+    }
+
+    /// Returns the IDs of all lines that were delivered at least once, in no particular order.
+    #[must_use]
+    pub fn seen_lines(&self) -> Vec<LineId> {
+        self.0.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Adds the lines seen by `other` to this tracker, e.g. to combine the trackers of several save slots.
+    pub fn merge(&mut self, other: &SeenLineTracker) {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return;
+        }
+        let other = other.0.read().unwrap().clone();
+        let mut seen_lines = self.0.write().unwrap();
+        for (line_id, times_seen) in other {
+            *seen_lines.entry(line_id).or_default() += times_seen;
+        }
+    }
+
+    /// Forgets all seen lines.
+    pub fn clear(&mut self) {
+        self.0.write().unwrap().clear();
+    }
+}
+
+impl From<HashMap<LineId, usize>> for SeenLineTracker {
+    fn from(seen_lines: HashMap<LineId, usize>) -> Self {
+        Self(Arc::new(RwLock::new(seen_lines)))
+    }
+}
+
+impl From<SeenLineTracker> for HashMap<LineId, usize> {
+    fn from(tracker: SeenLineTracker) -> Self {
+        tracker.0.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_adds_up_times_seen() {
+        let mut tracker = SeenLineTracker::new();
+        tracker.mark_seen("line:a".into());
+        let mut other = SeenLineTracker::new();
+        other.mark_seen("line:a".into());
+        other.mark_seen("line:b".into());
+
+        tracker.merge(&other);
+        tracker.merge(&tracker.clone());
+        assert_eq!(2, tracker.times_seen(&"line:a".into()));
+        assert_eq!(1, tracker.times_seen(&"line:b".into()));
+        assert_eq!(0, other.times_seen(&"line:c".into()));
+    }
+}
@@ -17,6 +17,7 @@ pub trait TextProvider: Debug + Send + Sync {
     /// with the original instance.
     fn clone_shallow(&self) -> Box<dyn TextProvider>;
     /// Passes the [`LineId`]s that this [`TextProvider`] should soon provide text for. These are the [`LineId`]s that are contained in the current node and are not required to be actually reached.
+    /// They are ordered by priority and may also contain lines of upcoming nodes, see [`DialogueEvent::LineHints`].
     fn accept_line_hints(&mut self, line_ids: &[LineId]);
     /// Returns the text for the given [`LineId`]. Will only be called if [`TextProvider::are_lines_available`] returns `true`.
     fn get_text(&self, id: &LineId) -> Option<String>;
@@ -0,0 +1,258 @@
+//! Contains [`TypedVariableStorage`], a [`VariableStorage`] that rejects values whose type does not match the declaration of the variable.
+//!
+//! ## Implementation notes
+//!
+//! The original implementation only checks types when compiling, so the game can store a value of any type in a declared variable.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashMap;
+use bevy_platform::sync::{Arc, RwLock};
+use core::any::Any;
+use yarnspinner_core::types::TypedValue;
+
+/// A [`VariableStorage`] that wraps another one and knows the declared types of the variables it stores.
+/// Setting a declared variable to a value of a different type fails with a [`VariableStorageError::TypeMismatch`]
+/// instead of failing later when the script uses the variable. Variables that were not declared can still be set to anything.
+///
+/// The declarations are usually the ones of the `Compilation` that produced the [`Program`]:
+/// ```ignore
+/// let mut storage = TypedVariableStorage::new(Box::new(MemoryVariableStorage::new()));
+/// for declaration in &compilation.declarations {
+///     storage.declare(&declaration.name, declaration.r#type.clone(), declaration.default_value.clone())?;
+/// }
+/// let dialogue = Dialogue::new(Box::new(storage), text_provider);
+/// ```
+///
+/// Clones and shallow clones share both their declarations and the wrapped storage.
+#[derive(Debug, Clone)]
+pub struct TypedVariableStorage {
+    storage: Box<dyn VariableStorage>,
+    declarations: Arc<RwLock<HashMap<String, DeclaredVariable>>>,
+}
+
+#[derive(Debug, Clone)]
+struct DeclaredVariable {
+    r#type: Type,
+    default_value: Option<YarnValue>,
+}
+
+impl TypedVariableStorage {
+    /// Wraps the given storage. No variables are declared yet, see [`TypedVariableStorage::declare`].
+    pub fn new(storage: Box<dyn VariableStorage>) -> Self {
+        Self {
+            storage,
+            declarations: Default::default(),
+        }
+    }
+
+    /// Declares a variable of the given type. The `default_value` is stored whenever the storage is cleared.
+    ///
+    /// Declarations of functions are ignored, so all declarations of a `Compilation` can be passed in.
+    /// Fails with a [`VariableStorageError::InvalidVariableName`] if the name of a variable does not start with a `$`
+    /// and with a [`VariableStorageError::TypeMismatch`] if the `default_value` does not have the declared type.
+    pub fn declare(
+        &mut self,
+        name: impl Into<String>,
+        r#type: Type,
+        default_value: Option<YarnValue>,
+    ) -> Result<&mut Self> {
+        if matches!(r#type, Type::Function(_)) {
+            return Ok(self);
+        }
+        let name = name.into();
+        if !name.starts_with('$') {
+            return Err(VariableStorageError::InvalidVariableName { name });
+        }
+        if let Some(default_value) = &default_value {
+            check_type(&name, &r#type, default_value)?;
+        }
+        self.declarations.write().unwrap().insert(
+            name,
+            DeclaredVariable {
+                r#type,
+                default_value,
+            },
+        );
+        Ok(self)
+    }
+
+    /// Gets the declared type of a variable, if it was declared.
+    #[must_use]
+    pub fn declared_type(&self, name: &str) -> Option<Type> {
+        self.declarations
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|declaration| declaration.r#type.clone())
+    }
+
+    /// Gets the names of all stored variables that were not declared, in alphabetical order.
+    #[must_use]
+    pub fn undeclared_variables(&self) -> Vec<String> {
+        let declarations = self.declarations.read().unwrap();
+        let mut names: Vec<_> = self
+            .storage
+            .variables()
+            .into_keys()
+            .filter(|name| !declarations.contains_key(name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Gets the wrapped [`VariableStorage`].
+    #[must_use]
+    pub fn storage(&self) -> &dyn VariableStorage {
+        self.storage.as_ref()
+    }
+
+    fn check_type(&self, name: &str, value: &YarnValue) -> Result<()> {
+        match self.declarations.read().unwrap().get(name) {
+            Some(declaration) => check_type(name, &declaration.r#type, value),
+            None => Ok(()),
+        }
+    }
+}
+
+impl VariableStorage for TypedVariableStorage {
+    fn clone_shallow(&self) -> Box<dyn VariableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn set(&mut self, name: String, value: YarnValue) -> Result<()> {
+        self.check_type(&name, &value)?;
+        self.storage.set(name, value)
+    }
+
+    fn get(&self, name: &str) -> Result<YarnValue> {
+        self.storage.get(name)
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.storage.contains(name)
+    }
+
+    fn extend(&mut self, values: HashMap<String, YarnValue>) -> Result<()> {
+        for (name, value) in &values {
+            self.check_type(name, value)?;
+        }
+        self.storage.as_mut().extend(values)
+    }
+
+    fn variables(&self) -> HashMap<String, YarnValue> {
+        self.storage.variables()
+    }
+
+    fn remove(&mut self, name: &str) -> Result<()> {
+        self.storage.remove(name)
+    }
+
+    fn scope(&self, name: &str) -> Option<VariableScope> {
+        self.storage.scope(name)
+    }
+
+    fn set_in_scope(&mut self, name: String, value: YarnValue, scope: VariableScope) -> Result<()> {
+        self.check_type(&name, &value)?;
+        self.storage.set_in_scope(name, value, scope)
+    }
+
+    fn clear_scope(&mut self, scope: VariableScope) {
+        self.storage.clear_scope(scope)
+    }
+
+    /// Clears all variables of the wrapped storage and stores the default values of the declared variables.
+    fn clear(&mut self) {
+        self.storage.clear();
+        let default_values = self
+            .declarations
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(name, declaration)| {
+                declaration
+                    .default_value
+                    .clone()
+                    .map(|value| (name.clone(), value))
+            })
+            .collect();
+        self.storage
+            .as_mut()
+            .extend(default_values)
+            .unwrap_or_else(|e| {
+                panic!("Failed to store default values of declared variables: {e}")
+            });
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn check_type(name: &str, r#type: &Type, value: &YarnValue) -> Result<()> {
+    let matches = match r#type {
+        Type::Any | Type::Function(_) => true,
+        // Enums are stored as the raw value of one of their cases
+        Type::Enum(enum_type) => enum_type.cases.iter().any(|case| &case.raw_value == value),
+        r#type => &value.r#type() == r#type,
+    };
+    if matches {
+        Ok(())
+    } else {
+        Err(VariableStorageError::TypeMismatch {
+            name: name.to_owned(),
+            expected: r#type.clone(),
+            value: value.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_values_of_wrong_type() {
+        let mut storage = typed_storage();
+        storage.set("$gold".to_owned(), 10.into()).unwrap();
+        storage
+            .set("$undeclared".to_owned(), "anything".into())
+            .unwrap();
+
+        let error = storage.set("$gold".to_owned(), "lots".into()).unwrap_err();
+        assert!(matches!(
+            error,
+            VariableStorageError::TypeMismatch { ref name, expected: Type::Number, .. } if name == "$gold"
+        ));
+        assert_eq!(YarnValue::from(10), storage.get("$gold").unwrap());
+    }
+
+    #[test]
+    fn clear_stores_default_values() {
+        let mut storage = typed_storage();
+        storage.set("$gold".to_owned(), 10.into()).unwrap();
+        storage.set("$undeclared".to_owned(), true.into()).unwrap();
+        assert_eq!(
+            vec!["$undeclared".to_owned()],
+            storage.undeclared_variables()
+        );
+
+        storage.clear();
+        assert_eq!(YarnValue::from(0), storage.get("$gold").unwrap());
+        assert!(!storage.contains("$undeclared"));
+        assert!(storage.undeclared_variables().is_empty());
+    }
+
+    fn typed_storage() -> TypedVariableStorage {
+        let mut storage = TypedVariableStorage::new(Box::new(MemoryVariableStorage::new()));
+        storage
+            .declare("$gold", Type::Number, Some(0.into()))
+            .unwrap()
+            .declare("visited", Type::Function(Default::default()), None)
+            .unwrap();
+        storage
+    }
+}
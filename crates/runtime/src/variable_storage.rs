@@ -38,6 +38,42 @@ pub trait VariableStorage: Debug + Send + Sync {
     fn variables(&self) -> HashMap<String, YarnValue>;
     /// Clears all variables in this variable storage.
     fn clear(&mut self);
+    /// Removes a variable, so that it is no longer defined. Does nothing if the variable is not defined.
+    ///
+    /// The default implementation rebuilds the storage from [`VariableStorage::variables`],
+    /// so storages that can remove a single variable directly should override it.
+    fn remove(&mut self, name: &str) -> Result<()> {
+        if !self.contains(name) {
+            return Ok(());
+        }
+        let mut variables = self.variables();
+        variables.remove(name);
+        self.clear();
+        self.extend(variables)
+    }
+    /// Gets the [`VariableScope`] a variable is stored in, or `None` if the variable is not defined.
+    ///
+    /// The default implementation is meant for storages without scopes and reports every defined variable as [`VariableScope::Global`].
+    fn scope(&self, name: &str) -> Option<VariableScope> {
+        self.contains(name).then_some(VariableScope::Global)
+    }
+    /// Sets the value of a variable in the given [`VariableScope`].
+    /// Called by the [`Dialogue`] for local variables, see [`VariableScope::Local`].
+    ///
+    /// The default implementation is meant for storages without scopes. It ignores local variables,
+    /// since the [`Dialogue`] keeps track of those itself, and stores all other variables like [`VariableStorage::set`].
+    fn set_in_scope(&mut self, name: String, value: YarnValue, scope: VariableScope) -> Result<()> {
+        match scope {
+            VariableScope::Local => Ok(()),
+            VariableScope::Conversation | VariableScope::Global => self.set(name, value),
+        }
+    }
+    /// Removes all variables of the given [`VariableScope`].
+    /// Called by the [`Dialogue`] whenever a node is left for [`VariableScope::Local`]
+    /// and whenever the dialogue stops for [`VariableScope::Conversation`].
+    ///
+    /// The default implementation is meant for storages without scopes and does nothing.
+    fn clear_scope(&mut self, _scope: VariableScope) {}
     /// Gets the [`VariableStorage`] as a trait object.
     /// This allows retrieving the concrete type by downcasting, using the `downcast_ref` method available through the `Any` trait.
     fn as_any(&self) -> &dyn Any;
@@ -54,12 +90,41 @@ impl Extend<(String, YarnValue)> for Box<dyn VariableStorage> {
     }
 }
 
+/// The lifetime of a variable. See [`LayeredVariableStorage`] for a [`VariableStorage`] that keeps the scopes apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq, Hash))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub enum VariableScope {
+    /// A variable introduced with `<<local>>`, which only exists until the node it was introduced in is left.
+    Local,
+    /// A variable that only exists until the dialogue stops, e.g. to pass information between the nodes of a single conversation.
+    Conversation,
+    /// A variable that lives as long as the [`VariableStorage`] itself. This is the scope of every variable set with `<<set>>`.
+    Global,
+}
+
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub enum VariableStorageError {
-    InvalidVariableName { name: String },
-    VariableNotFound { name: String },
-    InternalError { error: Box<dyn Error + Send + Sync> },
+    InvalidVariableName {
+        name: String,
+    },
+    VariableNotFound {
+        name: String,
+    },
+    InternalError {
+        error: Box<dyn Error + Send + Sync>,
+    },
+    TypeMismatch {
+        name: String,
+        expected: Type,
+        value: YarnValue,
+    },
 }
 
 impl Error for VariableStorageError {}
@@ -74,6 +139,14 @@ impl Display for VariableStorageError {
             ),
             VariableNotFound { name } => write!(f, "Variable name {name} is not defined"),
             InternalError { error } => write!(f, "Internal variable storage error: {error}"),
+            TypeMismatch {
+                name,
+                expected,
+                value,
+            } => write!(
+                f,
+                "Cannot set {name} to {value}: Variable is declared as {expected}"
+            ),
         }
     }
 }
@@ -131,6 +204,12 @@ impl VariableStorage for MemoryVariableStorage {
         self.0.write().unwrap().clear();
     }
 
+    fn remove(&mut self, name: &str) -> Result<()> {
+        Self::validate_name(name)?;
+        self.0.write().unwrap().remove(name);
+        Ok(())
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -141,7 +220,7 @@ impl VariableStorage for MemoryVariableStorage {
 }
 
 impl MemoryVariableStorage {
-    fn validate_name(name: impl AsRef<str>) -> Result<()> {
+    pub(crate) fn validate_name(name: impl AsRef<str>) -> Result<()> {
         let name = name.as_ref();
         if name.starts_with('$') {
             Ok(())
@@ -3,15 +3,30 @@
 //! ## Implementation Notes
 //! The `Operand` extensions and the `Operator` enum were moved into upstream crates to make them not depend on the runtime.
 
-pub(crate) use self::{execution_state::*, state::*};
+pub(crate) use self::{
+    execution_state::*, hot_swap::*, instruction_error::*, line_hints::*, rewind_point::*,
+    state::*, variable_journal::*,
+};
 use crate::Result;
 use crate::markup::{LineParser, ParsedMarkup};
 use crate::prelude::*;
-use core::fmt::Debug;
+use alloc::collections::VecDeque;
+use bevy_platform::collections::HashMap;
+use core::fmt::{Debug, Display};
 use log::*;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
 
 mod execution_state;
+mod hot_swap;
+mod instruction_error;
+mod line_hints;
+mod rewind_point;
 mod state;
+mod variable_journal;
+
+/// The return value of a registered function, or the message of the error it returned.
+pub(crate) type FunctionResult = core::result::Result<YarnValue, String>;
 
 #[derive(Debug, Clone)]
 pub(crate) struct VirtualMachine {
@@ -19,6 +34,14 @@ pub(crate) struct VirtualMachine {
     pub(crate) program: Option<Program>,
     pub(crate) variable_storage: Box<dyn VariableStorage>,
     pub(crate) line_hints_enabled: bool,
+    pub(crate) line_hints_lookahead: usize,
+    pub(crate) saliency_strategy: Box<dyn SaliencyStrategy>,
+    pub(crate) instruction_budget: Option<usize>,
+    pub(crate) rng: DialogueRng,
+    pub(crate) rollback_on_stop: bool,
+    journal: Option<VariableJournal>,
+    rewind_depth: usize,
+    rewind_points: VecDeque<RewindPoint>,
     current_node_name: Option<String>,
     state: State,
     execution_state: ExecutionState,
@@ -35,6 +58,7 @@ impl VirtualMachine {
         variable_storage: Box<dyn VariableStorage>,
         line_parser: LineParser,
         text_provider: Box<dyn TextProvider>,
+        rng: DialogueRng,
     ) -> Self {
         Self {
             library,
@@ -49,6 +73,14 @@ impl VirtualMachine {
             current_node: Default::default(),
             batched_events: Default::default(),
             line_hints_enabled: Default::default(),
+            line_hints_lookahead: Default::default(),
+            saliency_strategy: Box::new(LeastRecentlyViewedSaliencyStrategy::new()),
+            instruction_budget: Default::default(),
+            rng,
+            rollback_on_stop: Default::default(),
+            journal: Default::default(),
+            rewind_depth: Default::default(),
+            rewind_points: Default::default(),
         }
     }
 
@@ -68,6 +100,190 @@ impl VirtualMachine {
         self.variable_storage.as_mut()
     }
 
+    /// Stores a value in the [`VariableStorage`] and returns a [`DialogueEvent::VariableChanged`] if the value differs from the previous one.
+    pub(crate) fn set_variable(
+        &mut self,
+        name: String,
+        value: YarnValue,
+    ) -> Result<Option<DialogueEvent>> {
+        // A variable that was never stored still has the initial value it was declared with
+        let old = self
+            .variable_storage
+            .get(&name)
+            .ok()
+            .or_else(|| self.initial_value(&name));
+        let changed = old.as_ref() != Some(&value);
+        if let Some(journal) = self.journal.as_mut() {
+            journal.record(self.variable_storage.as_ref(), &name);
+        }
+        self.variable_storage.set(name.clone(), value.clone())?;
+        Ok(changed.then_some(DialogueEvent::VariableChanged {
+            name,
+            old,
+            new: value,
+        }))
+    }
+
+    fn initial_value(&self, name: &str) -> Option<YarnValue> {
+        self.program
+            .as_ref()
+            .and_then(|program| program.initial_values.get(name))
+            .map(|initial_value| initial_value.clone().into())
+    }
+
+    pub(crate) fn is_in_transaction(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Starts recording the variable writes so that they can be rolled back.
+    /// An explicitly begun transaction takes over an automatic one, so that it is no longer committed when the dialogue completes.
+    pub(crate) fn begin_transaction(&mut self, is_automatic: bool) {
+        let journal = self
+            .journal
+            .get_or_insert_with(|| VariableJournal::new(is_automatic));
+        journal.is_automatic &= is_automatic;
+    }
+
+    pub(crate) fn commit_transaction(&mut self) {
+        self.journal = None;
+    }
+
+    /// Undoes all variable writes since the transaction began and returns a [`DialogueEvent::VariableChanged`] for every variable whose value changed back.
+    pub(crate) fn rollback_transaction(&mut self) -> Result<Vec<DialogueEvent>> {
+        let Some(journal) = self.journal.take() else {
+            return Ok(Vec::new());
+        };
+        let restored_variables = journal.rollback(self.variable_storage.as_mut())?;
+        let events = restored_variables
+            .into_iter()
+            .filter_map(|restored_variable| {
+                let name = restored_variable.name;
+                // Variables that are no longer stored fall back to their initial value
+                let old = restored_variable
+                    .rolled_back_value
+                    .or_else(|| self.initial_value(&name));
+                let new = restored_variable
+                    .restored_value
+                    .or_else(|| self.initial_value(&name))?;
+                (old.as_ref() != Some(&new)).then_some(DialogueEvent::VariableChanged {
+                    name,
+                    old,
+                    new,
+                })
+            })
+            .collect();
+        Ok(events)
+    }
+
+    pub(crate) fn rewind_depth(&self) -> usize {
+        self.rewind_depth
+    }
+
+    /// Sets how many delivered lines are remembered for [`VirtualMachine::rewind`], forgetting the oldest ones if there are more.
+    pub(crate) fn set_rewind_depth(&mut self, rewind_depth: usize) {
+        self.rewind_depth = rewind_depth;
+        let excess = self.rewind_points.len().saturating_sub(rewind_depth);
+        self.rewind_points.drain(..excess);
+    }
+
+    pub(crate) fn rewindable_lines(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &Line> + ExactSizeIterator {
+        self.rewind_points
+            .iter()
+            .map(|rewind_point| &rewind_point.line)
+    }
+
+    pub(crate) fn clear_rewind_points(&mut self) {
+        self.rewind_points.clear();
+    }
+
+    /// Remembers the current state as the one the given line was delivered in.
+    fn record_rewind_point(&mut self, line: &Line) {
+        if self.rewind_depth == 0 {
+            return;
+        }
+        if self.rewind_points.len() >= self.rewind_depth {
+            self.rewind_points.pop_front();
+        }
+        self.rewind_points.push_back(RewindPoint {
+            line: line.clone(),
+            current_node_name: self.current_node_name.clone(),
+            state: self.state.clone(),
+            random_state: self.rng.state(),
+            variables: non_local_variables(self.variable_storage.as_ref()),
+        });
+    }
+
+    /// Returns to the state in which the line `steps` lines before the last delivered one was delivered, forgetting all lines after it.
+    /// Returns a [`DialogueEvent::VariableChanged`] for every variable whose value changed back, followed by the line in the current language.
+    pub(crate) fn rewind(&mut self, steps: usize) -> Result<Vec<DialogueEvent>> {
+        let recorded_lines = self.rewind_points.len();
+        if steps >= recorded_lines {
+            return Err(DialogueError::RewindOutOfRange {
+                steps,
+                recorded_lines,
+            });
+        }
+        let rewind_point = self.rewind_points[recorded_lines - 1 - steps].clone();
+        let current_node = rewind_point
+            .current_node_name
+            .as_deref()
+            .map(|node_name| self.get_node_from_name(node_name).cloned())
+            .transpose()?;
+        self.rewind_points.truncate(recorded_lines - steps);
+
+        let mut events = self.restore_variables(rewind_point.variables)?;
+        self.current_node = current_node;
+        self.current_node_name = rewind_point.current_node_name;
+        self.state = rewind_point.state;
+        self.set_execution_state(ExecutionState::WaitingForContinue);
+        self.batched_events.clear();
+        self.rng.set_state(rewind_point.random_state);
+        self.sync_local_variables()?;
+
+        let line = rewind_point.line;
+        let values = line.substitution_values();
+        let line = self.prepare_line(line.id, &values)?;
+        events.push(DialogueEvent::Line(line));
+        Ok(events)
+    }
+
+    /// Stores the given values and removes all other variables except for local ones.
+    /// Returns a [`DialogueEvent::VariableChanged`] for every variable whose value changed.
+    fn restore_variables(
+        &mut self,
+        variables: HashMap<String, YarnValue>,
+    ) -> Result<Vec<DialogueEvent>> {
+        let mut events = Vec::new();
+        let current_variables = non_local_variables(self.variable_storage.as_ref());
+        for (name, old) in &current_variables {
+            if variables.contains_key(name) {
+                continue;
+            }
+            if let Some(journal) = self.journal.as_mut() {
+                journal.record(self.variable_storage.as_ref(), name);
+            }
+            self.variable_storage.remove(name)?;
+            // Variables that are no longer stored fall back to their initial value
+            if let Some(new) = self.initial_value(name)
+                && new != *old
+            {
+                events.push(DialogueEvent::VariableChanged {
+                    name: name.clone(),
+                    old: Some(old.clone()),
+                    new,
+                });
+            }
+        }
+        for (name, value) in variables {
+            if current_variables.get(&name) != Some(&value) {
+                events.extend(self.set_variable(name, value)?);
+            }
+        }
+        Ok(events)
+    }
+
     pub(crate) fn set_language_code(&mut self, language_code: impl Into<Option<Language>>) {
         let language_code = language_code.into();
         self.language_code.clone_from(&language_code);
@@ -78,28 +294,80 @@ impl VirtualMachine {
     pub(crate) fn reset_state(&mut self) {
         self.state = State::default();
         self.current_node_name = None;
+        // Local variables end with the node they were introduced in
+        self.variable_storage.clear_scope(VariableScope::Local);
     }
 
     pub(crate) fn set_execution_state(&mut self, execution_state: ExecutionState) -> &mut Self {
         self.execution_state = execution_state;
         if execution_state == ExecutionState::Stopped {
-            self.reset_state()
+            self.reset_state();
+            self.variable_storage
+                .clear_scope(VariableScope::Conversation);
         }
         self
     }
 
+    /// Introduces or updates a variable that only exists in the current node.
+    /// Unlike [`VirtualMachine::set_variable`], this never emits a [`DialogueEvent::VariableChanged`].
+    fn set_local_variable(&mut self, name: String, value: InternalValue) -> Result<()> {
+        self.variable_storage.set_in_scope(
+            name.clone(),
+            value.clone().into(),
+            VariableScope::Local,
+        )?;
+        self.state.local_variables.insert(name, value);
+        Ok(())
+    }
+
+    /// Replaces the local variables in the [`VariableStorage`] with the ones of the current node,
+    /// e.g. after returning from a detour.
+    fn sync_local_variables(&mut self) -> Result<()> {
+        self.variable_storage.clear_scope(VariableScope::Local);
+        for (name, value) in &self.state.local_variables {
+            self.variable_storage.set_in_scope(
+                name.clone(),
+                value.clone().into(),
+                VariableScope::Local,
+            )?;
+        }
+        Ok(())
+    }
+
     /// # Implementation Notes
     /// The original does not reset the state upon calling this. I suspect that's a bug.
     pub(crate) fn stop(&mut self) -> Vec<DialogueEvent> {
+        if self.rollback_on_stop {
+            match self.rollback_transaction() {
+                Ok(events) => self.batched_events.extend(events),
+                Err(e) => error!("Failed to roll back variables when stopping the dialogue: {e}"),
+            }
+        }
         self.set_execution_state(ExecutionState::Stopped);
         self.batched_events.push(DialogueEvent::DialogueComplete);
         core::mem::take(&mut self.batched_events)
     }
 
+    /// Stops the dialogue because it reached its end, as opposed to being stopped with [`VirtualMachine::stop`].
+    fn complete(&mut self) {
+        self.set_execution_state(ExecutionState::Stopped);
+        self.batched_events.push(DialogueEvent::DialogueComplete);
+        if self
+            .journal
+            .as_ref()
+            .is_some_and(|journal| journal.is_automatic)
+        {
+            self.commit_transaction();
+        }
+    }
+
     pub(crate) fn set_node(&mut self, node_name: impl Into<String>) -> Result<()> {
         let node_name = node_name.into();
         debug!("Loading node \"{node_name}\"");
         let current_node = self.get_node_from_name(&node_name)?;
+        if current_node.is_hidden() {
+            return Err(DialogueError::InvalidNode { node_name });
+        }
         self.current_node = Some(current_node.clone());
 
         self.reset_state();
@@ -116,33 +384,14 @@ impl VirtualMachine {
     }
 
     fn send_line_hints(&mut self) {
-        // Create a list; we will never have more lines and options
-        // than total instructions, so that's a decent capacity for
-        // the list
-        // [sic] TODO: maybe this list could be reused to save on allocations?
-
-        let string_ids: Vec<_> = self
-            .current_node
-            .as_ref()
-            .unwrap()
-            .instructions
-            .iter()
-            // Loop over every instruction and find the ones that run a
-            // line or add an option; these are the two instructions
-            // that will signal a line can appear to the player
-            .filter_map(|instruction| {
-                let opcode: OpCode = instruction.opcode.try_into().unwrap();
-                [OpCode::RunLine, OpCode::AddOption]
-                    .contains(&opcode)
-                    .then(|| {
-                        // Both RunLine and AddOption have the string ID
-                        // they want to show as their first operand, so
-                        // store that
-                        let id: String = instruction.operands[0].clone().try_into().unwrap();
-                        LineId(id)
-                    })
-            })
-            .collect();
+        // ## Implementation note
+        // The original only hints at the lines of the current node.
+        // We also hint at the lines of the nodes it jumps to, up to `line_hints_lookahead` jumps away.
+        let string_ids = line_hints(
+            self.program.as_ref().unwrap(),
+            self.current_node.as_ref().unwrap(),
+            self.line_hints_lookahead,
+        );
         self.text_provider.accept_line_hints(&string_ids);
         self.batched_events
             .push(DialogueEvent::LineHints(string_ids));
@@ -164,11 +413,6 @@ impl VirtualMachine {
             .program
             .as_ref()
             .ok_or_else(|| DialogueError::NoProgramLoaded)?;
-        assert!(
-            !program.nodes.is_empty(),
-            "Cannot load node \"{node_name}\": No nodes have been loaded.",
-        );
-
         program
             .nodes
             .get(node_name)
@@ -179,33 +423,91 @@ impl VirtualMachine {
 
     /// Resumes execution.
     pub(crate) fn continue_(
+        &mut self,
+        instruction_fn: impl FnMut(&mut Self, &Instruction) -> crate::Result<()>,
+    ) -> crate::Result<Vec<DialogueEvent>> {
+        self.continue_until(instruction_fn, |_, _| false)
+    }
+
+    /// Resumes execution like [`VirtualMachine::continue_`], but calls `should_pause` with the current node and program counter
+    /// before every instruction. If it returns `true`, execution pauses before that instruction as if content had been delivered.
+    pub(crate) fn continue_until(
         &mut self,
         mut instruction_fn: impl FnMut(&mut Self, &Instruction) -> crate::Result<()>,
+        mut should_pause: impl FnMut(&Node, usize) -> bool,
     ) -> crate::Result<Vec<DialogueEvent>> {
         self.assert_can_continue()?;
         self.set_execution_state(ExecutionState::Running);
 
+        let mut executed_instruction_count = 0;
+        let mut visited_nodes: Vec<String> = Vec::new();
         while self.execution_state == ExecutionState::Running {
             let current_node = self.current_node.clone().unwrap();
-            let current_instruction = &current_node.instructions[self.state.program_counter];
-            instruction_fn(self, current_instruction)?;
-            // ## Implementation note
-            // The original increments the program counter here, but that leads to intentional underflow on [`OpCode::RunNode`],
-            // so we do the incrementation in [`VirtualMachine::run_instruction`] instead.
-
-            if self.state.program_counter < current_node.instructions.len() {
-                continue;
+            if should_pause(&current_node, self.state.program_counter) {
+                self.set_execution_state(ExecutionState::WaitingForContinue);
+                break;
+            }
+            if let Some(instruction_budget) = self.instruction_budget {
+                if visited_nodes.last() != Some(&current_node.name) {
+                    if visited_nodes.len() >= MAX_TRACKED_NODES {
+                        visited_nodes.drain(..MAX_TRACKED_NODES / 2);
+                    }
+                    visited_nodes.push(current_node.name.clone());
+                }
+                if executed_instruction_count >= instruction_budget {
+                    return Err(DialogueError::InstructionBudgetExceeded {
+                        node_name: current_node.name.clone(),
+                        instruction_budget,
+                        jump_cycle: jump_cycle(&visited_nodes),
+                    });
+                }
+                executed_instruction_count += 1;
+            }
+            if let Some(current_instruction) =
+                current_node.instructions.get(self.state.program_counter)
+            {
+                instruction_fn(self, current_instruction)?;
+                // ## Implementation note
+                // The original increments the program counter here, but that leads to intentional underflow on [`OpCode::RunNode`],
+                // so we do the incrementation in [`VirtualMachine::run_instruction`] instead.
+
+                // Jumps, detours and returns change the current node, so we cannot rely on `current_node` here
+                let instruction_count = self
+                    .current_node
+                    .as_ref()
+                    .map_or(0, |node| node.instructions.len());
+                if self.state.program_counter < instruction_count {
+                    continue;
+                }
             }
 
-            self.batched_events
-                .push(DialogueEvent::NodeComplete(current_node.name.clone()));
-            self.set_execution_state(ExecutionState::Stopped);
-            self.batched_events.push(DialogueEvent::DialogueComplete);
-            debug!("Run complete.");
+            self.return_from_node()?;
         }
         Ok(core::mem::take(&mut self.batched_events))
     }
 
+    /// Completes the current node and continues with the node that detoured to it.
+    /// Stops the dialogue if there is no such node.
+    fn return_from_node(&mut self) -> Result<()> {
+        let current_node_name = self.current_node_name.clone().unwrap();
+        self.batched_events
+            .push(DialogueEvent::NodeComplete(current_node_name));
+
+        let Some(caller) = self.state.call_stack.pop() else {
+            self.complete();
+            debug!("Run complete.");
+            return Ok(());
+        };
+        debug!("Returning to node \"{}\"", caller.node_name);
+        let caller_node = self.get_node_from_name(&caller.node_name)?.clone();
+        self.current_node = Some(caller_node);
+        self.current_node_name = Some(caller.node_name);
+        self.state.program_counter = caller.program_counter;
+        self.state.stack = caller.stack;
+        self.state.local_variables = caller.local_variables;
+        self.sync_local_variables()
+    }
+
     pub(crate) fn parse_markup(&mut self, line: &str) -> crate::markup::Result<ParsedMarkup> {
         self.line_parser.parse_markup(line)
     }
@@ -224,10 +526,64 @@ impl VirtualMachine {
     }
 
     pub(crate) fn unload_programs(&mut self) {
-        self.program = None
+        self.program = None;
+        self.clear_rewind_points();
+    }
+
+    /// Replaces the program while keeping the current position, see [`Dialogue::hot_swap_program`].
+    pub(crate) fn hot_swap_program(&mut self, program: Program) -> Result<Vec<DialogueEvent>> {
+        let Some(node_name) = self.current_node_name.clone() else {
+            self.program.replace(program);
+            self.clear_rewind_points();
+            return Ok(Vec::new());
+        };
+        let is_waiting_for_option_selection = self.is_waiting_for_option_selection();
+        let old_program = self.program.replace(program).unwrap_or_default();
+        self.clear_rewind_points();
+        let new_program = self.program.as_ref().unwrap();
+        let Some(new_node) = new_program.nodes.get(&node_name).cloned() else {
+            let reason = format!("The node \"{node_name}\" no longer exists.");
+            warn!("Stopping the dialogue after hot swapping the program: {reason}");
+            self.current_node = None;
+            self.set_execution_state(ExecutionState::Stopped);
+            return Ok(vec![
+                DialogueEvent::HotSwapFallback { node_name, reason },
+                DialogueEvent::DialogueComplete,
+            ]);
+        };
+        match map_state(
+            &old_program,
+            new_program,
+            &node_name,
+            &self.state,
+            is_waiting_for_option_selection,
+        ) {
+            Ok(state) => {
+                let have_options_changed = state.current_options != self.state.current_options;
+                self.state = state;
+                self.current_node = Some(new_node);
+                Ok(if have_options_changed {
+                    vec![DialogueEvent::Options(self.state.current_options.clone())]
+                } else {
+                    Vec::new()
+                })
+            }
+            Err(reason) => {
+                warn!("Restarting node \"{node_name}\" after hot swapping the program: {reason}");
+                self.set_node(node_name.clone())?;
+                if is_waiting_for_option_selection {
+                    self.set_execution_state(ExecutionState::WaitingForContinue);
+                }
+                Ok(vec![DialogueEvent::HotSwapFallback { node_name, reason }])
+            }
+        }
     }
 
-    pub(crate) fn set_selected_option(&mut self, selected_option_id: OptionId) -> Result<()> {
+    /// Returns the selected option.
+    pub(crate) fn set_selected_option(
+        &mut self,
+        selected_option_id: OptionId,
+    ) -> Result<DialogueOption> {
         if self.execution_state != ExecutionState::WaitingOnOptionSelection {
             return Err(DialogueError::UnexpectedOptionSelectionError);
         }
@@ -240,10 +596,8 @@ impl VirtualMachine {
 
         // We now know what number option was selected; push the
         // corresponding node name to the stack.
-        let destination_node = self.state.current_options[selected_option_id.0]
-            .destination_node
-            .clone();
-        self.state.push(destination_node);
+        let selected_option = self.state.current_options[selected_option_id.0].clone();
+        self.state.push(selected_option.destination_node.clone());
 
         // We no longer need the accumulated list of options; clear it
         // so that it's ready for the next one
@@ -251,7 +605,7 @@ impl VirtualMachine {
 
         // We're no longer in the WaitingForOptions state; we are now waiting for our game to let us continue
         self.set_execution_state(ExecutionState::WaitingForContinue);
-        Ok(())
+        Ok(selected_option)
     }
 
     pub(crate) fn is_active(&self) -> bool {
@@ -266,31 +620,228 @@ impl VirtualMachine {
         self.current_node_name.clone()
     }
 
+    /// The index of the next instruction that will be run in the current node.
+    pub(crate) fn program_counter(&self) -> Option<usize> {
+        self.current_node
+            .is_some()
+            .then_some(self.state.program_counter)
+    }
+
+    pub(crate) fn value_stack(&self) -> Vec<YarnValue> {
+        self.state
+            .stack
+            .iter()
+            .map(|value| value.clone().into())
+            .collect()
+    }
+
+    pub(crate) fn current_options(&self) -> &[DialogueOption] {
+        &self.state.current_options
+    }
+
+    pub(crate) fn call_stack(&self) -> Vec<String> {
+        self.state
+            .call_stack
+            .iter()
+            .map(|caller| caller.node_name.clone())
+            .collect()
+    }
+
+    pub(crate) fn snapshot(&self) -> Result<DialogueSnapshot> {
+        let program = self
+            .program
+            .as_ref()
+            .ok_or(DialogueError::NoProgramLoaded)?;
+        Ok(DialogueSnapshot {
+            version: DialogueSnapshot::CURRENT_VERSION,
+            program_checksum: program.checksum(),
+            current_node_name: self.current_node_name.clone(),
+            execution_state: self.execution_state,
+            state: self.state.clone(),
+            language_code: self.language_code.clone(),
+            random_state: self.rng.state(),
+        })
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: DialogueSnapshot) -> Result<()> {
+        if snapshot.version != DialogueSnapshot::CURRENT_VERSION {
+            return Err(DialogueError::UnsupportedSnapshotVersion {
+                version: snapshot.version,
+                supported_version: DialogueSnapshot::CURRENT_VERSION,
+            });
+        }
+        let program = self
+            .program
+            .as_ref()
+            .ok_or(DialogueError::NoProgramLoaded)?;
+        let program_checksum = program.checksum();
+        if snapshot.program_checksum != program_checksum {
+            return Err(DialogueError::SnapshotProgramMismatch {
+                snapshot_checksum: snapshot.program_checksum,
+                program_checksum,
+            });
+        }
+        let current_node = snapshot
+            .current_node_name
+            .as_deref()
+            .map(|node_name| self.get_node_from_name(node_name).cloned())
+            .transpose()?;
+        for caller in &snapshot.state.call_stack {
+            self.get_node_from_name(&caller.node_name)?;
+        }
+
+        self.current_node = current_node;
+        self.current_node_name = snapshot.current_node_name;
+        self.state = snapshot.state;
+        self.execution_state = snapshot.execution_state;
+        self.batched_events.clear();
+        self.clear_rewind_points();
+        self.rng.set_state(snapshot.random_state);
+        self.set_language_code(snapshot.language_code);
+        self.sync_local_variables()
+    }
+
+    /// Evaluates the conditions of the nodes in the node group `node_group_name` without running any of them
+    /// and returns the nodes whose conditions currently pass.
+    pub(crate) fn saliency_candidates_for_node_group(
+        &mut self,
+        node_group_name: &str,
+        function_call_fn: impl FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> FunctionResult,
+    ) -> Result<Vec<SaliencyCandidate>> {
+        let node = self.get_node_from_name(node_group_name)?.clone();
+        if !node.is_node_group_hub() {
+            return Err(DialogueError::NotANodeGroup {
+                node_name: node_group_name.to_owned(),
+            });
+        }
+        // The generated code only evaluates conditions before it selects a candidate,
+        // so we run it up to that point on a scratch state. The conditions may still call `random` and friends,
+        // so the random state is restored as well to keep later random results the same.
+        let state = core::mem::take(&mut self.state);
+        let current_node = self.current_node.replace(node.clone());
+        let current_node_name = self.current_node_name.replace(node_group_name.to_owned());
+        let event_count = self.batched_events.len();
+        let random_state = self.rng.state();
+
+        let result = self.run_until_saliency_selection(&node, function_call_fn);
+        let candidates = core::mem::replace(&mut self.state, state).saliency_candidates;
+
+        self.current_node = current_node;
+        self.current_node_name = current_node_name;
+        self.batched_events.truncate(event_count);
+        self.rng.set_state(random_state);
+        result.map(|_| candidates)
+    }
+
+    /// Returns `true` if the loaded program contains a smart variable with the given name.
+    pub(crate) fn is_smart_variable(&self, variable_name: &str) -> bool {
+        self.program
+            .as_ref()
+            .and_then(|program| program.nodes.get(variable_name))
+            .is_some_and(Node::is_smart_variable)
+    }
+
+    /// Evaluates the expression of a smart variable by running the node the compiler generated for it on a scratch state.
+    ///
     /// ## Implementation note
     ///
-    /// Increments the program counter here instead of in `continue_` for cleaner code
+    /// Takes the function call closure as a trait object, since [`VirtualMachine::run_instruction`] calls this method
+    /// when it encounters a smart variable, which would otherwise need an infinite number of monomorphizations.
+    pub(crate) fn evaluate_smart_variable(
+        &mut self,
+        variable_name: &str,
+        function_call_fn: &mut dyn FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> FunctionResult,
+    ) -> Result<YarnValue> {
+        if !self.is_smart_variable(variable_name) {
+            return Err(DialogueError::NotASmartVariable {
+                variable_name: variable_name.to_owned(),
+            });
+        }
+        let node = self.get_node_from_name(variable_name)?.clone();
+        let state = core::mem::take(&mut self.state);
+        let current_node = self.current_node.replace(node.clone());
+        let current_node_name = self.current_node_name.replace(variable_name.to_owned());
+
+        let result = self.run_to_end(&node, function_call_fn);
+        let mut smart_variable_state = core::mem::replace(&mut self.state, state);
+
+        self.current_node = current_node;
+        self.current_node_name = current_node_name;
+        result?;
+        smart_variable_state
+            .pop_value()
+            .map(Into::into)
+            .map_err(|error| error.at(variable_name.to_owned(), node.instructions.len()))
+    }
+
+    fn run_to_end(
+        &mut self,
+        node: &Node,
+        function_call_fn: &mut dyn FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> FunctionResult,
+    ) -> Result<()> {
+        while let Some(instruction) = node.instructions.get(self.state.program_counter) {
+            self.run_instruction(instruction, &mut *function_call_fn)?;
+        }
+        Ok(())
+    }
+
+    fn run_until_saliency_selection(
+        &mut self,
+        node: &Node,
+        mut function_call_fn: impl FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> FunctionResult,
+    ) -> Result<()> {
+        while let Some(instruction) = node.instructions.get(self.state.program_counter) {
+            if instruction.opcode == OpCode::SelectSaliencyCandidate as i32 {
+                break;
+            }
+            self.run_instruction(instruction, &mut function_call_fn)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the instruction at the program counter of the current node.
+    /// Malformed instructions result in an error that contains their location.
     pub(crate) fn run_instruction(
         &mut self,
         instruction: &Instruction,
-        mut function_call_fn: impl FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> YarnValue,
+        function_call_fn: impl FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> FunctionResult,
     ) -> crate::Result<()> {
-        let opcode: OpCode = instruction.opcode.try_into().unwrap();
+        let instruction_index = self.state.program_counter;
+        self.execute_instruction(instruction, function_call_fn)
+            .map_err(|error| {
+                let node_name = self.current_node_name.clone().unwrap_or_default();
+                error.at(node_name, instruction_index)
+            })
+    }
+
+    /// ## Implementation note
+    ///
+    /// Increments the program counter here instead of in `continue_` for cleaner code
+    fn execute_instruction(
+        &mut self,
+        instruction: &Instruction,
+        mut function_call_fn: impl FnMut(&dyn UntypedYarnFn, Vec<YarnValue>) -> FunctionResult,
+    ) -> InstructionResult<()> {
+        let opcode =
+            OpCode::try_from(instruction.opcode).map_err(|_| InstructionError::UnknownOpCode {
+                opcode: instruction.opcode,
+            })?;
         match opcode {
             OpCode::JumpTo => {
                 // Jumps to a named label
-                let label_name: String = instruction.read_operand(0);
-                self.state.program_counter = self.find_instruction_point_for_label(&label_name);
+                let label_name: String = read_operand(instruction, 0)?;
+                self.state.program_counter = self.find_instruction_point_for_label(&label_name)?;
             }
             OpCode::Jump => {
                 // Jumps to a label whose name is on the stack.
-                let jump_destination: String = self.state.peek();
+                let jump_destination: String = self.state.peek()?;
                 self.state.program_counter =
-                    self.find_instruction_point_for_label(&jump_destination);
+                    self.find_instruction_point_for_label(&jump_destination)?;
             }
             OpCode::RunLine => {
                 // Looks up a string from the string table and passes it to the client as a line
 
-                let string_id: String = instruction.read_operand(0);
+                let string_id: String = read_operand(instruction, 0)?;
                 let string_id: LineId = string_id.into();
 
                 // The second operand, if provided (compilers prior
@@ -298,12 +849,10 @@ impl VirtualMachine {
                 // of expressions in the line. We need to pop these
                 // values off the stack and deliver them to the
                 // line handler.
-                assert_up_to_date_compiler(instruction.operands.len() >= 2);
+                require_operand_count(instruction, 2)?;
 
-                let substitutions = self.pop_substitutions_with_count_at_operand(instruction, 1);
-                let line = self.prepare_line(string_id, &substitutions)?;
-
-                self.batched_events.push(DialogueEvent::Line(line));
+                let values = self.pop_substitutions_with_count_at_operand(instruction, 1)?;
+                let line = self.prepare_line(string_id, &values)?;
 
                 // Implementation note:
                 // In the original, this is only done if `execution_state` is still `DeliveringContent`,
@@ -312,13 +861,16 @@ impl VirtualMachine {
                 // called `continue_` themselves outside of the line handler.
                 self.set_execution_state(ExecutionState::WaitingForContinue);
                 self.state.program_counter += 1;
+
+                self.record_rewind_point(&line);
+                self.batched_events.push(DialogueEvent::Line(line));
             }
             OpCode::RunCommand => {
                 // Passes a string to the client as a custom command
-                let command_text: String = instruction.read_operand(0);
-                assert_up_to_date_compiler(instruction.operands.len() >= 2);
+                let command_text: String = read_operand(instruction, 0)?;
+                require_operand_count(instruction, 2)?;
                 let command_text = self
-                    .pop_substitutions_with_count_at_operand(instruction, 1)
+                    .pop_substitutions_with_count_at_operand::<String>(instruction, 1)?
                     .into_iter()
                     .enumerate()
                     .fold(command_text, |command_text, (i, substitution)| {
@@ -338,29 +890,29 @@ impl VirtualMachine {
             }
             OpCode::AddOption => {
                 // Add an option to the current state
-                let string_id: String = instruction.read_operand(0);
+                let string_id: String = read_operand(instruction, 0)?;
                 let string_id: LineId = string_id.into();
-                assert_up_to_date_compiler(instruction.operands.len() >= 4);
-                let substitutions = self.pop_substitutions_with_count_at_operand(instruction, 2);
-                let line = self.prepare_line(string_id, &substitutions)?;
+                require_operand_count(instruction, 4)?;
+                let values = self.pop_substitutions_with_count_at_operand(instruction, 2)?;
+                let line = self.prepare_line(string_id, &values)?;
 
                 // Indicates whether the VM believes that the
                 // option should be shown to the user, based on any
                 // conditions that were attached to the option.
-                let line_condition_passed = if instruction.read_operand(3) {
+                let line_condition_passed = if read_operand(instruction, 3)? {
                     // The fourth operand is a bool that indicates
                     // whether this option had a condition or not.
                     // If it does, then a bool value will exist on
                     // the stack indicating whether the condition
                     // passed or not. We pass that information to
                     // the game.
-                    self.state.pop()
+                    self.state.pop()?
                 } else {
                     true
                 };
 
                 let index = self.state.current_options.len();
-                let node_name = instruction.read_operand(1);
+                let node_name = read_operand(instruction, 1)?;
                 // ## Implementation note:
                 // The original calculates the ID in the `ShowOptions` opcode,
                 // but this way is cleaner because it allows us to store a `DialogueOption` instead of a bunch of values in a big tuple.
@@ -397,34 +949,35 @@ impl VirtualMachine {
             }
             OpCode::PushString => {
                 // Pushes a string value onto the stack. The operand is an index into the string table, so that's looked up first.
-                let string_table_index: String = instruction.read_operand(0);
+                let string_table_index: String = read_operand(instruction, 0)?;
                 self.state.push(string_table_index);
                 self.state.program_counter += 1;
             }
             OpCode::PushFloat => {
                 // Pushes a floating point onto the stack.
-                let float: f32 = instruction.read_operand(0);
+                let float: f32 = read_operand(instruction, 0)?;
                 self.state.push(float);
                 self.state.program_counter += 1;
             }
             OpCode::PushBool => {
                 // Pushes a boolean value onto the stack.
-                let boolean: bool = instruction.read_operand(0);
+                let boolean: bool = read_operand(instruction, 0)?;
                 self.state.push(boolean);
                 self.state.program_counter += 1;
             }
 
             OpCode::PushNull => {
-                panic!(
-                    "PushNull is no longer valid op code, because null is no longer a valid value from Yarn Spinner 2.0 onwards. To fix this error, re-compile the original source code."
-                );
+                // PushNull is no longer a valid op code, because null is no longer a valid value from Yarn Spinner 2.0 onwards.
+                return Err(InstructionError::UnknownOpCode {
+                    opcode: instruction.opcode,
+                });
             }
             OpCode::JumpIfFalse => {
                 // Jumps to a named label if the value on the top of the stack evaluates to the boolean value 'false'.
-                let is_top_value_true: bool = self.state.peek();
+                let is_top_value_true: bool = self.state.peek()?;
                 if !is_top_value_true {
-                    let label_name: String = instruction.read_operand(0);
-                    let instruction_point = self.find_instruction_point_for_label(&label_name);
+                    let label_name: String = read_operand(instruction, 0)?;
+                    let instruction_point = self.find_instruction_point_for_label(&label_name)?;
                     self.state.program_counter = instruction_point;
                 } else {
                     self.state.program_counter += 1;
@@ -432,23 +985,23 @@ impl VirtualMachine {
             }
             OpCode::Pop => {
                 // Pops a value from the stack.
-                self.state.pop_value();
+                self.state.pop_value()?;
                 self.state.program_counter += 1;
             }
             OpCode::CallFunc => {
-                let actual_parameter_count: usize = self.state.pop();
+                let actual_parameter_count: usize = self.state.pop()?;
                 // Get the parameters, which were pushed in reverse
                 let parameters = {
                     let mut parameters: Vec<_> = (0..actual_parameter_count)
                         .rev()
-                        .map(|_| self.state.pop_value().raw_value)
-                        .collect();
+                        .map(|_| self.state.pop_value().map(|value| value.raw_value))
+                        .collect::<InstructionResult<_>>()?;
                     parameters.reverse();
                     parameters
                 };
 
                 // Call a function, whose parameters are expected to be on the stack. Pushes the function's return value, if it returns one.
-                let function_name: String = instruction.read_operand(0);
+                let function_name: String = read_operand(instruction, 0)?;
                 let function =
                     self.library
                         .get(&function_name)
@@ -461,17 +1014,34 @@ impl VirtualMachine {
                 // actually passed at the top of the stack.
                 let expected_parameter_count = function.parameter_types().len();
 
-                assert_eq!(
-                    expected_parameter_count, actual_parameter_count,
-                    "Function {function_name} expected {expected_parameter_count} parameters, but received {actual_parameter_count}",
-                );
+                if expected_parameter_count != actual_parameter_count {
+                    return Err(InstructionError::FunctionParameterCountMismatch {
+                        function_name,
+                        expected_parameter_count,
+                        actual_parameter_count,
+                    });
+                }
+                // Calling a function with arguments it can't convert would panic
+                self.library
+                    .check_arguments(&function_name, &parameters)
+                    .map_err(|e| InstructionError::TypeConversionFailed {
+                        message: format!("Invalid argument for function {function_name}: {e}"),
+                    })?;
+                let return_type = function.return_type().try_into().map_err(|e| {
+                    InstructionError::TypeConversionFailed {
+                        message: format!(
+                            "Failed to get Yarn type for return type id of function {function_name}: {e}"
+                        ),
+                    }
+                })?;
 
                 // Invoke the function
-                let return_value = function_call_fn(function, parameters);
-                let return_type = function
-                    .return_type()
-                    .try_into()
-                    .unwrap_or_else(|e| panic!("Failed to get Yarn type for return type id of function {function_name}: {e:?}"));
+                let return_value = function_call_fn(function, parameters).map_err(|message| {
+                    InstructionError::FunctionFailed {
+                        function_name,
+                        message,
+                    }
+                })?;
                 let typed_return_value = InternalValue {
                     raw_value: return_value,
                     r#type: return_type,
@@ -484,50 +1054,84 @@ impl VirtualMachine {
             }
             OpCode::PushVariable => {
                 // Get the contents of a variable, push that onto the stack.
-                let variable_name: String = instruction.read_operand(0);
-                let loaded_value = self
-                    .variable_storage
-                    .get(&variable_name)
-                    .or_else(|e| {
-                        if let VariableStorageError::VariableNotFound { .. } = e {
-                            // We don't have a value for this. The initial
-                            // value may be found in the program. (If it's
-                            // not, then the variable's value is undefined,
-                            // which isn't allowed.)
-                            let initial_value = self
-                                .program
-                                .as_ref()
-                                .unwrap()
-                                .initial_values
-                                .get(&variable_name)
-                                .unwrap_or_else(|| panic!("The loaded program does not contain an initial value for the variable {variable_name}"))
-                                .clone();
-
-                            // Store the initial value in the variable_storage
-                            self.variable_storage.set(variable_name.clone(), initial_value.clone().into())?;
-
-                            Ok(initial_value.into())
-                        } else {
-                            Err(e)
+                let variable_name: String = read_operand(instruction, 0)?;
+                if let Some(value) = self.state.local_variables.get(&variable_name) {
+                    // Local variables shadow stored ones
+                    self.state.push(value.clone());
+                    self.state.program_counter += 1;
+                    return Ok(());
+                }
+                if self.is_smart_variable(&variable_name) {
+                    // Smart variables are not stored, but computed from their expression
+                    let value =
+                        self.evaluate_smart_variable(&variable_name, &mut function_call_fn)?;
+                    self.state.push(value);
+                    self.state.program_counter += 1;
+                    return Ok(());
+                }
+                let loaded_value = self.variable_storage.get(&variable_name).or_else(|e| {
+                    if let VariableStorageError::VariableNotFound { .. } = e {
+                        // We don't have a value for this. The initial
+                        // value may be found in the program. (If it's
+                        // not, then the variable's value is undefined,
+                        // which isn't allowed.)
+                        let Some(initial_value) = self
+                            .program
+                            .as_ref()
+                            .and_then(|program| program.initial_values.get(&variable_name))
+                            .cloned()
+                        else {
+                            return Err(e);
+                        };
+
+                        // Store the initial value in the variable_storage
+                        if let Some(journal) = self.journal.as_mut() {
+                            journal.record(self.variable_storage.as_ref(), &variable_name);
                         }
-                    })?;
+                        self.variable_storage
+                            .set(variable_name.clone(), initial_value.clone().into())?;
+
+                        Ok(initial_value.into())
+                    } else {
+                        Err(e)
+                    }
+                })?;
                 self.state.push(loaded_value);
                 self.state.program_counter += 1;
             }
             OpCode::StoreVariable => {
                 // Store the top value on the stack in a variable.
-                let top_value = self.state.peek_value().clone();
-                let variable_name: String = instruction.read_operand(0);
-                self.variable_storage.set(variable_name, top_value.into())?;
+                let top_value = self.state.peek_value()?.clone();
+                let variable_name: String = read_operand(instruction, 0)?;
+                if self.state.local_variables.contains_key(&variable_name) {
+                    self.set_local_variable(variable_name, top_value)?;
+                } else if let Some(event) = self.set_variable(variable_name, top_value.into())? {
+                    self.batched_events.push(event);
+                }
+                self.state.program_counter += 1;
+            }
+            OpCode::StoreLocalVariable => {
+                // Store the top value on the stack in a variable that only exists in the current node.
+                let top_value = self.state.peek_value()?.clone();
+                let variable_name: String = read_operand(instruction, 0)?;
+                self.set_local_variable(variable_name, top_value)?;
                 self.state.program_counter += 1;
             }
             OpCode::Stop => {
                 // Immediately stop execution, and report that fact.
+                // This also leaves every node that is waiting for a detour to return.
                 let current_node_name = self.current_node_name.clone().unwrap();
-                self.batched_events
-                    .push(DialogueEvent::NodeComplete(current_node_name));
-                self.batched_events.push(DialogueEvent::DialogueComplete);
-                self.set_execution_state(ExecutionState::Stopped);
+                let caller_names = self
+                    .state
+                    .call_stack
+                    .iter()
+                    .rev()
+                    .map(|caller| caller.node_name.clone());
+                let node_complete_events = core::iter::once(current_node_name)
+                    .chain(caller_names)
+                    .map(DialogueEvent::NodeComplete);
+                self.batched_events.extend(node_complete_events);
+                self.complete();
 
                 self.state.program_counter += 1;
             }
@@ -536,25 +1140,82 @@ impl VirtualMachine {
 
                 // Pop a string from the stack, and jump to a node
                 // with that name.
-                let node_name: String = self.state.pop();
+                let node_name: String = self.state.pop()?;
+                let current_node_name = self.current_node_name.clone().unwrap();
                 self.batched_events
-                    .push(DialogueEvent::NodeComplete(node_name.clone()));
+                    .push(DialogueEvent::NodeComplete(current_node_name));
+
+                // A node reached by jumping out of a detour returns to the same caller
+                let call_stack = core::mem::take(&mut self.state.call_stack);
                 self.set_node(&node_name)?;
+                self.state.call_stack = call_stack;
 
                 // No need to increment the program counter, since otherwise we'd skip the first instruction
             }
+            OpCode::DetourToNode => {
+                // Pop a string from the stack, and run the node with that name
+                // until it returns here.
+                let node_name: String = self.state.pop()?;
+                let caller = CallStackFrame {
+                    node_name: self.current_node_name.clone().unwrap(),
+                    program_counter: self.state.program_counter + 1,
+                    stack: core::mem::take(&mut self.state.stack),
+                    local_variables: core::mem::take(&mut self.state.local_variables),
+                };
+                let mut call_stack = core::mem::take(&mut self.state.call_stack);
+                call_stack.push(caller);
+                self.set_node(&node_name)?;
+                self.state.call_stack = call_stack;
+
+                // No need to increment the program counter, since otherwise we'd skip the first instruction
+            }
+            OpCode::Return => {
+                self.return_from_node()?;
+            }
+            OpCode::AddSaliencyCandidate => {
+                // Pop the result of the candidate's condition and remember the candidate if it passed
+                let condition_passed: bool = self.state.pop()?;
+                if condition_passed {
+                    self.state.saliency_candidates.push(SaliencyCandidate {
+                        content_id: read_operand(instruction, 0)?,
+                        complexity_score: read_operand(instruction, 1)?,
+                        destination: read_operand(instruction, 2)?,
+                    });
+                }
+                self.state.program_counter += 1;
+            }
+            OpCode::SelectSaliencyCandidate => {
+                // Let the saliency strategy pick one of the candidates and tell the program where to go
+                let candidates = core::mem::take(&mut self.state.saliency_candidates);
+                let selected_candidate = if candidates.is_empty() {
+                    None
+                } else {
+                    self.saliency_strategy
+                        .query_best_content(&candidates)
+                        .cloned()
+                };
+                if let Some(candidate) = selected_candidate {
+                    self.saliency_strategy.content_was_selected(&candidate);
+                    self.state.push(candidate.destination);
+                    self.state.push(true);
+                } else {
+                    self.state.push(false);
+                }
+                self.state.program_counter += 1;
+            }
         }
         Ok(())
     }
 
-    fn prepare_line(&mut self, string_id: LineId, substitutions: &[String]) -> Result<Line> {
+    pub(crate) fn prepare_line(&mut self, string_id: LineId, values: &[YarnValue]) -> Result<Line> {
         let line_text = self.text_provider.get_text(&string_id).ok_or_else(|| {
             DialogueError::LineProviderError {
                 id: string_id.clone(),
                 language_code: self.language_code.clone(),
             }
         })?;
-        let substituted_text = expand_substitutions(&line_text, substitutions);
+        let language = self.language_code.clone().unwrap_or_default();
+        let substituted_text = expand_substitutions(&line_text, values, &language);
         let markup = self
             .parse_markup(&substituted_text)
             .map_err(DialogueError::MarkupParseError)?;
@@ -562,71 +1223,117 @@ impl VirtualMachine {
             id: string_id,
             text: markup.text,
             attributes: markup.attributes,
+            substitutions: values.iter().map(String::from).collect(),
+            values: values.to_vec(),
         };
         Ok(line)
     }
 
     /// Looks up the instruction number for a named label in the current node.
-    ///
-    /// # Panics
-    ///
-    /// Panics in the following cases:
-    /// - The label is not found in the current node
-    /// - The current node is unset
-    /// - The found instruction point is negative
-    fn find_instruction_point_for_label(&self, label_name: &str) -> usize {
+    fn find_instruction_point_for_label(&self, label_name: &str) -> InstructionResult<usize> {
         self.current_node
             .as_ref()
-            .unwrap()
-            .labels
-            .get(label_name)
-            .copied()
-            .unwrap_or_else(|| {
-                panic!(
-                    "Unknown label {label_name} in node {}",
-                    self.current_node_name.as_ref().unwrap()
-                )
+            .and_then(|node| node.labels.get(label_name))
+            .and_then(|&instruction_point| usize::try_from(instruction_point).ok())
+            .ok_or_else(|| InstructionError::UnknownLabel {
+                label_name: label_name.to_owned(),
             })
-            .try_into()
-            .unwrap()
     }
 
-    fn pop_substitutions_with_count_at_operand(
+    fn pop_substitutions_with_count_at_operand<T>(
         &mut self,
         instruction: &Instruction,
         index: usize,
-    ) -> Vec<String> {
-        let expression_count: usize = instruction.operands[index].clone().try_into().unwrap();
+    ) -> InstructionResult<Vec<T>>
+    where
+        T: TryFrom<InternalValue>,
+        <T as TryFrom<InternalValue>>::Error: Display,
+    {
+        let expression_count: usize = read_operand(instruction, index)?;
         let mut values: Vec<_> = (0..expression_count)
             .rev()
             .map(|_| self.state.pop())
-            .collect();
+            .collect::<InstructionResult<_>>()?;
         values.reverse();
-        values
+        Ok(values)
     }
 }
 
-fn assert_up_to_date_compiler(predicate: bool) {
-    assert!(
-        predicate,
-        "The Yarn script provided was compiled using an older compiler. \
-        Please recompile it using the latest version of either Yarn Spinner or Yarn Spinner."
-    )
+fn read_operand<T>(instruction: &Instruction, operand_index: usize) -> InstructionResult<T>
+where
+    T: TryFrom<Operand>,
+{
+    instruction
+        .read_operand(operand_index)
+        .ok_or(InstructionError::InvalidOperand { operand_index })
+}
+
+/// Scripts compiled by an older compiler lack some of the operands we need.
+fn require_operand_count(instruction: &Instruction, count: usize) -> InstructionResult<()> {
+    let operand_count = instruction.operands.len();
+    if operand_count < count {
+        Err(InstructionError::InvalidOperand {
+            operand_index: operand_count,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// How many of the most recently entered nodes [`VirtualMachine::continue_until`] remembers to find the cycle of an infinite loop.
+const MAX_TRACKED_NODES: usize = 64;
+
+/// Returns the nodes entered since the last time the current node was entered, which is the last one in `visited_nodes`.
+/// When the instruction budget runs out, these are the nodes the dialogue is looping through.
+fn jump_cycle(visited_nodes: &[String]) -> Vec<String> {
+    let Some((current_node, previous_nodes)) = visited_nodes.split_last() else {
+        return Vec::new();
+    };
+    let cycle_start = previous_nodes
+        .iter()
+        .rposition(|node_name| node_name == current_node)
+        .map_or(previous_nodes.len(), |index| index + 1);
+    visited_nodes[cycle_start..].to_vec()
 }
 
 /// Replaces all substitution markers in a text with the given substitution list.
 ///
 /// This method replaces substitution markers
-/// (for example, `{0}`) with the corresponding entry in `substitutions`.
-/// If `test` contains a substitution marker whose
-/// index is not present in `substitutions`, it is
+/// (for example, `{0}`) with the corresponding entry in `values`.
+/// If `text` contains a substitution marker whose
+/// index is not present in `values`, it is
 /// ignored.
+///
+/// ## Implementation note
+///
+/// Markers may carry a format specifier, e.g. `{0:N0}`, which formats numbers according to `language`.
+/// See [`NumberFormat`] for the supported specifiers. The original implementation only knows markers without specifiers.
 #[must_use]
-fn expand_substitutions(text: &str, substitutions: &[String]) -> String {
-    substitutions
-        .iter()
-        .enumerate()
-        .fold(text.to_owned(), |text, (i, substitution)| {
-            text.replace(&format!("{{{i}}}",), substitution)
+fn expand_substitutions(text: &str, values: &[YarnValue], language: &Language) -> String {
+    SUBSTITUTION_MARKER
+        .replace_all(text, |captures: &Captures| {
+            let Some(value) = captures[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| values.get(index))
+            else {
+                return captures[0].to_owned();
+            };
+            let Some(format_specifier) = captures.get(2).map(|m| m.as_str()) else {
+                return String::from(value);
+            };
+            let formatted = match value {
+                YarnValue::Number(number) => NumberFormat::parse(format_specifier)
+                    .and_then(|format| format.format(*number, language)),
+                _ => None,
+            };
+            formatted.unwrap_or_else(|| {
+                warn!("Ignoring the format specifier \"{format_specifier}\", which cannot be applied to the value \"{value}\".");
+                String::from(value)
+            })
         })
+        .into_owned()
 }
+
+static SUBSTITUTION_MARKER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{(\d+)(?::([^{}]*))?\}").unwrap());
@@ -0,0 +1,173 @@
+//! Contains the position mapping used by [`Dialogue::hot_swap_program`], which finds the current instruction, options and callers in the new program.
+
+use crate::prelude::*;
+
+/// Maps `state`, which is positioned in the node `node_name` of `old_program`, to the same position in `new_program`.
+/// Returns why the position could not be found if the mapping fails.
+pub(crate) fn map_state(
+    old_program: &Program,
+    new_program: &Program,
+    node_name: &str,
+    state: &State,
+    is_waiting_for_option_selection: bool,
+) -> core::result::Result<State, String> {
+    let (old_node, new_node) = nodes(old_program, new_program, node_name)
+        .ok_or_else(|| format!("The node \"{node_name}\" no longer exists."))?;
+    // Checked before any position is mapped, so that a removed caller is reported as such
+    if let Some(caller) = state
+        .call_stack
+        .iter()
+        .find(|caller| !new_program.nodes.contains_key(&caller.node_name))
+    {
+        return Err(format!(
+            "The node \"{}\", which detoured to \"{node_name}\", no longer exists.",
+            caller.node_name
+        ));
+    }
+    let mut new_state = state.clone();
+    if is_waiting_for_option_selection {
+        let (options, program_counter) =
+            map_options(new_node, &state.current_options).ok_or_else(|| {
+                format!("None of the current options of node \"{node_name}\" exist anymore.")
+            })?;
+        new_state.current_options = options;
+        new_state.program_counter = program_counter;
+    } else {
+        new_state.program_counter = map_program_counter(old_node, new_node, state.program_counter)
+            .ok_or_else(|| format!("The current position in node \"{node_name}\" was changed."))?;
+    }
+    for caller in &mut new_state.call_stack {
+        let caller_name = &caller.node_name;
+        let (old_node, new_node) = nodes(old_program, new_program, caller_name).ok_or_else(|| {
+            format!("The node \"{caller_name}\", which detoured to \"{node_name}\", no longer exists.")
+        })?;
+        caller.program_counter = map_program_counter(old_node, new_node, caller.program_counter)
+            .ok_or_else(|| {
+                format!("The detour from node \"{caller_name}\" to \"{node_name}\" was changed.")
+            })?;
+    }
+    Ok(new_state)
+}
+
+fn nodes<'a>(
+    old_program: &'a Program,
+    new_program: &'a Program,
+    node_name: &str,
+) -> Option<(&'a Node, &'a Node)> {
+    Some((
+        old_program.nodes.get(node_name)?,
+        new_program.nodes.get(node_name)?,
+    ))
+}
+
+/// Returns the instruction in `new_node` that corresponds to the instruction `program_counter` in `old_node`.
+///
+/// The position is anchored at the nearest line or option before it, which is found in `new_node` by its line ID.
+/// The instructions between the anchor and the position must be unchanged, since they may have left values on the stack.
+fn map_program_counter(old_node: &Node, new_node: &Node, program_counter: usize) -> Option<usize> {
+    let old_instructions = old_node.instructions.get(..program_counter)?;
+    let Some(old_anchor) = old_instructions.iter().rposition(|i| line_id(i).is_some()) else {
+        // Nothing was delivered yet, so everything up to here must be unchanged
+        let new_instructions = new_node.instructions.get(..program_counter)?;
+        return (new_instructions == old_instructions).then_some(program_counter);
+    };
+    let anchor_instruction = &old_instructions[old_anchor];
+    let new_anchor = new_node
+        .instructions
+        .iter()
+        .position(|instruction| is_same_content(anchor_instruction, instruction))?;
+    let new_program_counter = new_anchor + program_counter - old_anchor;
+    let new_instructions = new_node
+        .instructions
+        .get(new_anchor + 1..new_program_counter)?;
+    (new_instructions == &old_instructions[old_anchor + 1..]).then_some(new_program_counter)
+}
+
+/// Keeps the options that `new_node` still offers, renumbering them and updating their destinations.
+/// Returns them together with the instruction after the one that shows them.
+fn map_options(
+    new_node: &Node,
+    options: &[DialogueOption],
+) -> Option<(Vec<DialogueOption>, usize)> {
+    let mut last_option_index = None;
+    let mut new_options = Vec::new();
+    for option in options {
+        let Some((index, instruction)) = new_node.instructions.iter().enumerate().find(|(_, i)| {
+            i.opcode == OpCode::AddOption as i32 && line_id(i) == Some(option.line.id.0.clone())
+        }) else {
+            continue;
+        };
+        last_option_index = last_option_index.max(Some(index));
+        new_options.push(DialogueOption {
+            id: OptionId(new_options.len()),
+            destination_node: instruction.read_operand(1)?,
+            ..option.clone()
+        });
+    }
+    let show_options_index = last_option_index?
+        + new_node.instructions[last_option_index?..]
+            .iter()
+            .position(|i| i.opcode == OpCode::ShowOptions as i32)?;
+    Some((new_options, show_options_index + 1))
+}
+
+fn line_id(instruction: &Instruction) -> Option<String> {
+    [OpCode::RunLine as i32, OpCode::AddOption as i32]
+        .contains(&instruction.opcode)
+        .then(|| instruction.read_operand(0))
+        .flatten()
+}
+
+/// Returns `true` if both instructions deliver the same line or option. The number of substitutions may differ,
+/// since they were already taken from the stack, but an option must still lead to the same destination.
+fn is_same_content(old: &Instruction, new: &Instruction) -> bool {
+    old.opcode == new.opcode
+        && line_id(old).is_some()
+        && line_id(old) == line_id(new)
+        && (old.opcode != OpCode::AddOption as i32 || old.operands.get(1) == new.operands.get(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(instructions: &[(OpCode, &str)]) -> Node {
+        Node {
+            instructions: instructions
+                .iter()
+                .map(|(opcode, operand)| Instruction {
+                    opcode: *opcode as i32,
+                    operands: vec![Operand::from(operand.to_string()), Operand::from(0)],
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn maps_program_counter_by_nearest_line() {
+        let old_node = node(&[
+            (OpCode::RunLine, "line:first"),
+            (OpCode::RunCommand, "wave"),
+            (OpCode::RunLine, "line:second"),
+        ]);
+        let new_node = node(&[
+            (OpCode::RunLine, "line:opening"),
+            (OpCode::RunLine, "line:first"),
+            (OpCode::RunCommand, "wave"),
+            (OpCode::RunLine, "line:second"),
+        ]);
+        assert_eq!(Some(0), map_program_counter(&old_node, &new_node, 0));
+        assert_eq!(Some(2), map_program_counter(&old_node, &new_node, 1));
+        assert_eq!(Some(3), map_program_counter(&old_node, &new_node, 2));
+        assert_eq!(Some(4), map_program_counter(&old_node, &new_node, 3));
+
+        let changed_node = node(&[
+            (OpCode::RunLine, "line:first"),
+            (OpCode::RunCommand, "bow"),
+            (OpCode::RunLine, "line:second"),
+        ]);
+        assert_eq!(None, map_program_counter(&old_node, &changed_node, 2));
+        assert_eq!(Some(3), map_program_counter(&old_node, &changed_node, 3));
+    }
+}
@@ -0,0 +1,99 @@
+//! Not part of the original implementation, which throws exceptions when it encounters a malformed instruction.
+
+use crate::prelude::*;
+
+pub(crate) type InstructionResult<T> = core::result::Result<T, InstructionError>;
+
+/// An error that occurred while running a single instruction.
+/// The [`VirtualMachine`] turns it into a [`DialogueError`] that knows where the instruction is located.
+#[derive(Debug)]
+pub(crate) enum InstructionError {
+    StackUnderflow,
+    InvalidOperand {
+        operand_index: usize,
+    },
+    UnknownOpCode {
+        opcode: i32,
+    },
+    UnknownLabel {
+        label_name: String,
+    },
+    TypeConversionFailed {
+        message: String,
+    },
+    FunctionParameterCountMismatch {
+        function_name: String,
+        expected_parameter_count: usize,
+        actual_parameter_count: usize,
+    },
+    FunctionFailed {
+        function_name: String,
+        message: String,
+    },
+    /// An error that either doesn't depend on the instruction's location or already contains it.
+    Dialogue(DialogueError),
+}
+
+impl InstructionError {
+    pub(crate) fn at(self, node_name: String, instruction_index: usize) -> DialogueError {
+        match self {
+            Self::StackUnderflow => DialogueError::StackUnderflow {
+                node_name,
+                instruction_index,
+            },
+            Self::InvalidOperand { operand_index } => DialogueError::InvalidOperand {
+                node_name,
+                instruction_index,
+                operand_index,
+            },
+            Self::UnknownOpCode { opcode } => DialogueError::UnknownOpCode {
+                node_name,
+                instruction_index,
+                opcode,
+            },
+            Self::UnknownLabel { label_name } => DialogueError::UnknownLabel {
+                node_name,
+                instruction_index,
+                label_name,
+            },
+            Self::TypeConversionFailed { message } => DialogueError::TypeConversionFailed {
+                node_name,
+                instruction_index,
+                message,
+            },
+            Self::FunctionParameterCountMismatch {
+                function_name,
+                expected_parameter_count,
+                actual_parameter_count,
+            } => DialogueError::FunctionParameterCountMismatch {
+                node_name,
+                instruction_index,
+                function_name,
+                expected_parameter_count,
+                actual_parameter_count,
+            },
+            Self::FunctionFailed {
+                function_name,
+                message,
+            } => DialogueError::FunctionFailed {
+                node_name,
+                instruction_index,
+                function_name,
+                message,
+            },
+            Self::Dialogue(error) => error,
+        }
+    }
+}
+
+impl From<DialogueError> for InstructionError {
+    fn from(source: DialogueError) -> Self {
+        Self::Dialogue(source)
+    }
+}
+
+impl From<VariableStorageError> for InstructionError {
+    fn from(source: VariableStorageError) -> Self {
+        Self::Dialogue(source.into())
+    }
+}
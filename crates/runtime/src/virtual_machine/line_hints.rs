@@ -0,0 +1,139 @@
+//! Contains the lookahead used by [`Dialogue::set_line_hints_lookahead`], which collects line hints from the nodes a node jumps or detours to.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashSet;
+
+/// Returns the IDs of the lines and options in `node`, followed by those of the nodes it jumps or detours to,
+/// up to `lookahead` jumps away. Nearer lines come first, and the lines of a node keep their order.
+///
+/// Options lead to labels in the same node, so their lines and the jumps that follow them are already part of `node`.
+/// Jumps whose destination is only known at runtime, e.g. `<<jump {$next}>>`, are not followed.
+pub(crate) fn line_hints<'a>(
+    program: &'a Program,
+    node: &'a Node,
+    lookahead: usize,
+) -> Vec<LineId> {
+    let mut visited_nodes: HashSet<&'a str> = HashSet::default();
+    visited_nodes.insert(node.name.as_str());
+    let mut nodes = vec![node];
+    let mut line_ids = Vec::new();
+    for depth in 0..=lookahead {
+        line_ids.extend(nodes.iter().flat_map(|node| line_ids_in(node)));
+        if depth == lookahead {
+            break;
+        }
+        nodes = nodes
+            .iter()
+            .flat_map(|node| jump_destinations(node))
+            .filter(|node_name| visited_nodes.insert(*node_name))
+            .filter_map(|node_name| program.nodes.get(node_name))
+            .filter(|node| !node.is_hidden())
+            .collect();
+        if nodes.is_empty() {
+            break;
+        }
+    }
+    line_ids
+}
+
+fn line_ids_in(node: &Node) -> impl Iterator<Item = LineId> + '_ {
+    node.instructions
+        .iter()
+        // Loop over every instruction and find the ones that run a
+        // line or add an option; these are the two instructions
+        // that will signal a line can appear to the player
+        .filter(|instruction| {
+            [OpCode::RunLine as i32, OpCode::AddOption as i32].contains(&instruction.opcode)
+        })
+        // Both RunLine and AddOption have the string ID
+        // they want to show as their first operand, so
+        // store that
+        .filter_map(|instruction| instruction.read_operand(0).map(LineId))
+}
+
+/// Returns the names of the nodes that `node` jumps or detours to, in the order of the jumps.
+fn jump_destinations(node: &Node) -> impl Iterator<Item = &str> {
+    node.instructions
+        .windows(2)
+        .filter(|instructions| {
+            instructions[0].opcode == OpCode::PushString as i32
+                && [OpCode::RunNode as i32, OpCode::DetourToNode as i32]
+                    .contains(&instructions[1].opcode)
+        })
+        .filter_map(
+            |instructions| match instructions[0].operands.first()?.value.as_ref()? {
+                OperandValue::StringValue(node_name) => Some(node_name.as_str()),
+                _ => None,
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, instructions: &[(OpCode, &str)]) -> Node {
+        Node {
+            name: name.to_owned(),
+            instructions: instructions
+                .iter()
+                .map(|(opcode, operand)| Instruction {
+                    opcode: *opcode as i32,
+                    operands: vec![Operand::from(operand.to_string())],
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn nearer_lines_come_first() {
+        let nodes = [
+            node(
+                "Start",
+                &[
+                    (OpCode::RunLine, "line:start"),
+                    (OpCode::PushString, "Shop"),
+                    (OpCode::DetourToNode, ""),
+                    (OpCode::PushString, "End"),
+                    (OpCode::RunNode, ""),
+                ],
+            ),
+            node(
+                "Shop",
+                &[
+                    (OpCode::AddOption, "line:buy"),
+                    (OpCode::PushString, "Start"),
+                    (OpCode::RunNode, ""),
+                ],
+            ),
+            node(
+                "End",
+                &[
+                    (OpCode::RunLine, "line:end"),
+                    (OpCode::PushString, "Epilogue"),
+                    (OpCode::RunNode, ""),
+                ],
+            ),
+            node("Epilogue", &[(OpCode::RunLine, "line:epilogue")]),
+        ];
+        let program = Program {
+            nodes: nodes
+                .into_iter()
+                .map(|node| (node.name.clone(), node))
+                .collect(),
+            ..Default::default()
+        };
+        let start = &program.nodes["Start"];
+
+        assert_eq!(
+            vec![LineId::from("line:start")],
+            line_hints(&program, start, 0)
+        );
+        let expected: Vec<LineId> = ["line:start", "line:buy", "line:end"]
+            .map(LineId::from)
+            .to_vec();
+        assert_eq!(expected, line_hints(&program, start, 1));
+        assert_eq!(4, line_hints(&program, start, 10).len());
+    }
+}
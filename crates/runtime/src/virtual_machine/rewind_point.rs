@@ -0,0 +1,29 @@
+//! Contains [`RewindPoint`], the state of the dialogue at the moment a line was delivered.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashMap;
+
+/// Everything needed to return to the moment a line was delivered. See [`Dialogue::rewind`].
+///
+/// Unlike a [`DialogueSnapshot`], this also captures the values of the variables, since rewinding should undo their changes as well.
+#[derive(Debug, Clone)]
+pub(crate) struct RewindPoint {
+    /// The delivered line. Its [`Line::values`] are used to deliver it again in the current language.
+    pub(crate) line: Line,
+    pub(crate) current_node_name: Option<String>,
+    pub(crate) state: State,
+    pub(crate) random_state: u64,
+    /// The stored variables, except for local ones. These are part of [`RewindPoint::state`] instead.
+    pub(crate) variables: HashMap<String, YarnValue>,
+}
+
+/// Returns the variables of the storage that outlive the current node.
+pub(crate) fn non_local_variables(
+    variable_storage: &dyn VariableStorage,
+) -> HashMap<String, YarnValue> {
+    variable_storage
+        .variables()
+        .into_iter()
+        .filter(|(name, _)| variable_storage.scope(name) != Some(VariableScope::Local))
+        .collect()
+}
@@ -1,7 +1,8 @@
 //! Adapted from <https://github.com/YarnSpinnerTool/YarnSpinner/blob/da39c7195107d8211f21c263e4084f773b84eaff/YarnSpinner/VirtualMachine.cs>, which we split into multiple files
 
 use crate::prelude::*;
-use core::fmt::Debug;
+use bevy_platform::collections::HashMap;
+use core::fmt::Display;
 
 #[derive(Debug, Clone, PartialEq, Default)]
 #[cfg_attr(feature = "bevy", derive(Reflect))]
@@ -21,6 +22,38 @@ pub(crate) struct State {
 
     /// The value stack.
     pub(crate) stack: Vec<InternalValue>,
+
+    /// The candidates that will be passed to the [`SaliencyStrategy`]
+    /// when the next SelectSaliencyCandidate instruction is encountered.
+    pub(crate) saliency_candidates: Vec<SaliencyCandidate>,
+
+    /// The nodes that detoured to the current node, innermost last.
+    pub(crate) call_stack: Vec<CallStackFrame>,
+
+    /// The variables introduced with `<<local>>` in the current node.
+    pub(crate) local_variables: HashMap<String, InternalValue>,
+}
+
+/// A node that is waiting for a detour to return.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy", reflect(Debug, PartialEq))]
+#[cfg_attr(
+    all(feature = "bevy", feature = "serde"),
+    reflect(Serialize, Deserialize)
+)]
+pub(crate) struct CallStackFrame {
+    pub(crate) node_name: String,
+
+    /// The instruction to continue at when the detour returns.
+    pub(crate) program_counter: usize,
+
+    /// The value stack of the node at the time of the detour.
+    pub(crate) stack: Vec<InternalValue>,
+
+    /// The local variables of the node at the time of the detour.
+    pub(crate) local_variables: HashMap<String, InternalValue>,
 }
 
 impl State {
@@ -30,46 +63,42 @@ impl State {
 
     /// Pops a value from the stack and tries to convert it to the specified type.
     ///
-    /// ## Panics
-    /// - Panics on an empty stack to mirror C# behavior.
-    /// - Panics if the value cannot be converted to the specified type.
-    pub(crate) fn pop<T>(&mut self) -> T
+    /// ## Implementation note
+    ///
+    /// The original throws an exception on an empty stack or a failed conversion. We return an error instead.
+    pub(crate) fn pop<T>(&mut self) -> InstructionResult<T>
     where
         T: TryFrom<InternalValue>,
-        <T as TryFrom<InternalValue>>::Error: Debug,
+        <T as TryFrom<InternalValue>>::Error: Display,
     {
-        self.pop_value()
-            .try_into()
-            .unwrap_or_else(|e| panic!("Failed to convert popped value: {e:?}",))
+        self.pop_value()?.try_into().map_err(conversion_error)
     }
 
-    /// Pops a value from the stack. Panics on an empty stack to mirror C# behavior.
-    pub(crate) fn pop_value(&mut self) -> InternalValue {
-        self.stack
-            .pop()
-            .unwrap_or_else(|| panic!("Tried to pop value, but the stack was empty."))
+    /// Pops a value from the stack.
+    pub(crate) fn pop_value(&mut self) -> InstructionResult<InternalValue> {
+        self.stack.pop().ok_or(InstructionError::StackUnderflow)
     }
 
-    /// Peeks the top value of the stack. Panics on an empty stack to mirror C# behavior.
-    pub(crate) fn peek<T>(&self) -> T
+    /// Copies the top value of the stack and tries to convert it to the specified type.
+    pub(crate) fn peek<T>(&self) -> InstructionResult<T>
     where
         T: TryFrom<InternalValue>,
-        <T as TryFrom<InternalValue>>::Error: Debug,
+        <T as TryFrom<InternalValue>>::Error: Display,
     {
-        self.peek_value()
+        self.peek_value()?
             .clone()
             .try_into()
-            .unwrap_or_else(|e| panic!("Failed to convert popped value: {e:?}",))
+            .map_err(conversion_error)
     }
 
-    /// Copies the top value of the stack and tries to convert it to the specified type.
-    ///
-    /// ## Panics
-    /// - Panics on an empty stack to mirror C# behavior.
-    /// - Panics if the value cannot be converted to the specified type.
-    pub(crate) fn peek_value(&self) -> &InternalValue {
-        self.stack
-            .last()
-            .unwrap_or_else(|| panic!("Tried to peek value, but the stack was empty."))
+    /// Peeks the top value of the stack.
+    pub(crate) fn peek_value(&self) -> InstructionResult<&InternalValue> {
+        self.stack.last().ok_or(InstructionError::StackUnderflow)
+    }
+}
+
+fn conversion_error(error: impl Display) -> InstructionError {
+    InstructionError::TypeConversionFailed {
+        message: format!("Failed to convert value on the stack: {error}"),
     }
 }
@@ -0,0 +1,65 @@
+//! Contains [`VariableJournal`], which records the variable writes of a transaction so that they can be rolled back.
+
+use crate::prelude::*;
+use bevy_platform::collections::HashMap;
+
+/// Records the values variables had before they were first written during a transaction,
+/// so that the writes can be undone in any [`VariableStorage`]. See [`Dialogue::begin_transaction`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VariableJournal {
+    /// The values of the written variables before the transaction began. `None` for variables that were not defined.
+    original_values: HashMap<String, Option<YarnValue>>,
+
+    /// Whether the transaction was begun because of [`Dialogue::set_rollback_on_stop`] rather than by [`Dialogue::begin_transaction`].
+    /// Such a transaction is committed as soon as the dialogue completes.
+    pub(crate) is_automatic: bool,
+}
+
+/// A variable whose value was restored by [`VariableJournal::rollback`].
+#[derive(Debug, Clone)]
+pub(crate) struct RestoredVariable {
+    pub(crate) name: String,
+    /// The stored value before the rollback, if any.
+    pub(crate) rolled_back_value: Option<YarnValue>,
+    /// The stored value after the rollback, if any.
+    pub(crate) restored_value: Option<YarnValue>,
+}
+
+impl VariableJournal {
+    pub(crate) fn new(is_automatic: bool) -> Self {
+        Self {
+            original_values: Default::default(),
+            is_automatic,
+        }
+    }
+
+    /// Remembers the current value of a variable that is about to be written.
+    /// Only the first write of a variable is recorded, since that is the value a rollback restores.
+    pub(crate) fn record(&mut self, variable_storage: &dyn VariableStorage, name: &str) {
+        if !self.original_values.contains_key(name) {
+            self.original_values
+                .insert(name.to_owned(), variable_storage.get(name).ok());
+        }
+    }
+
+    /// Restores the recorded values and removes the variables that were not defined before the transaction.
+    pub(crate) fn rollback(
+        self,
+        variable_storage: &mut dyn VariableStorage,
+    ) -> Result<Vec<RestoredVariable>> {
+        let mut restored_variables = Vec::with_capacity(self.original_values.len());
+        for (name, original_value) in self.original_values {
+            let current_value = variable_storage.get(&name).ok();
+            match original_value.clone() {
+                Some(value) => variable_storage.set(name.clone(), value)?,
+                None => variable_storage.remove(&name)?,
+            }
+            restored_variables.push(RestoredVariable {
+                name,
+                rolled_back_value: current_value,
+                restored_value: original_value,
+            });
+        }
+        Ok(restored_variables)
+    }
+}
@@ -19,9 +19,10 @@ pub mod prelude {
     };
     pub use crate::runtime::{
         Command as YarnCommand, CompiledProgramAnalyser as YarnAnalyser,
-        Context as YarnAnalysisContext, Dialogue, DialogueError, DialogueEvent, DialogueOption,
-        Language, Line as YarnLine, MarkupAttribute, MarkupValue, OptionId,
-        Result as YarnRuntimeResult, StringTable, TextProvider, VariableStorage,
+        Context as YarnAnalysisContext, Dialogue, DialogueError, DialogueEvent, DialogueHistory,
+        DialogueOption, DialogueRng, DialogueSnapshot, HistoryEntry, Language, Line as YarnLine,
+        MarkupAttribute, MarkupValue, OptionId, Result as YarnRuntimeResult, SaliencyStrategy,
+        SeenLineTracker, StringTable, TextProvider, VariableStorage,
     };
 }
 
@@ -29,10 +30,11 @@ pub mod core {
     //! Core types and traits that are used by both the compiler and runtime.
     pub use yarnspinner_core::prelude::{
         Header, Instruction, IntoYarnValueFromNonYarnValue, InvalidOpCodeError, Library, LineId,
-        Node, Position, Program, Type, UntypedYarnFn, YarnFn, YarnFnParam, YarnFnParamItem,
-        YarnValue, YarnValueCastError, YarnValueWrapper, YarnValueWrapperIter, optionality,
-        yarn_fn_type, yarn_library,
+        MergePolicy, Node, Position, Program, ProgramMergeError, Type, UntypedYarnFn, YarnFn,
+        YarnFnParam, YarnFnParamItem, YarnValue, YarnValueCastError, YarnValueWrapper,
+        YarnValueWrapperIter, optionality, yarn_fn_type, yarn_library,
     };
+    pub use yarnspinner_core::types::{EnumCase, EnumType};
 }
 pub mod compiler {
     //! Types and traits used by the compiler, in particular the [`Compiler`] struct.
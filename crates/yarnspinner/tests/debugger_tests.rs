@@ -0,0 +1,186 @@
+//! Tests for the breakpoints and stepping of [`DialogueDebugger`].
+
+#[cfg(feature = "bevy")]
+use bevy::prelude::World;
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+const SOURCE: &str = "title: Start
+---
+<<declare $gold = 5>>
+Welcome. #line:welcome
+<<detour Shop>>
+Goodbye. #line:goodbye
+===
+title: Shop
+---
+<<set $gold = $gold - 1>>
+-> Buy bread #line:bread
+-> Leave #line:leave
+===
+";
+
+#[test]
+fn test_node_breakpoint_pauses_before_node() {
+    let mut debugger = debugger();
+    debugger.add_breakpoint(Breakpoint::Node("Shop".to_owned()));
+
+    assert_eq!(vec!["Welcome."], lines(continue_(&mut debugger)));
+    assert_eq!(None, debugger.hit_breakpoint());
+
+    continue_(&mut debugger);
+    assert_eq!(
+        Some(&Breakpoint::Node("Shop".to_owned())),
+        debugger.hit_breakpoint()
+    );
+    assert_eq!(Some("Shop".to_owned()), debugger.dialogue().current_node());
+    assert_eq!(Some(0), debugger.instruction_index());
+    assert!(debugger.dialogue().is_active());
+
+    // Resuming doesn't pause at the same breakpoint again
+    let events = continue_(&mut debugger);
+    assert_eq!(None, debugger.hit_breakpoint());
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, DialogueEvent::Options(_)))
+    );
+}
+
+#[test]
+fn test_line_breakpoint_shows_pending_options() {
+    let mut debugger = debugger();
+    debugger.add_breakpoint(Breakpoint::Line("line:leave".into()));
+
+    continue_(&mut debugger);
+    continue_(&mut debugger);
+    assert_eq!(
+        Some(&Breakpoint::Line("line:leave".into())),
+        debugger.hit_breakpoint()
+    );
+    let pending_options: Vec<_> = debugger
+        .pending_options()
+        .iter()
+        .map(|option| option.line.id.clone())
+        .collect();
+    assert_eq!(vec![LineId::from("line:bread")], pending_options);
+}
+
+#[test]
+fn test_source_line_breakpoint_uses_debug_info() {
+    let mut debugger = debugger();
+    debugger.add_breakpoint(Breakpoint::SourceLine {
+        file_name: "<input>".to_owned(),
+        line: 9,
+    });
+
+    continue_(&mut debugger);
+    continue_(&mut debugger);
+    assert!(debugger.hit_breakpoint().is_some());
+    assert_eq!(Some("Shop".to_owned()), debugger.dialogue().current_node());
+    let (file_name, position) = debugger.source_position().unwrap();
+    assert_eq!("<input>", file_name);
+    assert_eq!(9, position.line);
+    // The instructions of `<<set>>` have not run yet
+    assert!(debugger.value_stack().is_empty());
+}
+
+#[test]
+fn test_stepping_over_instructions_and_statements() {
+    let mut debugger = debugger();
+    debugger.add_breakpoint(Breakpoint::Node("Shop".to_owned()));
+    continue_(&mut debugger);
+    continue_(&mut debugger);
+
+    // `<<set $gold = $gold - 1>>` starts by pushing $gold
+    assert!(step_instruction(&mut debugger).is_empty());
+    assert_eq!(Some(1), debugger.instruction_index());
+    assert_eq!(vec![YarnValue::from(5)], debugger.value_stack());
+
+    // The rest of the statement runs in one step
+    step_statement(&mut debugger);
+    assert!(debugger.value_stack().is_empty());
+    assert_eq!(10, debugger.source_position().unwrap().1.line);
+    assert_eq!(
+        YarnValue::from(4),
+        debugger.dialogue().variable_storage().get("$gold").unwrap()
+    );
+}
+
+#[test]
+fn test_lines_delivered_while_debugging_are_recorded() {
+    let mut debugger = debugger();
+    debugger
+        .dialogue_mut()
+        .set_history(DialogueHistory::default());
+    debugger
+        .dialogue_mut()
+        .set_seen_line_tracker(SeenLineTracker::new());
+
+    assert_eq!(vec!["Welcome."], lines(continue_(&mut debugger)));
+    let dialogue = debugger.dialogue();
+    assert_eq!(1, dialogue.history().unwrap().len());
+    assert!(
+        dialogue
+            .seen_line_tracker()
+            .unwrap()
+            .is_seen(&LineId::from("line:welcome"))
+    );
+}
+
+fn debugger() -> DialogueDebugger {
+    let compilation = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: SOURCE.to_owned(),
+        })
+        .compile()
+        .unwrap();
+    let debug_info = compilation.debug_info.clone();
+    let mut test_base = TestBase::new().with_compilation(compilation);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let mut debugger = DialogueDebugger::new(test_base.dialogue);
+    for debug_info in debug_info.into_values() {
+        debugger.add_debug_info(debug_info);
+    }
+    debugger
+}
+
+fn continue_(debugger: &mut DialogueDebugger) -> Vec<DialogueEvent> {
+    #[cfg(feature = "bevy")]
+    let events = debugger.continue_with_world(&mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let events = debugger.continue_();
+    events.unwrap_or_else(|e| panic!("Encountered error while running dialogue: {e}"))
+}
+
+fn step_instruction(debugger: &mut DialogueDebugger) -> Vec<DialogueEvent> {
+    #[cfg(feature = "bevy")]
+    let events = debugger.step_instruction_with_world(&mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let events = debugger.step_instruction();
+    events.unwrap_or_else(|e| panic!("Encountered error while running dialogue: {e}"))
+}
+
+fn step_statement(debugger: &mut DialogueDebugger) -> Vec<DialogueEvent> {
+    #[cfg(feature = "bevy")]
+    let events = debugger.step_statement_with_world(&mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let events = debugger.step_statement();
+    events.unwrap_or_else(|e| panic!("Encountered error while running dialogue: {e}"))
+}
+
+fn lines(events: Vec<DialogueEvent>) -> Vec<String> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            DialogueEvent::Line(line) => Some(line.text),
+            _ => None,
+        })
+        .collect()
+}
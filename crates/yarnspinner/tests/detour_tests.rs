@@ -0,0 +1,237 @@
+//! Tests for `<<detour>>` and `<<return>>` statements and the call stack they use.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+const SHOP: &str = "title: Start
+---
+Welcome.
+<<detour Shop>>
+Goodbye.
+===
+title: Shop
+---
+What would you like?
+<<detour Prices>>
+Come again.
+===
+title: Prices
+---
+Everything is 5 gold.
+===
+";
+
+#[test]
+fn test_detour_returns_to_caller() {
+    let result = compile(SHOP).unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec![
+            "NodeStart Start",
+            "Welcome.",
+            "NodeStart Shop",
+            "What would you like?",
+            "NodeStart Prices",
+            "Everything is 5 gold.",
+            "NodeComplete Prices",
+            "Come again.",
+            "NodeComplete Shop",
+            "Goodbye.",
+            "NodeComplete Start",
+            "DialogueComplete",
+        ],
+        run_events(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_return_leaves_detour_early() {
+    let result = compile(
+        "title: Start
+---
+<<detour Shop>>
+Goodbye.
+===
+title: Shop
+---
+We're closed.
+<<return>>
+Never shown.
+===
+",
+    )
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["We're closed.", "Goodbye."],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_detour_to_expression() {
+    let result = compile(
+        "title: Start
+---
+<<declare $shop = \"Shop\">>
+<<detour {$shop}>>
+Goodbye.
+===
+title: Shop
+---
+Welcome to the shop.
+===
+",
+    )
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Welcome to the shop.", "Goodbye."],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_jump_in_detour_returns_to_same_caller() {
+    let result = compile(
+        "title: Start
+---
+<<detour Shop>>
+Goodbye.
+===
+title: Shop
+---
+<<jump Backroom>>
+===
+title: Backroom
+---
+Psst.
+===
+",
+    )
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec![
+            "NodeStart Start",
+            "NodeStart Shop",
+            "NodeComplete Shop",
+            "NodeStart Backroom",
+            "Psst.",
+            "NodeComplete Backroom",
+            "Goodbye.",
+            "NodeComplete Start",
+            "DialogueComplete",
+        ],
+        run_events(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_stop_in_detour_completes_all_nodes() {
+    let result = compile(
+        "title: Start
+---
+<<detour Shop>>
+Never shown.
+===
+title: Shop
+---
+<<stop>>
+===
+",
+    )
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec![
+            "NodeStart Start",
+            "NodeStart Shop",
+            "NodeComplete Shop",
+            "NodeComplete Start",
+            "DialogueComplete",
+        ],
+        run_events(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_call_stack_is_part_of_snapshot() {
+    let result = compile(SHOP).unwrap();
+    let mut test_base = TestBase::new().with_compilation(result.clone());
+
+    test_base.dialogue.set_node("Start").unwrap();
+    // "Welcome." and "What would you like?"
+    continue_(&mut test_base.dialogue);
+    continue_(&mut test_base.dialogue);
+    assert_eq!(Some("Shop".to_owned()), test_base.dialogue.current_node());
+    assert_eq!(vec!["Start"], test_base.dialogue.call_stack());
+
+    let snapshot = test_base.dialogue.snapshot().unwrap();
+    assert_eq!(vec!["Start"], snapshot.call_stack());
+
+    let mut other = TestBase::new().with_compilation(result);
+    other.dialogue.restore(snapshot).unwrap();
+    assert_eq!(vec!["Start"], other.dialogue.call_stack());
+    assert_eq!(
+        vec!["Everything is 5 gold.", "Come again.", "Goodbye."],
+        run_lines(&mut other.dialogue)
+    );
+    assert!(other.dialogue.call_stack().is_empty());
+}
+
+#[test]
+fn test_detour_needs_a_destination() {
+    let error = compile("title: Start\n---\n<<detour Shop Backroom>>\n===\n").unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("<<detour>> must be followed by the name of a node")
+    }));
+}
+
+fn compile(source: &str) -> yarnspinner::compiler::Result<Compilation> {
+    Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+}
+
+/// Runs the dialogue until it stops and returns the text of all delivered lines
+/// as well as the node and dialogue events.
+fn run_events(dialogue: &mut Dialogue) -> Vec<String> {
+    let mut events = Vec::new();
+    loop {
+        events.extend(
+            continue_(dialogue)
+                .into_iter()
+                .filter_map(|event| match event {
+                    DialogueEvent::Line(line) => Some(line.text),
+                    DialogueEvent::NodeStart(node_name) => Some(format!("NodeStart {node_name}")),
+                    DialogueEvent::NodeComplete(node_name) => {
+                        Some(format!("NodeComplete {node_name}"))
+                    }
+                    DialogueEvent::DialogueComplete => Some("DialogueComplete".to_owned()),
+                    _ => None,
+                }),
+        );
+        if !dialogue.is_active() {
+            return events;
+        }
+    }
+}
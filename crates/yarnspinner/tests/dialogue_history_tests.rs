@@ -0,0 +1,79 @@
+//! Tests for recording and relocalizing a [`DialogueHistory`].
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_history_records_lines_and_selected_options() {
+    let source = "
+            <<declare $name = \"Alice\">>
+            Sally: Hi, {$name}! #line:greeting
+            -> Hello! #line:hello
+            -> Bye! #line:bye
+            Sally: See you. #line:farewell
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_history(DialogueHistory::new(2));
+    test_base.dialogue.set_node("Start").unwrap();
+
+    continue_(&mut test_base.dialogue);
+    continue_(&mut test_base.dialogue);
+    let history = test_base.dialogue.history().unwrap();
+    assert_eq!(1, history.len());
+    let line = history.last().unwrap().line();
+    assert_eq!(LineId::from("line:greeting"), line.id);
+    assert_eq!(Some("Sally"), line.character_name());
+    assert_eq!(vec!["Alice".to_owned()], line.substitutions);
+
+    test_base.dialogue.set_selected_option(OptionId(1)).unwrap();
+    continue_(&mut test_base.dialogue);
+    let entries: Vec<_> = test_base.dialogue.history().unwrap().entries().collect();
+    assert_eq!(2, entries.len());
+    assert!(
+        matches!(entries[0], HistoryEntry::SelectedOption(line) if line.id == LineId::from("line:bye"))
+    );
+    assert!(matches!(entries[1], HistoryEntry::Line(line) if line.text == "Sally: See you."));
+}
+
+#[test]
+fn test_history_is_relocalized_when_language_changes() {
+    let source = "
+            <<declare $name = \"Alice\">>
+            Sally: Hi, {$name}! #line:greeting
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let base_language: Vec<_> = result
+        .string_table
+        .iter()
+        .map(|(id, info)| (id.clone(), info.text.clone()))
+        .collect();
+    let mut test_base = TestBase::new().with_compilation(result);
+    let mut text_provider = StringTableTextProvider::new();
+    text_provider.extend_base_language(base_language);
+    text_provider.extend_translation(
+        "de-CH",
+        [(
+            LineId::from("line:greeting"),
+            "Sally: Grüezi, {0}!".to_owned(),
+        )],
+    );
+    test_base.string_table.replace(text_provider);
+    test_base.dialogue.set_history(DialogueHistory::default());
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+
+    test_base
+        .dialogue
+        .set_language_code(Language::from("de-CH"));
+    let history = test_base.dialogue.history().unwrap();
+    assert_eq!(Some(&Language::from("de-CH")), history.language());
+    let line = history.last().unwrap().line();
+    assert_eq!("Sally: Grüezi, Alice!", line.text);
+    assert_eq!("Grüezi, Alice!", line.text_without_character_name());
+    assert!(!test_base.dialogue.is_history_outdated());
+}
@@ -339,7 +339,9 @@ fn test_selecting_option_from_inside_option_callback() {
                 DialogueEvent::Command(_)
                 | DialogueEvent::NodeComplete(_)
                 | DialogueEvent::NodeStart(_)
-                | DialogueEvent::LineHints(_) => {}
+                | DialogueEvent::LineHints(_)
+                | DialogueEvent::VariableChanged { .. }
+                | DialogueEvent::HotSwapFallback { .. } => {}
             }
         }
     }
@@ -0,0 +1,130 @@
+//! Tests for enums declared with `<<enum>>` statements and variables of their types.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+
+mod test_base;
+
+const FOOD: &str = "<<enum Food>>
+<<case Apple>>
+<<case Orange>>
+<<case Pear>>
+<<endenum>>
+";
+
+const DRINK: &str = "<<enum Drink>>
+<<case Tea = \"tea\">>
+<<case Coffee = \"coffee\">>
+<<endenum>>
+";
+
+#[test]
+fn test_enum_cases_can_be_compared_and_assigned() {
+    let result = Compiler::from_test_source(&format!(
+        "{FOOD}\
+        <<declare $favourite = Food.Orange>>\n\
+        <<if $favourite == .Orange>>\n\
+        Orange is best\n\
+        <<endif>>\n\
+        <<set $favourite = .Pear>>\n\
+        <<if $favourite != Food.Orange>>\n\
+        Pear is better\n\
+        <<endif>>\n"
+    ))
+    .compile()
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Orange is best", "Pear is better"],
+        run_lines(&mut test_base.dialogue)
+    );
+    // Enum values are stored as the raw values of their cases
+    assert_eq!(
+        YarnValue::from(2),
+        test_base
+            .dialogue
+            .variable_storage()
+            .get("$favourite")
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_enum_variables_are_declared_with_their_raw_values() {
+    let result =
+        Compiler::from_test_source(&format!("{DRINK}<<declare $drink = .Coffee as Drink>>\n"))
+            .compile()
+            .unwrap();
+
+    let drink = result
+        .declarations
+        .iter()
+        .find(|declaration| declaration.name == "$drink")
+        .unwrap();
+    let Type::Enum(enum_type) = &drink.r#type else {
+        panic!(
+            "Expected $drink to be an enum, but it is a {}",
+            drink.r#type
+        );
+    };
+    assert_eq!("Drink", enum_type.name);
+    assert_eq!(
+        Some("Tea"),
+        enum_type
+            .case_for_raw_value(&"tea".into())
+            .map(|case| case.name.as_str())
+    );
+    assert_eq!(Some(YarnValue::from("coffee")), drink.default_value);
+
+    let program = result.program.unwrap();
+    assert_eq!(
+        YarnValue::from("coffee"),
+        YarnValue::from(program.initial_values["$drink"].clone())
+    );
+}
+
+#[test]
+fn test_different_enums_cannot_be_compared() {
+    let error = Compiler::from_test_source(&format!(
+        "{FOOD}{DRINK}\
+        <<if Food.Apple == Drink.Tea>>\n\
+        Never\n\
+        <<endif>>\n"
+    ))
+    .compile()
+    .unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("All terms of == must be the same, not Food, Drink")
+    }));
+}
+
+#[test]
+fn test_enum_cases_must_exist() {
+    let error = Compiler::from_test_source(&format!("{FOOD}<<declare $fruit = Food.Banana>>\n"))
+        .compile()
+        .unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("Enum Food has no case named Banana")
+    }));
+}
+
+#[test]
+fn test_enum_raw_values_must_have_the_same_type() {
+    let error = Compiler::from_test_source(
+        "<<enum Size>>\n<<case Small = 1>>\n<<case Large = \"large\">>\n<<endenum>>\n",
+    )
+    .compile()
+    .unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic.message.contains(
+            "The raw value of case Large is a String, but the other cases of enum Size have Number raw values",
+        )
+    }));
+}
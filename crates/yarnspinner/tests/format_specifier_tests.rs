@@ -0,0 +1,121 @@
+//! Tests for format specifiers in inline expressions, e.g. `{$gold:N0}`, and the raw values that are delivered alongside the formatted text.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_format_specifiers_are_applied_to_numbers() {
+    let source = "
+            <<declare $gold = 1234.5>>
+            <<declare $ratio = 0.125>>
+            You have {$gold:N0} gold, {$ratio:P1} of it {$gold:0.00} in coins. #line:gold
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    assert_eq!(
+        "You have {0:N0} gold, {1:P1} of it {2:0.00} in coins.",
+        result.string_table[&LineId::from("line:gold")].text
+    );
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let line = next_line(&mut test_base.dialogue).unwrap();
+    assert_eq!(
+        "You have 1,235 gold, 12.5% of it 1234.50 in coins.",
+        line.text
+    );
+    assert_eq!(
+        vec![YarnValue::from(1234.5), 0.125.into(), 1234.5.into()],
+        line.values
+    );
+    assert_eq!(vec!["1234.5", "0.125", "1234.5"], line.substitutions);
+}
+
+#[test]
+fn test_format_specifiers_are_ignored_for_text() {
+    let source = "
+            <<declare $name = \"Alice\">>
+            Hi, {$name:N0}! #line:greeting
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let line = next_line(&mut test_base.dialogue).unwrap();
+    assert_eq!("Hi, Alice!", line.text);
+}
+
+#[test]
+fn test_format_specifiers_in_comments_are_left_alone() {
+    let source = "
+            /// The gold of the player, e.g. {0:N2}
+            <<declare $gold = 1234.5>>
+            // Shows {$gold:N2} at most
+            You have {$gold:N0} gold. #line:gold
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let declaration = result
+        .declarations
+        .iter()
+        .find(|declaration| declaration.name == "$gold")
+        .unwrap();
+    assert_eq!(
+        Some("The gold of the player, e.g. {0:N2}"),
+        declaration.description.as_deref()
+    );
+    assert_eq!(
+        "You have {0:N0} gold.",
+        result.string_table[&LineId::from("line:gold")].text
+    );
+}
+
+#[test]
+fn test_format_specifiers_are_found_after_an_unclosed_command_in_a_comment() {
+    let source = "
+            <<declare $gold = 1234.5>>
+            // Looks like the start of a command: <<
+            You have {$gold:N0} gold. #line:gold
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    assert_eq!(
+        "You have {0:N0} gold.",
+        result.string_table[&LineId::from("line:gold")].text
+    );
+}
+
+#[test]
+fn test_format_specifiers_use_the_language_of_the_dialogue() {
+    let source = "
+            <<declare $gold = 1234.5>>
+            You have {$gold:N1} gold. #line:gold
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let base_language: Vec<_> = result
+        .string_table
+        .iter()
+        .map(|(id, info)| (id.clone(), info.text.clone()))
+        .collect();
+    let mut test_base = TestBase::new().with_compilation(result);
+    let mut text_provider = StringTableTextProvider::new();
+    text_provider.extend_base_language(base_language);
+    text_provider.extend_translation(
+        "de-DE",
+        [(LineId::from("line:gold"), "Du hast {0:N1} Gold.".to_owned())],
+    );
+    test_base.string_table.replace(text_provider);
+    test_base.dialogue.set_history(DialogueHistory::default());
+    test_base.dialogue.set_node("Start").unwrap();
+    let line = next_line(&mut test_base.dialogue).unwrap();
+    assert_eq!("You have 1,234.5 gold.", line.text);
+
+    // Relocalizing the history formats the recorded values again
+    test_base
+        .dialogue
+        .set_language_code(Language::from("de-DE"));
+    let line = test_base.dialogue.history().unwrap().last().unwrap().line();
+    assert_eq!("Du hast 1.234,5 Gold.", line.text);
+    assert_eq!(vec![YarnValue::from(1234.5)], line.values);
+}
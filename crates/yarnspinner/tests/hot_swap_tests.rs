@@ -0,0 +1,217 @@
+//! Tests for [`Dialogue::hot_swap_program`], which replaces the program while a dialogue is running.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_hot_swapping_keeps_position_and_variables() {
+    let source = "
+            <<declare $gold = 0>>
+            First line #line:first
+            <<set $gold to 10>>
+            Second line #line:second
+            Third line #line:third
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+    continue_(&mut test_base.dialogue);
+
+    let source = "
+            <<declare $gold = 0>>
+            <<declare $mood = \"happy\">>
+            A new opening #line:opening
+            First line #line:first
+            <<set $gold to 10>>
+            Second line #line:second
+            An inserted line #line:inserted
+            Third line #line:third
+            ";
+    let (mut test_base, events) = hot_swap(test_base, source);
+    assert!(events.is_empty());
+    assert_eq!(
+        YarnValue::from(10),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+    assert_eq!(
+        YarnValue::from("happy"),
+        test_base.variable_storage.get("$mood").unwrap()
+    );
+
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(Some("An inserted line"), events.iter().find_map(line_text));
+}
+
+#[test]
+fn test_hot_swapping_keeps_options_that_still_exist() {
+    let source = "
+            Pick one. #line:pick
+            -> A #line:a
+                You picked A.
+            -> B #line:b
+                You picked B.
+            -> C #line:c
+                You picked C.
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+    continue_(&mut test_base.dialogue);
+
+    let source = "
+            Pick one. #line:pick
+            -> A #line:a
+                You picked A.
+            -> C #line:c
+                You picked C, which is new.
+            ";
+    let (mut test_base, events) = hot_swap(test_base, source);
+    let [DialogueEvent::Options(options)] = events.as_slice() else {
+        panic!("Expected the remaining options, but got {events:?}");
+    };
+    let option_ids: Vec<_> = options.iter().map(|option| &option.line.id).collect();
+    assert_eq!(
+        vec![&LineId::from("line:a"), &LineId::from("line:c")],
+        option_ids
+    );
+    assert!(test_base.dialogue.is_waiting_for_option_selection());
+
+    test_base
+        .dialogue
+        .set_selected_option(options[1].id)
+        .unwrap();
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(
+        Some("You picked C, which is new."),
+        events.iter().find_map(line_text)
+    );
+}
+
+#[test]
+fn test_hot_swapping_restarts_node_if_position_is_lost() {
+    let source = "
+            First line #line:first
+            Second line #line:second
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+
+    let source = "
+            A rewritten line #line:rewritten
+            Second line #line:second
+            ";
+    let (mut test_base, events) = hot_swap(test_base, source);
+    assert!(matches!(
+        events.as_slice(),
+        [DialogueEvent::HotSwapFallback { node_name, .. }] if node_name == "Start"
+    ));
+
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(
+        Some(&DialogueEvent::NodeStart("Start".to_owned())),
+        events.first()
+    );
+    assert_eq!(Some("A rewritten line"), events.iter().find_map(line_text));
+}
+
+#[test]
+fn test_hot_swapping_stops_if_current_node_was_removed() {
+    let source = "First line #line:first";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+
+    let program = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: create_test_node_with_name("First line #line:first", "Other"),
+        })
+        .compile()
+        .unwrap()
+        .program
+        .unwrap();
+    let events = test_base.dialogue.hot_swap_program(program).unwrap();
+    assert!(matches!(
+        events.as_slice(),
+        [
+            DialogueEvent::HotSwapFallback { node_name, .. },
+            DialogueEvent::DialogueComplete,
+        ] if node_name == "Start"
+    ));
+    assert!(!test_base.dialogue.is_active());
+    assert!(!test_base.dialogue.node_exists("Start"));
+
+    test_base.dialogue.set_node("Other").unwrap();
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(Some("First line"), events.iter().find_map(line_text));
+}
+
+#[test]
+fn test_hot_swapping_restarts_node_if_caller_was_removed() {
+    let source = "title: Start
+---
+<<detour Shop>>
+===
+title: Shop
+---
+Welcome #line:welcome
+Goodbye #line:goodbye
+===
+";
+    let mut test_base = TestBase::new().with_compilation(compile(source));
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+    assert_eq!(vec!["Start"], test_base.dialogue.call_stack());
+
+    let source = "title: Shop
+---
+Welcome #line:welcome
+Goodbye #line:goodbye
+===
+";
+    let events = test_base
+        .dialogue
+        .hot_swap_program(compile(source).program.unwrap())
+        .unwrap();
+    assert!(matches!(
+        events.as_slice(),
+        [DialogueEvent::HotSwapFallback { node_name, reason }]
+            if node_name == "Shop" && reason.contains("\"Start\"")
+    ));
+    assert!(test_base.dialogue.call_stack().is_empty());
+}
+
+/// Compiles the source and hot swaps it into the dialogue of the test base, along with its string table.
+fn hot_swap(test_base: TestBase, source: &str) -> (TestBase, Vec<DialogueEvent>) {
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let program = result.program.clone().unwrap();
+    let mut test_base = test_base.with_string_table(result.string_table);
+    let events = test_base.dialogue.hot_swap_program(program).unwrap();
+    (test_base, events)
+}
+
+fn line_text(event: &DialogueEvent) -> Option<&str> {
+    match event {
+        DialogueEvent::Line(line) => Some(line.text.as_str()),
+        _ => None,
+    }
+}
+
+fn compile(source: &str) -> Compilation {
+    Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+        .unwrap()
+}
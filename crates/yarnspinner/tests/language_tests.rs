@@ -49,7 +49,6 @@ fn can_compile_space_demo() {
 }
 
 #[test]
-#[should_panic]
 fn test_merging_nodes() {
     let test_base = TestBase::default();
     let sally_path = space_demo_scripts_path().join("Sally.yarn");
@@ -68,10 +67,21 @@ fn test_merging_nodes() {
         .unwrap();
 
     // Loading code with the same contents should throw
-    let _combined_not_working = Program::combine(vec![
-        result_sally.program.unwrap(),
+    let program_sally = result_sally.program.unwrap();
+    let programs = vec![
+        program_sally.clone(),
         result_sally_and_ship.program.unwrap(),
-    ]);
+    ];
+    let error = Program::combine(programs.clone()).unwrap_err();
+    let sally_nodes: Vec<_> = program_sally.nodes.keys().cloned().collect();
+    assert_eq!(sally_nodes, error.conflicting_nodes);
+    assert!(error.conflicting_initial_values.is_empty());
+
+    // Unless the collisions are resolved explicitly
+    let combined = Program::combine_with_policy(programs, MergePolicy::KeepFirst)
+        .unwrap()
+        .unwrap();
+    assert!(combined.nodes.len() > program_sally.nodes.len());
 }
 
 #[test]
@@ -0,0 +1,126 @@
+//! Tests for line groups and the built-in [`SaliencyStrategy`] implementations that pick one of their variants.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_line_group_with_first_strategy_always_runs_first_variant() {
+    let result = Compiler::from_test_source("=> Hi there\n=> Hello\n=> Hey\nBye\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base
+        .dialogue
+        .set_saliency_strategy(FirstSaliencyStrategy);
+
+    for _ in 0..2 {
+        test_base.dialogue.set_node("Start").unwrap();
+        assert_eq!(
+            vec!["Hi there".to_owned(), "Bye".to_owned()],
+            run_lines(&mut test_base.dialogue)
+        );
+    }
+}
+
+#[test]
+fn test_line_group_cycles_through_variants_by_default() {
+    let result = Compiler::from_test_source("=> One\n=> Two\n=> Three\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    let lines: Vec<_> = (0..4)
+        .flat_map(|_| {
+            test_base.dialogue.set_node("Start").unwrap();
+            run_lines(&mut test_base.dialogue)
+        })
+        .collect();
+    assert_eq!(vec!["One", "Two", "Three", "One"], lines);
+}
+
+#[test]
+fn test_line_group_skips_variants_whose_condition_fails() {
+    let result = Compiler::from_test_source(
+        "<<declare $met = false>>\n\
+        <<declare $armed = true>>\n\
+        => Who are you? <<if not $met>>\n\
+        => Good to see you again. <<if $met>>\n\
+        => Put that sword away, friend. <<if $met and $armed>>\n",
+    )
+    .compile()
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base
+        .dialogue
+        .set_saliency_strategy(BestSaliencyStrategy);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(vec!["Who are you?"], run_lines(&mut test_base.dialogue));
+
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .set("$met".to_owned(), true.into())
+        .unwrap();
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Put that sword away, friend."],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_line_group_without_passing_variants_runs_nothing() {
+    let result = Compiler::from_test_source("=> Never <<if false>>\n=> Nope <<if false>>\nAfter\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(vec!["After"], run_lines(&mut test_base.dialogue));
+}
+
+#[test]
+fn test_random_strategy_only_runs_variants_of_the_group() {
+    let result = Compiler::from_test_source("=> One\n=> Two\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    let strategy = RandomSaliencyStrategy::new(test_base.dialogue.rng().clone());
+    test_base.dialogue.set_saliency_strategy(strategy);
+
+    for _ in 0..5 {
+        test_base.dialogue.set_node("Start").unwrap();
+        let lines = run_lines(&mut test_base.dialogue);
+        assert_eq!(1, lines.len());
+        assert!(["One", "Two"].contains(&lines[0].as_str()));
+    }
+}
+
+#[test]
+fn test_random_strategy_repeats_selections_after_restoring_snapshot() {
+    let result = Compiler::from_test_source("=> One\n=> Two\n=> Three\n=> Four\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.rng_mut().set_seed(7);
+    let strategy = RandomSaliencyStrategy::new(test_base.dialogue.rng().clone());
+    test_base.dialogue.set_saliency_strategy(strategy);
+    test_base.dialogue.set_node("Start").unwrap();
+    let snapshot = test_base.dialogue.snapshot().unwrap();
+
+    let run_group = |dialogue: &mut Dialogue| {
+        (0..8)
+            .flat_map(|_| {
+                dialogue.set_node("Start").unwrap();
+                run_lines(dialogue)
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = run_group(&mut test_base.dialogue);
+    test_base.dialogue.restore(snapshot).unwrap();
+    assert_eq!(expected, run_group(&mut test_base.dialogue));
+}
@@ -0,0 +1,71 @@
+//! Tests for line hints that follow jumps and options into other nodes with [`Dialogue::set_line_hints_lookahead`].
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_line_hints_follow_jumps_and_options() {
+    let source = [
+        create_test_node_with_name(
+            "
+            Hello! #line:hello
+            -> Visit the shop #line:visit
+                <<detour Shop>>
+            -> Leave #line:leave
+            <<jump Goodbye>>
+            ",
+            "Start",
+        ),
+        create_test_node_with_name("Welcome to the shop. #line:shop", "Shop"),
+        create_test_node_with_name(
+            "
+            Goodbye! #line:goodbye
+            <<jump Epilogue>>
+            ",
+            "Goodbye",
+        ),
+        create_test_node_with_name("The end. #line:epilogue", "Epilogue"),
+    ]
+    .join("\n");
+    let result = Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source,
+        })
+        .compile()
+        .unwrap();
+    let mut dialogue = TestBase::new().with_compilation(result).dialogue;
+    dialogue
+        .set_line_hints_enabled(true)
+        .set_line_hints_lookahead(1)
+        .set_node("Start")
+        .unwrap();
+
+    let line_hints = dialogue.pop_line_hints().unwrap();
+    let expected: Vec<_> = [
+        "line:hello",
+        "line:visit",
+        "line:leave",
+        "line:shop",
+        "line:goodbye",
+    ]
+    .map(LineId::from)
+    .to_vec();
+    assert_eq!(expected, line_hints);
+
+    // Entering a node hints at the nodes reachable from it in turn
+    while !continue_(&mut dialogue)
+        .iter()
+        .any(|event| matches!(event, DialogueEvent::Options(_)))
+    {}
+    dialogue.set_selected_option(OptionId(1)).unwrap();
+    let events = continue_(&mut dialogue);
+    assert!(events.contains(&DialogueEvent::LineHints(vec![
+        LineId::from("line:goodbye"),
+        LineId::from("line:epilogue"),
+    ])));
+}
@@ -0,0 +1,109 @@
+//! Tests for `<<local>>` variables, which only exist until their node is left, and the scopes of [`LayeredVariableStorage`].
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_local_variable_shadows_stored_variable_until_node_is_left() {
+    let source = "title: Start
+---
+<<declare $name = \"global\">>
+<<local $name = \"local\">>
+{$name}
+<<set $name to \"changed\">>
+{$name}
+<<detour Other>>
+{$name}
+===
+title: Other
+---
+{$name}
+===
+";
+    let test_base = TestBase::new().with_test_plan(
+        TestPlan::new()
+            .expect_line("local")
+            .expect_line("changed")
+            .expect_line("global")
+            .expect_line("changed"),
+    );
+    let result = compile(source).unwrap();
+
+    test_base.with_compilation(result).run_standard_testcase();
+}
+
+#[test]
+fn test_layered_storage_knows_scope_of_local_variables() {
+    let source = "title: Start
+---
+<<local $count = 1>>
+<<pause>>
+<<set $count += 1>>
+<<pause>>
+<<set $gold to 10>>
+===
+";
+    let result = compile(source).unwrap();
+    let storage = LayeredVariableStorage::default();
+    let mut dialogue = Dialogue::new(
+        Box::new(storage.clone()),
+        Box::new(StringTableTextProvider::new()),
+    );
+    dialogue.replace_program(result.program.unwrap());
+    dialogue.set_node("Start").unwrap();
+
+    continue_(&mut dialogue);
+    assert_eq!(Some(VariableScope::Local), storage.scope("$count"));
+    assert_eq!(YarnValue::from(1), storage.get("$count").unwrap());
+
+    continue_(&mut dialogue);
+    assert_eq!(Some(VariableScope::Local), storage.scope("$count"));
+    assert_eq!(YarnValue::from(2), storage.get("$count").unwrap());
+
+    continue_(&mut dialogue);
+    assert!(!dialogue.is_active());
+    assert_eq!(None, storage.scope("$count"));
+    assert_eq!(Some(VariableScope::Global), storage.scope("$gold"));
+}
+
+#[test]
+fn test_conversation_variables_are_cleared_when_dialogue_stops() {
+    let result = compile("title: Start\n---\n<<set $met to true>>\n===\n").unwrap();
+    let mut storage = LayeredVariableStorage::default();
+    storage
+        .set_in_scope("$met".to_owned(), false.into(), VariableScope::Conversation)
+        .unwrap();
+    let mut dialogue = Dialogue::new(
+        Box::new(storage.clone()),
+        Box::new(StringTableTextProvider::new()),
+    );
+    dialogue.replace_program(result.program.unwrap());
+    dialogue.set_node("Start").unwrap();
+
+    let events = continue_(&mut dialogue);
+    assert!(events.contains(&DialogueEvent::DialogueComplete));
+    assert!(!storage.contains("$met"));
+}
+
+#[test]
+fn test_local_variable_must_be_assigned() {
+    let result = compile("title: Start\n---\n<<local $count += 1>>\n===\n");
+
+    let diagnostics = result.unwrap_err().0;
+    assert!(diagnostics.iter().any(|diagnostic| {
+        diagnostic.message == "Local variable $count must be assigned a value with `=` or `to`"
+    }));
+}
+
+fn compile(source: &str) -> yarnspinner::compiler::Result<Compilation> {
+    Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+}
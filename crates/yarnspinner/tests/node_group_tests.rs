@@ -0,0 +1,276 @@
+//! Tests for node groups, i.e. nodes sharing a title that are chosen by their `when:` headers.
+
+#[cfg(feature = "bevy")]
+use bevy::prelude::World;
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+const GREETINGS: &str = "title: Start
+---
+<<declare $met_player = false>>
+===
+title: Greeting
+when: not $met_player
+---
+Nice to meet you.
+===
+title: Greeting
+when: $met_player
+---
+Welcome back.
+===
+title: Greeting
+when: once
+---
+Have we met before?
+===
+title: Greeting
+when: always
+---
+Hello.
+===
+";
+
+#[test]
+fn test_node_group_runs_node_whose_condition_passes() {
+    let result = compile(GREETINGS).unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base
+        .dialogue
+        .set_saliency_strategy(BestSaliencyStrategy);
+
+    test_base.dialogue.set_node("Greeting").unwrap();
+    assert_eq!(
+        vec!["Nice to meet you."],
+        run_lines(&mut test_base.dialogue)
+    );
+
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .set("$met_player".to_owned(), true.into())
+        .unwrap();
+    test_base.dialogue.set_node("Greeting").unwrap();
+    assert_eq!(vec!["Welcome back."], run_lines(&mut test_base.dialogue));
+}
+
+#[test]
+fn test_node_group_with_once_condition_runs_node_only_once() {
+    let source = "title: Greeting
+when: once
+---
+Have we met before?
+===
+title: Greeting
+when: always
+---
+Hello.
+===
+";
+    let result = compile(source).unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base
+        .dialogue
+        .set_saliency_strategy(FirstSaliencyStrategy);
+
+    test_base.dialogue.set_node("Greeting").unwrap();
+    assert_eq!(
+        vec!["Have we met before?"],
+        run_lines(&mut test_base.dialogue)
+    );
+    test_base.dialogue.set_node("Greeting").unwrap();
+    assert_eq!(vec!["Hello."], run_lines(&mut test_base.dialogue));
+}
+
+#[test]
+fn test_node_group_candidate_count_reflects_conditions() {
+    let result = compile(GREETINGS).unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    assert!(test_base.dialogue.is_node_group("Greeting"));
+    assert!(!test_base.dialogue.is_node_group("Start"));
+    assert_eq!(3, candidate_count(&mut test_base.dialogue, "Greeting"));
+
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .set("$met_player".to_owned(), true.into())
+        .unwrap();
+    assert_eq!(3, candidate_count(&mut test_base.dialogue, "Greeting"));
+
+    // Querying the candidates must not run the `once` node
+    test_base
+        .dialogue
+        .set_saliency_strategy(FirstSaliencyStrategy);
+    test_base.dialogue.set_node("Greeting").unwrap();
+    assert_eq!(vec!["Welcome back."], run_lines(&mut test_base.dialogue));
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .set("$met_player".to_owned(), false.into())
+        .unwrap();
+    assert_eq!(3, candidate_count(&mut test_base.dialogue, "Greeting"));
+
+    #[cfg(feature = "bevy")]
+    let result = test_base
+        .dialogue
+        .saliency_candidates_for_node_group_with_world("Start", &mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let result = test_base
+        .dialogue
+        .saliency_candidates_for_node_group("Start");
+    assert!(matches!(result, Err(DialogueError::NotANodeGroup { .. })));
+}
+
+#[test]
+fn test_node_group_candidates_do_not_advance_random_state() {
+    let source = "title: Greeting
+when: dice(6) > 3
+---
+Lucky you.
+===
+title: Greeting
+when: always
+---
+Hello.
+===
+";
+    let result = compile(source).unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.rng_mut().set_seed(42);
+    let random_state = test_base.dialogue.rng().state();
+
+    candidate_count(&mut test_base.dialogue, "Greeting");
+    assert_eq!(random_state, test_base.dialogue.rng().state());
+}
+
+#[test]
+fn test_node_group_member_names_survive_edits_above_the_group() {
+    let edited = format!("title: Intro\n---\nA new node above the group.\n===\n\n{GREETINGS}");
+    assert_eq!(member_names(GREETINGS), member_names(&edited));
+}
+
+#[test]
+fn test_node_group_members_without_when_header_are_an_error() {
+    let source = "title: Greeting
+when: always
+---
+Hello.
+===
+title: Greeting
+---
+Hi.
+===
+";
+    let error = compile(source).unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("All nodes in the group 'Greeting' must have a 'when' header")
+    }));
+}
+
+#[test]
+fn test_node_group_condition_must_be_boolean() {
+    let source = "title: Greeting
+when: 1 + 1
+---
+Hello.
+===
+";
+    let error = compile(source).unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("Error in the 'when' header of a node in the group 'Greeting'")
+    }));
+}
+
+#[test]
+fn test_node_group_condition_may_contain_command_end_in_string() {
+    let source = r#"title: Start
+---
+<<declare $password = "a>>b">>
+===
+title: Greeting
+when: $password == "a>>b"
+---
+Welcome in.
+===
+title: Greeting
+when: always
+---
+Hello.
+===
+"#;
+    let result = compile(source).unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base
+        .dialogue
+        .set_saliency_strategy(BestSaliencyStrategy);
+
+    test_base.dialogue.set_node("Greeting").unwrap();
+    assert_eq!(vec!["Welcome in."], run_lines(&mut test_base.dialogue));
+
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .set("$password".to_owned(), "a".into())
+        .unwrap();
+    test_base.dialogue.set_node("Greeting").unwrap();
+    assert_eq!(vec!["Hello."], run_lines(&mut test_base.dialogue));
+}
+
+#[test]
+fn test_node_group_condition_must_be_a_single_expression() {
+    let source = "title: Greeting
+when: true >> false
+---
+Hello.
+===
+";
+    let error = compile(source).unwrap_err();
+    let diagnostic = error
+        .0
+        .iter()
+        .find(|diagnostic| {
+            diagnostic
+                .message
+                .contains("Unexpected '>>' after the expression")
+        })
+        .unwrap();
+    assert_eq!(1, diagnostic.range.as_ref().unwrap().start.line);
+    assert_eq!(11, diagnostic.range.as_ref().unwrap().start.character);
+}
+
+fn compile(source: &str) -> yarnspinner::compiler::Result<Compilation> {
+    Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+}
+
+fn member_names(source: &str) -> Vec<String> {
+    let program = compile(source).unwrap().program.unwrap();
+    let names: Vec<_> = program
+        .nodes
+        .into_keys()
+        .filter(|name| name.starts_with("Greeting."))
+        .collect();
+    assert_eq!(4, names.len());
+    names
+}
+
+fn candidate_count(dialogue: &mut Dialogue, node_group_name: &str) -> usize {
+    #[cfg(feature = "bevy")]
+    let count =
+        dialogue.node_group_candidate_count_with_world(node_group_name, &mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let count = dialogue.node_group_candidate_count(node_group_name);
+    count.unwrap_or_else(|e| panic!("Failed to count the candidates of {node_group_name}: {e}"))
+}
@@ -0,0 +1,252 @@
+//! Tests for `<<once>>` blocks and for lines and options marked with `<<once>>`.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_once_block_runs_only_once() {
+    let result = Compiler::from_test_source("<<once>>\n    First time\n<<endonce>>\nEvery time\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["First time".to_owned(), "Every time".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Every time".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_line_with_once_condition_runs_only_once() {
+    let result = Compiler::from_test_source("Hello there <<once>>\nGoodbye\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Hello there".to_owned(), "Goodbye".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Goodbye".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_line_with_if_condition_is_skipped_when_false() {
+    let source = "
+            <<declare $met = false>>
+            Nice to meet you. <<if not $met>>
+            Welcome back. <<if $met>>
+            <<set $met to true>>
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Nice to meet you.".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Welcome back.".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_once_block_with_else_runs_else_after_first_time() {
+    let source = "
+            <<declare $friendly = true>>
+            <<once>>
+                Nice to meet you.
+            <<else>>
+                <<if $friendly>>
+                    Welcome back.
+                <<else>>
+                    You again?
+                <<endif>>
+            <<endonce>>
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Nice to meet you.".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+    for _ in 0..2 {
+        test_base.dialogue.set_node("Start").unwrap();
+        assert_eq!(
+            vec!["Welcome back.".to_owned()],
+            run_lines(&mut test_base.dialogue)
+        );
+    }
+}
+
+#[test]
+fn test_option_with_once_condition_is_unavailable_after_selection() {
+    let result = Compiler::from_test_source("-> Ask once <<once>>\n-> Leave\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    let options = next_options(&mut test_base.dialogue);
+    assert!(options.iter().all(|option| option.is_available));
+    test_base.dialogue.set_selected_option(OptionId(0)).unwrap();
+    run_lines(&mut test_base.dialogue);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    let options = next_options(&mut test_base.dialogue);
+    assert_eq!("Ask once", options[0].line.text);
+    assert!(!options[0].is_available);
+    assert!(options[1].is_available);
+}
+
+#[test]
+fn test_once_block_keeps_its_state_when_blocks_are_added_before_it() {
+    let old_source = "<<once>>\n    A secret #line:secret\n<<endonce>>\n";
+    let new_source = "<<once>>\n    A new secret #line:new_secret\n<<endonce>>\n<<once>>\n    A secret #line:secret\n<<endonce>>\n";
+    let mut test_base =
+        TestBase::new().with_compilation(Compiler::from_test_source(old_source).compile().unwrap());
+    test_base.dialogue.set_node("Start").unwrap();
+    run_lines(&mut test_base.dialogue);
+    let saved_variables = test_base.dialogue.variable_storage().variables();
+
+    let mut test_base =
+        TestBase::new().with_compilation(Compiler::from_test_source(new_source).compile().unwrap());
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .extend(saved_variables)
+        .unwrap();
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["A new secret".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_once_blocks_without_lines_are_tracked_separately() {
+    let source = "
+            <<declare $gold = 0>>
+            <<once>>
+                <<set $gold += 1>>
+            <<endonce>>
+            <<once>>
+                <<set $gold += 1>>
+            <<endonce>>
+            <<once>>
+                <<once>>
+                    Nested
+                <<endonce>>
+            <<endonce>>
+            You have {$gold} gold.
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Nested".to_owned(), "You have 2 gold.".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["You have 2 gold.".to_owned()],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_unclosed_once_block_is_an_error() {
+    let result = Compiler::from_test_source("<<once>>\n    Never closed\n").compile();
+    let error = result.unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("Expected an <<endonce>> to match the <<once>> statement")
+    }));
+}
+
+#[test]
+fn test_endonce_without_once_is_an_error() {
+    let result = Compiler::from_test_source("Hello\n<<endonce>>\n").compile();
+    let error = result.unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("Found an <<endonce>> without a matching <<once>> statement")
+    }));
+}
+
+#[test]
+fn test_once_block_with_two_elses_is_an_error() {
+    let source = "<<once>>\n    A\n<<else>>\n    B\n<<else>>\n    C\n<<endonce>>\n";
+    let error = Compiler::from_test_source(source).compile().unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("A <<once>> block can only have one <<else>>")
+    }));
+}
+
+#[test]
+fn test_once_with_condition_is_an_error() {
+    let source = "
+            <<declare $met = false>>
+            <<once if $met>>
+                Welcome back.
+            <<endonce>>
+            Hello there <<once if $met>>
+            ";
+    let error = Compiler::from_test_source(source).compile().unwrap_err();
+    let messages: Vec<_> = error
+        .0
+        .iter()
+        .map(|diagnostic| diagnostic.message.as_str())
+        .collect();
+    assert_eq!(
+        vec![
+            "<<once if>> is not supported as a line condition, put the line inside an <<if>> statement and mark it with <<once>> instead",
+            "<<once if>> is not supported, use an <<if>> statement inside the <<once>> block instead",
+        ],
+        messages
+    );
+}
+
+/// Runs the dialogue until it presents options and returns them.
+fn next_options(dialogue: &mut Dialogue) -> Vec<DialogueOption> {
+    loop {
+        let events = continue_(dialogue);
+        let options = events.into_iter().find_map(|event| match event {
+            DialogueEvent::Options(options) => Some(options),
+            _ => None,
+        });
+        if let Some(options) = options {
+            return options;
+        }
+        assert!(
+            dialogue.is_active(),
+            "Dialogue stopped without presenting options"
+        );
+    }
+}
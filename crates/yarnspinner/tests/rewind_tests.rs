@@ -0,0 +1,218 @@
+//! Tests for [`Dialogue::rewind`], which returns to an earlier line and undoes the variable changes since then.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_rewinding_restores_variables_and_delivers_line_again() {
+    let source = "
+            <<declare $gold = 100>>
+            First line
+            <<set $gold to 50>>
+            Second line
+            <<set $gold to 0>>
+            Third line
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_rewind_depth(10);
+    test_base.dialogue.set_node("Start").unwrap();
+    for _ in 0..3 {
+        continue_(&mut test_base.dialogue);
+    }
+    assert_eq!(3, test_base.dialogue.rewindable_lines().len());
+
+    let events = test_base.dialogue.rewind(1).unwrap();
+    assert_eq!(2, events.len());
+    assert_eq!(
+        DialogueEvent::VariableChanged {
+            name: "$gold".to_owned(),
+            old: Some(YarnValue::from(0)),
+            new: YarnValue::from(50),
+        },
+        events[0]
+    );
+    assert_eq!(Some("Second line"), line_text(&events[1]));
+    assert_eq!(
+        YarnValue::from(50),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+    assert_eq!(2, test_base.dialogue.rewindable_lines().len());
+
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(Some("Third line"), events.iter().find_map(line_text));
+    assert_eq!(
+        YarnValue::from(0),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+}
+
+#[test]
+fn test_rewinding_returns_to_line_before_options() {
+    let source = "
+            Pick one.
+            -> A
+            -> B
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_rewind_depth(1);
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+    continue_(&mut test_base.dialogue);
+    test_base.dialogue.set_selected_option(OptionId(0)).unwrap();
+    continue_(&mut test_base.dialogue);
+    assert!(!test_base.dialogue.is_active());
+
+    let events = test_base.dialogue.rewind(0).unwrap();
+    assert_eq!(1, events.len());
+    assert_eq!(Some("Pick one."), line_text(&events[0]));
+    let events = continue_(&mut test_base.dialogue);
+    assert!(matches!(events.last(), Some(DialogueEvent::Options(options)) if options.len() == 2));
+}
+
+#[test]
+fn test_rewinding_is_limited_by_depth() {
+    let source = "
+            First line
+            Second line
+            Third line
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_rewind_depth(2);
+    test_base.dialogue.set_node("Start").unwrap();
+    for _ in 0..3 {
+        continue_(&mut test_base.dialogue);
+    }
+
+    let error = test_base.dialogue.rewind(2).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::RewindOutOfRange {
+            steps: 2,
+            recorded_lines: 2
+        }
+    ));
+    let lines: Vec<_> = test_base
+        .dialogue
+        .rewindable_lines()
+        .map(|line| line.text.as_str())
+        .collect();
+    assert_eq!(vec!["Second line", "Third line"], lines);
+}
+
+#[test]
+fn test_rewinding_across_jump() {
+    let source = "title: Start
+---
+<<declare $gold = 100>>
+In the start node.
+<<set $gold to 50>>
+<<jump End>>
+===
+title: End
+---
+In the end node.
+===
+";
+    let mut test_base = TestBase::new().with_compilation(compile(source));
+    test_base.dialogue.set_rewind_depth(10);
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+    continue_(&mut test_base.dialogue);
+    assert_eq!(2, test_base.dialogue.rewindable_lines().len());
+
+    let events = test_base.dialogue.rewind(1).unwrap();
+    assert_eq!(
+        Some("In the start node."),
+        events.iter().find_map(line_text)
+    );
+    assert_eq!(Some("Start".to_owned()), test_base.dialogue.current_node());
+    assert_eq!(
+        YarnValue::from(100),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(Some("In the end node."), events.iter().find_map(line_text));
+}
+
+#[test]
+fn test_rewinding_across_detour_and_return() {
+    let source = "title: Start
+---
+Before the detour.
+<<detour Shop>>
+After the detour.
+===
+title: Shop
+---
+In the shop.
+===
+";
+    let mut test_base = TestBase::new().with_compilation(compile(source));
+    test_base.dialogue.set_rewind_depth(10);
+    test_base.dialogue.set_node("Start").unwrap();
+    for _ in 0..3 {
+        continue_(&mut test_base.dialogue);
+    }
+    assert_eq!(3, test_base.dialogue.rewindable_lines().len());
+
+    // Back into the detour, which still returns to the node that detoured to it
+    let events = test_base.dialogue.rewind(1).unwrap();
+    assert_eq!(Some("In the shop."), events.iter().find_map(line_text));
+    assert_eq!(Some("Shop".to_owned()), test_base.dialogue.current_node());
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(Some("After the detour."), events.iter().find_map(line_text));
+
+    // Back before the detour
+    let events = test_base.dialogue.rewind(2).unwrap();
+    assert_eq!(
+        Some("Before the detour."),
+        events.iter().find_map(line_text)
+    );
+    let events = continue_(&mut test_base.dialogue);
+    assert_eq!(Some("In the shop."), events.iter().find_map(line_text));
+}
+
+#[test]
+fn test_setting_node_forgets_rewindable_lines() {
+    let source = "
+            First line
+            Second line
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_rewind_depth(10);
+    test_base.dialogue.set_node("Start").unwrap();
+    continue_(&mut test_base.dialogue);
+    assert_eq!(1, test_base.dialogue.rewindable_lines().len());
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(0, test_base.dialogue.rewindable_lines().len());
+    continue_(&mut test_base.dialogue);
+    test_base.dialogue.stop();
+    assert_eq!(0, test_base.dialogue.rewindable_lines().len());
+}
+
+fn compile(source: &str) -> Compilation {
+    Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+        .unwrap()
+}
+
+fn line_text(event: &DialogueEvent) -> Option<&str> {
+    match event {
+        DialogueEvent::Line(line) => Some(line.text.as_str()),
+        _ => None,
+    }
+}
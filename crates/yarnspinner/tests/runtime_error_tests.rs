@@ -0,0 +1,206 @@
+//! Tests for errors while running dialogue that throw exceptions or hang in the original implementation at the commit we are porting from.
+
+#[cfg(feature = "bevy")]
+use bevy::prelude::World;
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_stack_underflow_is_an_error() {
+    let mut program = compile("title: Start\n---\n<<jump Other>>\n===\ntitle: Other\n---\n===\n");
+    // Remove the instruction that pushes the destination of the jump
+    program
+        .nodes
+        .get_mut("Start")
+        .unwrap()
+        .instructions
+        .remove(0);
+    let mut dialogue = dialogue(program);
+
+    let error = continue_(&mut dialogue).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::StackUnderflow {
+            ref node_name,
+            instruction_index: 0,
+        } if node_name == "Start"
+    ));
+}
+
+#[test]
+fn test_missing_operand_is_an_error() {
+    let mut program = compile("title: Start\n---\nHello #line:hello\n===\n");
+    let instruction = run_line_instruction(&mut program);
+    instruction.operands.truncate(1);
+    let mut dialogue = dialogue(program);
+
+    let error = continue_(&mut dialogue).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::InvalidOperand {
+            ref node_name,
+            instruction_index: 0,
+            operand_index: 1,
+        } if node_name == "Start"
+    ));
+}
+
+#[test]
+fn test_unknown_opcode_is_an_error() {
+    let mut program = compile("title: Start\n---\nHello #line:hello\n===\n");
+    let instruction = run_line_instruction(&mut program);
+    instruction.opcode = 1000;
+    let mut dialogue = dialogue(program);
+
+    let error = continue_(&mut dialogue).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::UnknownOpCode {
+            ref node_name,
+            instruction_index: 0,
+            opcode: 1000,
+        } if node_name == "Start"
+    ));
+}
+
+#[test]
+fn test_failed_function_argument_conversion_is_an_error() {
+    let program = compile("title: Start\n---\n{double(\"not a number\")}\n===\n");
+    let mut dialogue = dialogue(program);
+    dialogue
+        .library_mut()
+        .add_function("double", |value: f32| value * 2.0);
+
+    let error = continue_(&mut dialogue).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::TypeConversionFailed { ref node_name, .. } if node_name == "Start"
+    ));
+    assert!(error.to_string().contains("double"));
+}
+
+#[test]
+fn test_failed_function_is_an_error() {
+    let program = compile("title: Start\n---\n{number(\"not a number\")}\n===\n");
+    let mut dialogue = dialogue(program);
+
+    let error = continue_(&mut dialogue).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::FunctionFailed {
+            ref node_name,
+            ref function_name,
+            ..
+        } if node_name == "Start" && function_name == "number"
+    ));
+}
+
+#[test]
+fn test_rounding_to_negative_places_is_an_error() {
+    let program = compile("title: Start\n---\n{round_places(1.5, -1)}\n===\n");
+    let mut dialogue = dialogue(program);
+
+    let error = continue_(&mut dialogue).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::FunctionFailed { ref function_name, .. } if function_name == "round_places"
+    ));
+}
+
+#[test]
+fn test_rolling_a_die_without_sides_is_an_error() {
+    for sides in ["0", "-2", "0.5"] {
+        let program = compile(&format!("title: Start\n---\n{{dice({sides})}}\n===\n"));
+        let mut dialogue = dialogue(program);
+
+        let error = continue_(&mut dialogue).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                DialogueError::FunctionFailed { ref function_name, .. } if function_name == "dice"
+            ),
+            "Rolled a die with {sides} sides"
+        );
+    }
+}
+
+#[test]
+fn test_instruction_budget_reports_jump_cycle() {
+    let program = compile(
+        "title: Start\n---\n<<greet>>\n<<jump Ping>>\n===\n\
+        title: Ping\n---\n<<jump Pong>>\n===\n\
+        title: Pong\n---\n<<jump Ping>>\n===\n",
+    );
+    let mut dialogue = dialogue(program);
+    dialogue.set_instruction_budget(100);
+
+    // Pausing for a command doesn't exceed the budget
+    continue_(&mut dialogue).unwrap();
+    let error = continue_(&mut dialogue).unwrap_err();
+    let DialogueError::InstructionBudgetExceeded {
+        node_name,
+        instruction_budget,
+        mut jump_cycle,
+    } = error
+    else {
+        panic!("Expected the instruction budget to be exceeded, but got: {error}");
+    };
+    assert_eq!(100, instruction_budget);
+    assert_eq!(Some(&node_name), jump_cycle.last());
+    jump_cycle.sort();
+    assert_eq!(vec!["Ping", "Pong"], jump_cycle);
+}
+
+#[test]
+fn test_instruction_budget_reports_loop_within_node() {
+    let program = compile("title: Start\n---\n<<jump Start>>\n===\n");
+    let mut dialogue = dialogue(program);
+    dialogue.set_instruction_budget(100);
+
+    let error = continue_(&mut dialogue).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::InstructionBudgetExceeded { ref jump_cycle, .. } if jump_cycle == &["Start"]
+    ));
+}
+
+fn compile(source: &str) -> Program {
+    Compiler::new()
+        .add_file(File {
+            file_name: "<input>".to_owned(),
+            source: source.to_owned(),
+        })
+        .compile()
+        .unwrap()
+        .program
+        .unwrap()
+}
+
+fn dialogue(program: Program) -> Dialogue {
+    let mut dialogue = TestBase::new().with_program(program).dialogue;
+    dialogue.set_node("Start").unwrap();
+    dialogue
+}
+
+/// Returns the first instruction of `Start`, which runs its only line.
+fn run_line_instruction(program: &mut Program) -> &mut Instruction {
+    let instruction = &mut program.nodes.get_mut("Start").unwrap().instructions[0];
+    // The line ID and the number of expressions in the line
+    assert_eq!(2, instruction.operands.len());
+    instruction
+}
+
+fn continue_(dialogue: &mut Dialogue) -> yarnspinner::runtime::Result<Vec<DialogueEvent>> {
+    #[cfg(feature = "bevy")]
+    {
+        dialogue.continue_with_world(&mut World::default())
+    }
+    #[cfg(not(feature = "bevy"))]
+    {
+        dialogue.continue_()
+    }
+}
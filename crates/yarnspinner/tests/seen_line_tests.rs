@@ -0,0 +1,36 @@
+//! Tests for recording delivered lines in a [`SeenLineTracker`].
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_tracker_counts_delivered_lines_across_runs() {
+    let source = "
+            Welcome back. #line:welcome
+            -> Stay #line:stay
+            -> Leave #line:leave
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    let tracker = SeenLineTracker::new();
+    test_base.dialogue.set_seen_line_tracker(tracker.clone());
+    test_base.dialogue.set_rewind_depth(1);
+
+    for _ in 0..2 {
+        test_base.dialogue.set_node("Start").unwrap();
+        continue_(&mut test_base.dialogue);
+        continue_(&mut test_base.dialogue);
+        test_base.dialogue.set_selected_option(OptionId(0)).unwrap();
+        continue_(&mut test_base.dialogue);
+    }
+    test_base.dialogue.rewind(0).unwrap();
+
+    assert_eq!(2, tracker.times_seen(&LineId::from("line:welcome")));
+    // Options are not lines
+    assert!(!tracker.is_seen(&LineId::from("line:stay")));
+    assert_eq!(vec![LineId::from("line:welcome")], tracker.seen_lines());
+}
@@ -0,0 +1,192 @@
+//! Tests for smart variables, i.e. variables declared with an expression that is evaluated every time they are read.
+
+#[cfg(feature = "bevy")]
+use bevy::prelude::World;
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+const SHOP: &str = "<<declare $gold = 10>>
+<<declare $price = 5>>
+<<declare $can_afford = $gold >= $price>>
+<<declare $can_afford_two = $can_afford and $gold >= $price * 2>>
+<<declare $change = $gold - $price>>
+";
+
+#[test]
+fn test_smart_variable_is_recomputed_on_every_read() {
+    let result = Compiler::from_test_source(&format!(
+        "{SHOP}\
+        <<if $can_afford>>\n\
+        Affordable\n\
+        <<endif>>\n\
+        <<set $price = 20>>\n\
+        <<if not $can_afford>>\n\
+        Too expensive\n\
+        <<endif>>\n\
+        <<if not $can_afford_two>>\n\
+        Not even one\n\
+        <<endif>>\n\
+        Missing {{$change}}\n"
+    ))
+    .compile()
+    .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    test_base.dialogue.set_node("Start").unwrap();
+    assert_eq!(
+        vec!["Affordable", "Too expensive", "Not even one", "Missing -10"],
+        run_lines(&mut test_base.dialogue)
+    );
+}
+
+#[test]
+fn test_smart_variable_is_declared_with_the_type_of_its_expression() {
+    let result = Compiler::from_test_source(SHOP).compile().unwrap();
+
+    let can_afford = result
+        .declarations
+        .iter()
+        .find(|declaration| declaration.name == "$can_afford")
+        .unwrap();
+    assert_eq!(Type::Boolean, can_afford.r#type);
+    assert!(can_afford.is_smart_variable);
+    assert_eq!(None, can_afford.default_value);
+
+    let program = result.program.unwrap();
+    assert!(!program.initial_values.contains_key("$can_afford"));
+    assert!(program.nodes["$can_afford"].is_smart_variable());
+}
+
+#[test]
+fn test_game_code_can_evaluate_smart_variables() {
+    let result = Compiler::from_test_source(SHOP).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    assert!(test_base.dialogue.is_smart_variable("$can_afford"));
+    assert!(!test_base.dialogue.is_smart_variable("$gold"));
+    assert_eq!(
+        YarnValue::from(true),
+        evaluate(&mut test_base.dialogue, "$can_afford")
+    );
+
+    test_base
+        .dialogue
+        .variable_storage_mut()
+        .set("$gold".to_owned(), 1.0.into())
+        .unwrap();
+    assert_eq!(
+        YarnValue::from(false),
+        evaluate(&mut test_base.dialogue, "$can_afford")
+    );
+    assert!(
+        test_base
+            .dialogue
+            .variable_storage()
+            .get("$can_afford")
+            .is_err()
+    );
+
+    #[cfg(feature = "bevy")]
+    let result = test_base
+        .dialogue
+        .evaluate_smart_variable_with_world("$gold", &mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let result = test_base.dialogue.evaluate_smart_variable("$gold");
+    assert!(matches!(
+        result,
+        Err(DialogueError::NotASmartVariable { .. })
+    ));
+}
+
+#[test]
+fn test_smart_variable_nodes_are_hidden() {
+    let result = Compiler::from_test_source(&format!("{SHOP}<<jump {{\"$can_afford\"}}>>\n"))
+        .compile()
+        .unwrap();
+    let program = result.program.clone().unwrap();
+    assert!(program.nodes["$can_afford"].is_hidden());
+    let mut test_base = TestBase::new().with_compilation(result);
+
+    assert_eq!(
+        vec!["Start"],
+        test_base.dialogue.node_names().unwrap().collect::<Vec<_>>()
+    );
+    assert!(!test_base.dialogue.node_exists("$can_afford"));
+    assert!(
+        test_base
+            .dialogue
+            .get_headers_for_node("$can_afford")
+            .is_none()
+    );
+    assert!(matches!(
+        test_base.dialogue.set_node("$can_afford"),
+        Err(DialogueError::InvalidNode { .. })
+    ));
+
+    test_base.dialogue.set_node("Start").unwrap();
+    #[cfg(feature = "bevy")]
+    let result = test_base
+        .dialogue
+        .continue_with_world(&mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let result = test_base.dialogue.continue_();
+    assert!(matches!(
+        result,
+        Err(DialogueError::InvalidNode { node_name }) if node_name == "$can_afford"
+    ));
+}
+
+#[test]
+fn test_programs_declaring_the_same_smart_variable_can_be_combined() {
+    let compile = |node_name: &str| {
+        Compiler::new()
+            .add_file(File {
+                file_name: format!("{node_name}.yarn"),
+                source: create_test_node_with_name(SHOP, node_name),
+            })
+            .compile()
+            .unwrap()
+            .program
+            .unwrap()
+    };
+    let program = Program::combine(vec![compile("Start"), compile("Other")])
+        .unwrap()
+        .unwrap();
+    assert!(program.nodes["$can_afford"].is_smart_variable());
+}
+
+#[test]
+fn test_smart_variables_cannot_be_set() {
+    let error = Compiler::from_test_source(&format!("{SHOP}<<set $can_afford = true>>\n"))
+        .compile()
+        .unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("$can_afford is a smart variable and cannot be modified")
+    }));
+}
+
+#[test]
+fn test_smart_variables_cannot_depend_on_themselves() {
+    let error = Compiler::from_test_source("<<declare $a = $b + 1>>\n<<declare $b = $a * 2>>\n")
+        .compile()
+        .unwrap_err();
+    assert!(error.0.iter().any(|diagnostic| {
+        diagnostic
+            .message
+            .contains("The smart variable $a cannot depend on itself")
+    }));
+}
+
+fn evaluate(dialogue: &mut Dialogue, variable_name: &str) -> YarnValue {
+    #[cfg(feature = "bevy")]
+    let value = dialogue.evaluate_smart_variable_with_world(variable_name, &mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let value = dialogue.evaluate_smart_variable(variable_name);
+    value.unwrap_or_else(|e| panic!("Failed to evaluate {variable_name}: {e}"))
+}
@@ -0,0 +1,88 @@
+//! Tests for [`Dialogue::snapshot`] and [`Dialogue::restore`], which resume a dialogue at the line, options and random state it was captured at.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_restoring_snapshot_resumes_at_same_line() {
+    let result = Compiler::from_test_source("First\nSecond\nThird\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result.clone());
+    test_base.dialogue.set_node("Start").unwrap();
+
+    assert_eq!("First", next_line(&mut test_base.dialogue).unwrap().text);
+    let snapshot = test_base.dialogue.snapshot().unwrap();
+    assert_eq!(Some("Start"), snapshot.current_node());
+    assert_eq!("Second", next_line(&mut test_base.dialogue).unwrap().text);
+
+    let mut other = TestBase::new().with_compilation(result);
+    other.dialogue.restore(snapshot).unwrap();
+    assert_eq!(Some("Start".to_owned()), other.dialogue.current_node());
+    assert_eq!("Second", next_line(&mut other.dialogue).unwrap().text);
+    assert_eq!("Third", next_line(&mut other.dialogue).unwrap().text);
+}
+
+#[test]
+fn test_restoring_snapshot_keeps_pending_options() {
+    let result = Compiler::from_test_source("-> A\n    Chose A\n-> B\n    Chose B\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result.clone());
+    test_base.dialogue.set_node("Start").unwrap();
+    next_line(&mut test_base.dialogue);
+
+    let snapshot = test_base.dialogue.snapshot().unwrap();
+    assert!(snapshot.is_waiting_for_option_selection());
+    assert_eq!(2, snapshot.current_options().len());
+
+    let mut other = TestBase::new().with_compilation(result);
+    other.dialogue.restore(snapshot).unwrap();
+    assert!(other.dialogue.is_waiting_for_option_selection());
+    other.dialogue.set_selected_option(OptionId(1)).unwrap();
+    assert_eq!("Chose B", next_line(&mut other.dialogue).unwrap().text);
+}
+
+#[test]
+fn test_restoring_snapshot_against_different_program_fails() {
+    let result = Compiler::from_test_source("First\nSecond\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+    next_line(&mut test_base.dialogue);
+    let snapshot = test_base.dialogue.snapshot().unwrap();
+
+    let other_result = Compiler::from_test_source("Something else entirely\n")
+        .compile()
+        .unwrap();
+    let mut other = TestBase::new()
+        .with_compilation(other_result)
+        .with_runtime_errors_do_not_cause_failure();
+    let error = other.dialogue.restore(snapshot).unwrap_err();
+    assert!(matches!(
+        error,
+        DialogueError::SnapshotProgramMismatch { .. }
+    ));
+}
+
+#[test]
+fn test_restoring_snapshot_repeats_random_numbers() {
+    let result = Compiler::from_test_source("{dice(1000)}\n{random_range(1, 1000)}\n")
+        .compile()
+        .unwrap();
+    let mut test_base = TestBase::new().with_compilation(result.clone());
+    test_base.dialogue.rng_mut().set_seed(42);
+    test_base.dialogue.set_node("Start").unwrap();
+    let snapshot = test_base.dialogue.snapshot().unwrap();
+    let first = next_line(&mut test_base.dialogue);
+    let second = next_line(&mut test_base.dialogue);
+
+    let mut other = TestBase::new().with_compilation(result);
+    other.dialogue.restore(snapshot).unwrap();
+    assert_eq!(first, next_line(&mut other.dialogue));
+    assert_eq!(second, next_line(&mut other.dialogue));
+}
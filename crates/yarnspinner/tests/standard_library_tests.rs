@@ -0,0 +1,81 @@
+//! Tests for the functions of [`Library::standard_library`] that the original implementation registers in `Dialogue.cs`
+//! at the commit we are porting from, plus the ones added in later versions of Yarn Spinner and those of [`Library::string_library`].
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+
+mod test_base;
+
+#[test]
+fn test_math_functions() {
+    let source = "
+            {round(1.5)} {round(-1.2)}
+            {round_places(1.2345, 2)} {round_places(1.5, 10)}
+            {floor(-1.5)} {ceil(1.2)}
+            {inc(1)} {inc(1.5)} {inc(-1.5)} {inc(-2.7)}
+            {dec(1)} {dec(1.5)} {dec(-1.5)} {dec(-2.3)}
+            {decimal(1.25)}
+            {int(-1.7)}
+            {min(1, 2)} {max(1, 2)}
+            {abs(-3)}
+            ";
+    let test_base = TestBase::new().with_test_plan(
+        TestPlan::new()
+            .expect_line("2 -1")
+            .expect_line("1.23 1.5")
+            .expect_line("-2 2")
+            .expect_line("2 2 -1 -2")
+            .expect_line("0 1 -2 -3")
+            .expect_line("0.25")
+            .expect_line("-1")
+            .expect_line("1 2")
+            .expect_line("3"),
+    );
+    let result = Compiler::from_test_source(source).compile().unwrap();
+
+    test_base.with_compilation(result).run_standard_testcase();
+}
+
+#[test]
+fn test_format_invariant() {
+    let source = "{format_invariant(1.5)} {format_invariant(2)}";
+    let test_base = TestBase::new().with_test_plan(TestPlan::new().expect_line("1.5 2"));
+    let result = Compiler::from_test_source(source).compile().unwrap();
+
+    test_base.with_compilation(result).run_standard_testcase();
+}
+
+#[test]
+fn test_string_functions() {
+    let test_base = TestBase::new().extend_library(|library| {
+        library.import(Library::string_library());
+    });
+    // "e\u{301}" is an e followed by a combining acute accent, i.e. a decomposed "é"
+    let source = "
+            {length(\"e\u{301}\")} {length(\"é\")} {length(\"\")}
+            {upper(\"abc\")} {lower(\"ABC\")}
+            {contains(\"hello\", \"ell\")} {contains(\"e\u{301}\", \"e\")}
+            {starts_with(\"hello\", \"he\")} {starts_with(\"hello\", \"lo\")}
+            {substring(\"ae\u{301}bc\", 1, 2)} {substring(\"abc\", 2, 10)}
+            {replace(\"a-b-c\", \"-\", \"+\")}
+            |{trim(\"  padded  \")}|
+            ";
+    let test_base = test_base.with_test_plan(
+        TestPlan::new()
+            .expect_line("1 1 0")
+            .expect_line("ABC abc")
+            .expect_line("true false")
+            .expect_line("true false")
+            // Lines are normalized to NFC, which composes the "é"
+            .expect_line("\u{e9}b c")
+            .expect_line("a+b+c")
+            .expect_line("|padded|"),
+    );
+    let result = Compiler::from_test_source(source)
+        .extend_library(test_base.dialogue.library().clone())
+        .compile()
+        .unwrap();
+
+    test_base.with_compilation(result).run_standard_testcase();
+}
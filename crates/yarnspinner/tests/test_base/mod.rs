@@ -45,6 +45,43 @@ pub fn init_logger(runtime_errors_cause_failure: Arc<AtomicBool>) -> Result<(),
     log::set_boxed_logger(Box::new(logger)).map(|()| log::set_max_level(LevelFilter::Info))
 }
 
+/// Continues the dialogue once and returns the events it emitted. Fails the test if the dialogue encounters an error.
+pub fn continue_(dialogue: &mut Dialogue) -> Vec<DialogueEvent> {
+    #[cfg(feature = "bevy")]
+    let events = dialogue.continue_with_world(&mut World::default());
+    #[cfg(not(feature = "bevy"))]
+    let events = dialogue.continue_();
+    events.unwrap_or_else(|e| panic!("Encountered error while running dialogue: {e}"))
+}
+
+/// Continues the dialogue until it delivers options or stops and returns the text of every line it delivered.
+pub fn run_lines(dialogue: &mut Dialogue) -> Vec<String> {
+    let mut lines = Vec::new();
+    loop {
+        let mut stopped = false;
+        for event in continue_(dialogue) {
+            match event {
+                DialogueEvent::Line(line) => lines.push(line.text),
+                DialogueEvent::Options(_) | DialogueEvent::DialogueComplete => stopped = true,
+                _ => {}
+            }
+        }
+        if stopped || !dialogue.is_active() {
+            return lines;
+        }
+    }
+}
+
+/// Continues the dialogue once and returns the line it delivered, if any.
+pub fn next_line(dialogue: &mut Dialogue) -> Option<Line> {
+    continue_(dialogue)
+        .into_iter()
+        .find_map(|event| match event {
+            DialogueEvent::Line(line) => Some(line),
+            _ => None,
+        })
+}
+
 #[derive(Debug)]
 pub struct TestBase {
     pub dialogue: Dialogue,
@@ -126,7 +163,7 @@ impl TestBase {
 
     #[must_use]
     pub fn with_program(mut self, program: Program) -> Self {
-        self.dialogue.add_program(program);
+        self.dialogue.add_program(program).unwrap();
         self
     }
 
@@ -251,6 +288,8 @@ impl TestBase {
                     DialogueEvent::NodeComplete(_) => {}
                     DialogueEvent::NodeStart(_) => {}
                     DialogueEvent::LineHints(_) => {}
+                    DialogueEvent::VariableChanged { .. } => {}
+                    DialogueEvent::HotSwapFallback { .. } => {}
                     DialogueEvent::DialogueComplete => {
                         let Some(test_plan) = self.test_plan.as_mut() else {
                             continue;
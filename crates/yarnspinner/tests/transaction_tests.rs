@@ -0,0 +1,90 @@
+//! Tests for [`Dialogue::begin_transaction`] and rolling back variable writes when a dialogue is stopped.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_stopping_rolls_back_variables_when_enabled() {
+    let source = "
+            <<declare $gold = 100>>
+            <<set $gold to 50>>
+            You paid for the sword.
+            <<set $has_sword to true>>
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_rollback_on_stop(true);
+    test_base.dialogue.set_node("Start").unwrap();
+    assert!(test_base.dialogue.is_in_transaction());
+
+    continue_(&mut test_base.dialogue);
+    assert_eq!(
+        YarnValue::from(50),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+
+    let events = test_base.dialogue.stop();
+    assert_eq!(Some(&DialogueEvent::DialogueComplete), events.last());
+    assert!(events.contains(&DialogueEvent::VariableChanged {
+        name: "$gold".to_owned(),
+        old: Some(YarnValue::from(50)),
+        new: YarnValue::from(100),
+    }));
+    assert_eq!(
+        YarnValue::from(100),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+    assert!(!test_base.dialogue.is_in_transaction());
+}
+
+#[test]
+fn test_completing_dialogue_commits_automatic_transaction() {
+    let source = "
+            <<declare $gold = 100>>
+            <<set $gold to 50>>
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_rollback_on_stop(true);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let events = continue_(&mut test_base.dialogue);
+    assert!(events.contains(&DialogueEvent::DialogueComplete));
+    assert!(!test_base.dialogue.is_in_transaction());
+
+    test_base.dialogue.stop();
+    assert_eq!(
+        YarnValue::from(50),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+}
+
+#[test]
+fn test_explicit_transaction_spans_runs() {
+    let source = "
+            <<declare $gold = 100>>
+            <<set $gold to $gold - 10>>
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.begin_transaction();
+    for _ in 0..2 {
+        test_base.dialogue.set_node("Start").unwrap();
+        continue_(&mut test_base.dialogue);
+    }
+    assert!(test_base.dialogue.is_in_transaction());
+    assert_eq!(
+        YarnValue::from(80),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+
+    test_base.dialogue.rollback_transaction().unwrap();
+    assert_eq!(
+        YarnValue::from(100),
+        test_base.variable_storage.get("$gold").unwrap()
+    );
+}
@@ -627,7 +627,7 @@ fn test_type_conversion() {
 }
 
 #[test]
-#[should_panic = "Failed to convert a Yarn value to a number: ParseFloatError(ParseFloatError { kind: Invalid })"]
+#[should_panic = "called the function \"number\", which failed: invalid float literal"]
 fn test_type_conversion_failure_to_number() {
     let source = "{number(\"hello\")}";
     let test_base =
@@ -640,7 +640,7 @@ fn test_type_conversion_failure_to_number() {
 }
 
 #[test]
-#[should_panic = "Failed to convert a Yarn value to a bool: ParseBoolError(ParseBoolError"]
+#[should_panic = "called the function \"bool\", which failed: provided string was not `true` or `false`"]
 fn test_type_conversion_failure_to_bool() {
     let source = "{bool(\"hello\")}";
     let test_base =
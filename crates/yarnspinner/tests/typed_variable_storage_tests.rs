@@ -0,0 +1,54 @@
+//! Tests for [`TypedVariableStorage`] with the variable declarations of a compilation.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_declarations_of_compilation_are_enforced() {
+    let source = "
+            <<enum Food>>
+                <<case Apple>>
+                <<case Orange>>
+            <<endenum>>
+            <<declare $gold = 10>>
+            <<declare $food = Food.Apple>>
+            <<declare $rich = $gold > 100>>
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut storage = TypedVariableStorage::new(Box::new(MemoryVariableStorage::new()));
+    for declaration in &result.declarations {
+        storage
+            .declare(
+                &declaration.name,
+                declaration.r#type.clone(),
+                declaration.default_value.clone(),
+            )
+            .unwrap();
+    }
+
+    assert!(matches!(
+        storage.declared_type("$food"),
+        Some(Type::Enum(ref enum_type)) if enum_type.name == "Food"
+    ));
+    assert!(matches!(
+        storage.set("$gold".to_owned(), "lots".into()),
+        Err(VariableStorageError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        storage.set("$food".to_owned(), 5.into()),
+        Err(VariableStorageError::TypeMismatch { .. })
+    ));
+    storage.set("$gold".to_owned(), 20.into()).unwrap();
+    storage.set("$undeclared".to_owned(), true.into()).unwrap();
+    assert_eq!(vec!["$undeclared"], storage.undeclared_variables());
+
+    storage.clear();
+    assert_eq!(YarnValue::from(10), storage.get("$gold").unwrap());
+    assert!(storage.undeclared_variables().is_empty());
+    // Smart variables are never stored
+    assert!(!storage.contains("$rich"));
+}
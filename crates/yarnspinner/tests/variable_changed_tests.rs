@@ -0,0 +1,71 @@
+//! Tests for [`DialogueEvent::VariableChanged`], which reports writes that change the value of a variable, whether they come from the script or from the game.
+
+use test_base::prelude::*;
+use yarnspinner::compiler::*;
+use yarnspinner::core::*;
+use yarnspinner::runtime::*;
+
+mod test_base;
+
+#[test]
+fn test_storing_variable_sends_change() {
+    let source = "
+            <<declare $gold = 0>>
+            <<set $gold to 10>>
+            <<set $gold to 10>>
+            <<set $gold to 15>>
+            ";
+    let result = Compiler::from_test_source(source).compile().unwrap();
+    let mut test_base = TestBase::new().with_compilation(result);
+    test_base.dialogue.set_node("Start").unwrap();
+
+    let changes = variable_changes(continue_(&mut test_base.dialogue));
+    assert_eq!(
+        vec![
+            (
+                "$gold".to_owned(),
+                Some(YarnValue::from(0)),
+                YarnValue::from(10)
+            ),
+            (
+                "$gold".to_owned(),
+                Some(YarnValue::from(10)),
+                YarnValue::from(15)
+            ),
+        ],
+        changes
+    );
+}
+
+#[test]
+fn test_setting_variable_through_dialogue_sends_change() {
+    let result = Compiler::from_test_source("<<declare $name = \"\">>\n")
+        .compile()
+        .unwrap();
+    let mut dialogue = TestBase::new().with_compilation(result).dialogue;
+
+    let events = dialogue.set_variable("$name", "Sally").unwrap();
+    assert_eq!(
+        vec![(
+            "$name".to_owned(),
+            Some(YarnValue::from("")),
+            YarnValue::from("Sally")
+        )],
+        variable_changes(events)
+    );
+    assert!(dialogue.set_variable("$name", "Sally").unwrap().is_empty());
+    assert_eq!(
+        YarnValue::from("Sally"),
+        dialogue.variable_storage().get("$name").unwrap()
+    );
+}
+
+fn variable_changes(events: Vec<DialogueEvent>) -> Vec<(String, Option<YarnValue>, YarnValue)> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            DialogueEvent::VariableChanged { name, old, new } => Some((name, old, new)),
+            _ => None,
+        })
+        .collect()
+}